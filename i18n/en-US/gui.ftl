@@ -2,11 +2,96 @@ PROGRAM_TITLE = PROGRAM_NAME
 COPYRIGHT = Copyright
 UNITS_METRIC = ⚽ Metric
 UNITS_US = 🏈 US
+X_AXIS_DISTANCE = Distance
+X_AXIS_ELAPSED_TIME = Elapsed time
+X_AXIS_CLOCK_TIME = Time of day
 ABOUT_BUTTON_LABEL = About
 ABOUT_DIALOG_COMMENT = View your run files on the desktop.
-OPEN_FILE_BUTTON_LABEL = Open a FIT file
+OPEN_FILE_BUTTON_LABEL = Open an activity file
+LIBRARY_BUTTON_LABEL = Library
+LIBRARY_WINDOW_TITLE = Activity library
+LIBRARY_CHOOSE_FOLDER_BUTTON_LABEL = Choose folder
+LIBRARY_RESCAN_BUTTON_LABEL = Rescan
+LIBRARY_NO_FOLDER = No activity folder chosen.
+LIBRARY_SCAN_PROGRESS = Reading activity files { $done }/{ $total }
+LIBRARY_COLUMN_DATE = Date
+LIBRARY_COLUMN_SPORT = Sport
+LIBRARY_COLUMN_DISTANCE = Distance
+LIBRARY_COLUMN_DURATION = Duration
+LIBRARY_COLUMN_AVG_HEART_RATE = Average heart rate
+VOLUME_BUTTON_LABEL = Training volume
+VOLUME_WINDOW_TITLE = Training volume
+VOLUME_PERIOD_WEEK = By week
+VOLUME_PERIOD_MONTH = By month
+VOLUME_PERIOD_YEAR = By year
+VOLUME_ALL_SPORTS = All sports
+VOLUME_COLUMN_PERIOD = Period
+VOLUME_COLUMN_ACTIVITIES = Activities
+VOLUME_COLUMN_DISTANCE = Distance
+VOLUME_COLUMN_TIME = Time
+VOLUME_COLUMN_ASCENT = Ascent
+VOLUME_COLUMN_PACE = Average pace
+VOLUME_TOTAL = Total
+COMPARE_BUTTON_LABEL = Compare
+COMPARE_CLEAR_BUTTON_LABEL = Clear comparison
+GRAPHS_BUTTON_LABEL = Graphs
+SMOOTHING_BUTTON_LABEL = Smoothing
+SMOOTHING_RAW = Raw
+SMOOTHING_ROLLING_MEAN = Rolling mean
+SMOOTHING_ROLLING_MEDIAN = Rolling median
+SMOOTHING_EXPONENTIAL = Exponential
+SMOOTHING_WINDOW_LABEL = Window (points)
+SMOOTHING_REMOVE_SPIKES = Remove spikes
+MAP_BUTTON_LABEL = Map
+MAP_OFFLINE_LABEL = Offline
+MAP_PREFETCH_LABEL = Download tiles for this activity
+MAP_PREFETCH_PROGRESS = Downloading tiles { $done }/{ $total }
+MAP_OPEN_MBTILES_LABEL = Open MBTiles file…
+MAP_ONLINE_LABEL = Use the online map
+MAP_SOURCE_ADD_LABEL = Add a map source…
+MAP_SOURCE_REMOVE_LABEL = Remove this map source
+MAP_SOURCE_WINDOW_TITLE = Add a map source
+MAP_SOURCE_NAME = Name
+MAP_SOURCE_URL = URL template
+MAP_SOURCE_ATTRIBUTION = Attribution
+MAP_SOURCE_MIN_ZOOM = Lowest zoom level
+MAP_SOURCE_MAX_ZOOM = Highest zoom level
+MAP_SOURCE_ADD_BUTTON = Add
+TRACK_COLOR_LABEL = Color the track by
+TRACK_COLOR_SINGLE = Single color
+TRACK_COLOR_PACE = Pace
+TRACK_COLOR_HEART_RATE_ZONE = Heart rate zone
+TRACK_COLOR_ELEVATION = Elevation
+TRACK_COLOR_GRADE = Grade
+MAP_DISTANCE_MARKERS_LABEL = Mark every kilometer or mile
+MAP_LAP_MARKER = Lap { $lap }
+MAP_NO_POSITION_DATA = No position data in this activity
+RECORDS_BUTTON_LABEL = Personal records
+RECORDS_WINDOW_TITLE = Personal records
+LOAD_BUTTON_LABEL = Training load
+LOAD_WINDOW_TITLE = Training load
+LOAD_AXIS_LABEL = hrTSS per day
+LOAD_CHRONIC = Fitness (CTL)
+LOAD_ACUTE = Fatigue (ATL)
+LOAD_FORM = Form (TSB)
+RECORDS_NONE = No activity in the library covers a best effort distance.
+EXPORT_BUTTON_LABEL = Export
+EXPORT_GPX_BUTTON_LABEL = GPX track
+EXPORT_CSV_BUTTON_LABEL = CSV time series
+EXPORT_JSON_BUTTON_LABEL = JSON summary
 MESSAGE_FILE_NOT_FOUND = File not found.
 MESSAGE_PERMISSIONS = Unknown error. Permissions problem?
+MESSAGE_NOT_A_FIT_FILE = Unable to read the file. Not a FIT, TCX or GPX file?
+MESSAGE_PREFETCH_FAILED = { $failed } of { $total } tiles could not be downloaded.
+MESSAGE_NOT_AN_MBTILES_FILE = Unable to read the MBTiles file: { $error }
+MESSAGE_NOT_AN_ACTIVITY_FILE = Unable to read the activity: { $error }. The supported formats are FIT, TCX and GPX.
+MESSAGE_BAD_MAP_SOURCE = Give a name and a URL template containing { "{z}" }, { "{x}" } and { "{y}" }.
+CLI_INVALID_ARGUMENT = Invalid argument
+CLI_USAGE = 
+    Usage: siliconsneaker2 [file]
+           siliconsneaker2 summary <file> [--units metric|us]
+           siliconsneaker2 volume [folder] [--period week|month|year] [--sport <sport>] [--units metric|us]
+           siliconsneaker2 records [folder] [--sport <sport>] [--units metric|us]
 
 TOOLTIP_TEXT_VIEW = 
     This section contains a report of lap, heart rate zones, and session summary information.
@@ -15,9 +100,63 @@ TOOLTIP_TEXT_VIEW =
 TOOLTIP_ABOUT_BUTTON = Show program credits, license, and copyright information.
 
 TOOLTIP_OPEN_BUTTON = 
-    Open a Garmin Activity Fit file.
+    Open a Garmin Activity FIT file, a Training Center (TCX) file or a GPX track.
     Please ensure you have copied the file from the watch to the file system first.
 
+TOOLTIP_LIBRARY_BUTTON = 
+    Browse the activities of a folder, for example the GARMIN/Activity folder of a mounted watch.
+    The folder is indexed once and remembered for next time.
+
+TOOLTIP_LIBRARY_CHOOSE_FOLDER_BUTTON = Choose the folder holding your activity files and index it.
+
+TOOLTIP_LIBRARY_RESCAN_BUTTON = Index new or changed files in the activity folder.
+
+TOOLTIP_LIBRARY_VIEW = 
+    Click a column heading to sort the activities.
+    Double-click an activity to open it.
+
+TOOLTIP_VOLUME_BUTTON = Chart the distance, time, ascent, activities and pace of the library by week, month or year.
+
+TOOLTIP_COMPARE_BUTTON = 
+    Open a second activity file to overlay on the graphs and map in magenta.
+    Useful to compare runs over the same course.
+
+TOOLTIP_COMPARE_CLEAR_BUTTON = Remove the comparison activity from the graphs and map.
+
+TOOLTIP_COMPARE_LOADED = Comparing with { $file }. Click to remove the comparison.
+
+TOOLTIP_GRAPHS_BUTTON = Choose which recorded measurements are graphed.
+TOOLTIP_SMOOTHING_BUTTON = Smooth the graphs or remove spikes, e.g. from GPS pace.  Raw shows the recorded values.
+TOOLTIP_SMOOTHING_WINDOW = Number of readings each smoothed value is taken over.
+TOOLTIP_SMOOTHING_REMOVE_SPIKES = Replace readings far from their neighbours with the median of the window.
+TOOLTIP_MAP_BUTTON = Map tiles: offline mode, downloading ahead of time and local MBTiles files.
+TOOLTIP_MAP_OFFLINE = Show only the map tiles cached on disk, without using the network.
+TOOLTIP_MAP_PREFETCH = Download the map tiles covering this activity so that its map can be shown offline. Only for the map sources you added, as the OpenStreetMap tile servers forbid bulk downloads.
+TOOLTIP_MAP_OPEN_MBTILES = Show the map from a local MBTiles file of raster tiles.
+TOOLTIP_MAP_ONLINE = Stop using the MBTiles file and show the online map.
+TOOLTIP_MAP_SOURCE = Choose the map the tiles are drawn from.
+TOOLTIP_MAP_SOURCE_ADD = Add a map from an XYZ tile server, such as one running on this computer.
+TOOLTIP_MAP_SOURCE_REMOVE = Remove the chosen map source you added.
+TOOLTIP_MAP_SOURCE_URL = Address of the tiles, with { "{z}" } for the zoom level and { "{x}" } and { "{y}" } for the tile column and row.
+TOOLTIP_TRACK_COLOR = Color the track on the map by pace, heart rate zone, elevation or grade, from blue for the lowest to red for the highest.
+TOOLTIP_MAP_DISTANCE_MARKERS = Place a marker on the track at every kilometer, or mile in US units.  Clicking a lap or distance marker moves the position there and highlights the lap in the summary.
+TOOLTIP_RECORDS_BUTTON = List the fastest library activity over each distance from 400 m to the marathon.
+TOOLTIP_LOAD_BUTTON = Chart the fitness, fatigue and form of the library from the heart rate training stress of each activity.
+TOOLTIP_BEST_EFFORT_DROPDOWN = Highlight the fastest stretch of the activity over a distance on the graphs and map.
+TOOLTIP_EXPORT_BUTTON = Save the loaded activity in a format other programs can read.
+
+TOOLTIP_EXPORT_GPX_BUTTON = 
+    Save the GPS track of the loaded activity as a GPX file.
+    Elevation, time, heart rate, cadence and temperature are included for use in other mapping tools.
+
+TOOLTIP_EXPORT_CSV_BUTTON = 
+    Save every recorded measurement as a CSV table for spreadsheets.
+    Values are converted to the selected unit system.
+
+TOOLTIP_EXPORT_JSON_BUTTON = 
+    Save the session, lap and heart rate zone values as a JSON document for other programs.
+    Every value is labelled with its units in the selected unit system.
+
 TOOLTIP_UNITS_DROPDOWN = 
     Select your preferred unit system.
     This will be the default next time you start the program but can be changed anytime.
@@ -29,6 +168,7 @@ TOOLTIP_POSITION_SCALE =
     Use your keyboard right and left arrows for high precision control.
 
 TOOLTIP_ZOOM_SCALE = Zoom the graphs' y-axes with this control.
+TOOLTIP_X_AXIS_DROPDOWN = Plot the graphs against distance, elapsed time or time of day.
 
 TOOLTIP_MAP_FRAME = 
     This section displays a run path based on GPS data collected by your watch.
@@ -43,9 +183,19 @@ GRAPH_CAPTION_HR = Heart rate
 GRAPH_CAPTION_CADENCE = Cadence
 GRAPH_CAPTION_ELEVATION = Elevation
 GRAPH_CAPTION_TEMP = Temperature
+GRAPH_CAPTION_POWER = Power
+GRAPH_CAPTION_VERTICAL_OSCILLATION = Vertical oscillation
+GRAPH_CAPTION_STANCE_TIME = Ground contact time
+GRAPH_CAPTION_STEP_LENGTH = Step length
+GRAPH_CAPTION_RESPIRATION_RATE = Respiration rate
+GRAPH_CAPTION_GRADE = Grade
+GRAPH_CAPTION_EFFICIENCY_FACTOR = Efficiency factor
+GRAPH_OVERLAY_GAP = GAP
 
 LABEL_DISTANCE_MILES = Distance (miles)
 LABEL_DISTANCE_KM = Distance (km)
+LABEL_ELAPSED_TIME = Elapsed time (h:mm:ss)
+LABEL_CLOCK_TIME = Time of day (h:mm:ss)
 LABEL_PACE_US = Pace (min/mile)
 LABEL_PACE_METRIC = Pace (min/km)
 LABEL_HR_BPM = Heart rate (bpm)
@@ -60,6 +210,35 @@ SUMMARY_SESSION_HEADER = ============================ Session ==================
 SUMMARY_LAP_HEADER = Lap
 SUMMARY_HR_ZONE_HEADER = =================== Time in Heart Rate Zones for Session  ========
 SUMMARY_HR_ZONE_LABEL = Zone
+SUMMARY_PACE_ZONE_HEADER = =================== Time in Pace Zones for Session  =============
+PACE_ZONE_SLOWER = Slower
+PACE_ZONE_EASY = Easy
+PACE_ZONE_MARATHON = Marathon
+PACE_ZONE_THRESHOLD = Threshold
+PACE_ZONE_INTERVAL = Interval
+PACE_ZONE_REPETITION = Repetition
+SUMMARY_SPLITS_HEADER = ============================ Splits ===================================
+SPLITS_COLUMN_SPLIT = Split
+SPLITS_COLUMN_DISTANCE = Distance
+SPLITS_COLUMN_TIME = Time
+SPLITS_COLUMN_PACE = Pace
+SPLITS_COLUMN_HEART_RATE = HR
+SPLITS_COLUMN_CADENCE = Cadence
+SPLITS_COLUMN_ASCENT = Gain
+SPLITS_COLUMN_DESCENT = Loss
+SUMMARY_BEST_EFFORTS_HEADER = ============================ Best efforts ==============================
+BEST_EFFORT_NONE = No best effort
+BEST_EFFORT_400M = 400 m
+BEST_EFFORT_1K = 1 km
+BEST_EFFORT_MILE = 1 mile
+BEST_EFFORT_5K = 5 km
+BEST_EFFORT_10K = 10 km
+BEST_EFFORT_HALF_MARATHON = Half marathon
+BEST_EFFORT_MARATHON = Marathon
+SUMMARY_DECOUPLING_HEADER = ============================ Aerobic decoupling ========================
+DECOUPLING_FIRST_HALF = Efficiency factor, first half
+DECOUPLING_SECOND_HALF = Efficiency factor, second half
+DECOUPLING_PERCENT = Decoupling (pace:HR)
 
 UNIT_FEET = feet
 UNIT_METERS = meters
@@ -94,6 +273,9 @@ PRETTY_TOTAL_DISTANCE = Total distance
 PRETTY_TOTAL_ELAPSED_TIME = Total elapsed time
 PRETTY_TOTAL_TIMER_TIME =  Total timer time
 PRETTY_ENHANCED_AVG_SPEED = Average pace
+PRETTY_AVG_GRADE_ADJUSTED_PACE = Average grade adjusted pace
+PRETTY_TRIMP = Training impulse (TRIMP)
+PRETTY_HR_TSS = Heart rate stress (hrTSS)
 PRETTY_ENHANCED_MAX_SPEED =  Fastest pace
 PRETTY_MIN_TEMPERATURE = Minimum temperature
 PRETTY_MAX_TEMPERATURE = Maximum temperature
@@ -2,11 +2,96 @@ PROGRAM_TITLE = PROGRAM_NAME
 COPYRIGHT = Derechos de autor
 UNITS_METRIC = ⚽ Métrico
 UNITS_US = 🏈 EE. UU.
+X_AXIS_DISTANCE = Distancia
+X_AXIS_ELAPSED_TIME = Tiempo transcurrido
+X_AXIS_CLOCK_TIME = Hora del día
 ABOUT_BUTTON_LABEL = Acerca de
 ABOUT_DIALOG_COMMENT = Visualiza tus archivos de entrenamiento de carrera en tu ordenador.
-OPEN_FILE_BUTTON_LABEL = Abrir un archivo FIT
+OPEN_FILE_BUTTON_LABEL = Abrir un archivo de actividad
+LIBRARY_BUTTON_LABEL = Biblioteca
+LIBRARY_WINDOW_TITLE = Biblioteca de actividades
+LIBRARY_CHOOSE_FOLDER_BUTTON_LABEL = Elegir carpeta
+LIBRARY_RESCAN_BUTTON_LABEL = Reindexar
+LIBRARY_NO_FOLDER = No se ha elegido ninguna carpeta de actividades.
+LIBRARY_SCAN_PROGRESS = Leyendo los archivos de actividad { $done }/{ $total }
+LIBRARY_COLUMN_DATE = Fecha
+LIBRARY_COLUMN_SPORT = Deporte
+LIBRARY_COLUMN_DISTANCE = Distancia
+LIBRARY_COLUMN_DURATION = Duración
+LIBRARY_COLUMN_AVG_HEART_RATE = Frecuencia cardíaca media
+VOLUME_BUTTON_LABEL = Volumen de entrenamiento
+VOLUME_WINDOW_TITLE = Volumen de entrenamiento
+VOLUME_PERIOD_WEEK = Por semana
+VOLUME_PERIOD_MONTH = Por mes
+VOLUME_PERIOD_YEAR = Por año
+VOLUME_ALL_SPORTS = Todos los deportes
+VOLUME_COLUMN_PERIOD = Periodo
+VOLUME_COLUMN_ACTIVITIES = Actividades
+VOLUME_COLUMN_DISTANCE = Distancia
+VOLUME_COLUMN_TIME = Tiempo
+VOLUME_COLUMN_ASCENT = Desnivel
+VOLUME_COLUMN_PACE = Ritmo medio
+VOLUME_TOTAL = Total
+COMPARE_BUTTON_LABEL = Comparar
+COMPARE_CLEAR_BUTTON_LABEL = Quitar comparación
+GRAPHS_BUTTON_LABEL = Gráficos
+SMOOTHING_BUTTON_LABEL = Suavizado
+SMOOTHING_RAW = Sin filtrar
+SMOOTHING_ROLLING_MEAN = Media móvil
+SMOOTHING_ROLLING_MEDIAN = Mediana móvil
+SMOOTHING_EXPONENTIAL = Exponencial
+SMOOTHING_WINDOW_LABEL = Ventana (puntos)
+SMOOTHING_REMOVE_SPIKES = Eliminar picos
+MAP_BUTTON_LABEL = Mapa
+MAP_OFFLINE_LABEL = Sin conexión
+MAP_PREFETCH_LABEL = Descargar los mosaicos de esta actividad
+MAP_PREFETCH_PROGRESS = Descargando mosaicos { $done }/{ $total }
+MAP_OPEN_MBTILES_LABEL = Abrir archivo MBTiles…
+MAP_ONLINE_LABEL = Usar el mapa en línea
+MAP_SOURCE_ADD_LABEL = Añadir una fuente de mapa…
+MAP_SOURCE_REMOVE_LABEL = Eliminar esta fuente de mapa
+MAP_SOURCE_WINDOW_TITLE = Añadir una fuente de mapa
+MAP_SOURCE_NAME = Nombre
+MAP_SOURCE_URL = Plantilla de URL
+MAP_SOURCE_ATTRIBUTION = Atribución
+MAP_SOURCE_MIN_ZOOM = Nivel de zoom mínimo
+MAP_SOURCE_MAX_ZOOM = Nivel de zoom máximo
+MAP_SOURCE_ADD_BUTTON = Añadir
+TRACK_COLOR_LABEL = Colorear el recorrido según
+TRACK_COLOR_SINGLE = Color único
+TRACK_COLOR_PACE = Ritmo
+TRACK_COLOR_HEART_RATE_ZONE = Zona de frecuencia cardíaca
+TRACK_COLOR_ELEVATION = Altitud
+TRACK_COLOR_GRADE = Pendiente
+MAP_DISTANCE_MARKERS_LABEL = Marcar cada kilómetro o milla
+MAP_LAP_MARKER = Vuelta { $lap }
+MAP_NO_POSITION_DATA = Esta actividad no tiene datos de posición
+RECORDS_BUTTON_LABEL = Récords personales
+RECORDS_WINDOW_TITLE = Récords personales
+LOAD_BUTTON_LABEL = Carga de entrenamiento
+LOAD_WINDOW_TITLE = Carga de entrenamiento
+LOAD_AXIS_LABEL = hrTSS por día
+LOAD_CHRONIC = Forma física (CTL)
+LOAD_ACUTE = Fatiga (ATL)
+LOAD_FORM = Frescura (TSB)
+RECORDS_NONE = Ninguna actividad de la biblioteca cubre una distancia de mejor esfuerzo.
+EXPORT_BUTTON_LABEL = Exportar
+EXPORT_GPX_BUTTON_LABEL = Recorrido GPX
+EXPORT_CSV_BUTTON_LABEL = Serie temporal CSV
+EXPORT_JSON_BUTTON_LABEL = Resumen JSON
 MESSAGE_FILE_NOT_FOUND = Archivo no encontrado.
 MESSAGE_PERMISSIONS = Error desconocido. ¿Problema de permisos?
+MESSAGE_NOT_A_FIT_FILE = No se puede leer el archivo. ¿No es un archivo FIT, TCX o GPX?
+MESSAGE_PREFETCH_FAILED = No se pudieron descargar { $failed } de { $total } mosaicos.
+MESSAGE_NOT_AN_MBTILES_FILE = No se puede leer el archivo MBTiles: { $error }
+MESSAGE_NOT_AN_ACTIVITY_FILE = No se puede leer la actividad: { $error }. Los formatos admitidos son FIT, TCX y GPX.
+MESSAGE_BAD_MAP_SOURCE = Indique un nombre y una plantilla de URL que contenga { "{z}" }, { "{x}" } y { "{y}" }.
+CLI_INVALID_ARGUMENT = Argumento no válido
+CLI_USAGE = 
+    Uso: siliconsneaker2 [archivo]
+         siliconsneaker2 summary <archivo> [--units metric|us]
+         siliconsneaker2 volume [carpeta] [--period week|month|year] [--sport <sport>] [--units metric|us]
+         siliconsneaker2 records [carpeta] [--sport <sport>] [--units metric|us]
 
 TOOLTIP_TEXT_VIEW = 
     Esta sección contiene un informe de vueltas, zonas de frecuencia cardíaca e información resumida de la sesión.
@@ -15,9 +100,63 @@ TOOLTIP_TEXT_VIEW =
 TOOLTIP_ABOUT_BUTTON = Mostrar créditos del programa, licencia e información de derechos de autor.
 
 TOOLTIP_OPEN_BUTTON = 
-    Abrir un archivo de actividad de Garmin (FIT).
+    Abrir un archivo de actividad de Garmin (FIT), de Training Center (TCX) o un recorrido GPX.
     Asegúrese de haber copiado primero el archivo del reloj al sistema de archivos.
 
+TOOLTIP_LIBRARY_BUTTON = 
+    Explorar las actividades de una carpeta, por ejemplo la carpeta GARMIN/Activity de un reloj conectado.
+    La carpeta se indexa una sola vez y se recuerda para la próxima vez.
+
+TOOLTIP_LIBRARY_CHOOSE_FOLDER_BUTTON = Elegir la carpeta que contiene sus archivos de actividad e indexarla.
+
+TOOLTIP_LIBRARY_RESCAN_BUTTON = Indexar los archivos nuevos o modificados de la carpeta de actividades.
+
+TOOLTIP_LIBRARY_VIEW = 
+    Haga clic en el encabezado de una columna para ordenar las actividades.
+    Haga doble clic en una actividad para abrirla.
+
+TOOLTIP_VOLUME_BUTTON = Mostrar la distancia, el tiempo, el desnivel, las actividades y el ritmo de la biblioteca por semana, mes o año.
+
+TOOLTIP_COMPARE_BUTTON = 
+    Abrir un segundo archivo de actividad para superponerlo en magenta en los gráficos y el mapa.
+    Útil para comparar carreras en el mismo recorrido.
+
+TOOLTIP_COMPARE_CLEAR_BUTTON = Quitar la actividad de comparación de los gráficos y el mapa.
+
+TOOLTIP_COMPARE_LOADED = Comparando con { $file }. Haga clic para quitar la comparación.
+
+TOOLTIP_GRAPHS_BUTTON = Elija qué mediciones registradas se representan.
+TOOLTIP_SMOOTHING_BUTTON = Suavizar los gráficos o eliminar picos, por ejemplo del ritmo GPS.  Sin filtrar muestra los valores registrados.
+TOOLTIP_SMOOTHING_WINDOW = Número de lecturas sobre las que se calcula cada valor suavizado.
+TOOLTIP_SMOOTHING_REMOVE_SPIKES = Sustituir las lecturas alejadas de sus vecinas por la mediana de la ventana.
+TOOLTIP_MAP_BUTTON = Mosaicos del mapa: modo sin conexión, descarga anticipada y archivos MBTiles locales.
+TOOLTIP_MAP_OFFLINE = Mostrar solo los mosaicos guardados en el disco, sin usar la red.
+TOOLTIP_MAP_PREFETCH = Descargar los mosaicos que cubren esta actividad para poder ver su mapa sin conexión. Solo para las fuentes de mapas que ha añadido, ya que los servidores de OpenStreetMap prohíben las descargas masivas.
+TOOLTIP_MAP_OPEN_MBTILES = Mostrar el mapa de un archivo MBTiles local de mosaicos de imagen.
+TOOLTIP_MAP_ONLINE = Dejar de usar el archivo MBTiles y mostrar el mapa en línea.
+TOOLTIP_MAP_SOURCE = Elegir el mapa del que se dibujan las teselas.
+TOOLTIP_MAP_SOURCE_ADD = Añadir un mapa de un servidor de teselas XYZ, por ejemplo uno en este ordenador.
+TOOLTIP_MAP_SOURCE_REMOVE = Eliminar la fuente de mapa elegida que usted añadió.
+TOOLTIP_MAP_SOURCE_URL = Dirección de las teselas, con { "{z}" } para el nivel de zoom y { "{x}" } y { "{y}" } para la columna y la fila de la tesela.
+TOOLTIP_TRACK_COLOR = Colorear el recorrido en el mapa según el ritmo, la zona de frecuencia cardíaca, la altitud o la pendiente, del azul para el más bajo al rojo para el más alto.
+TOOLTIP_MAP_DISTANCE_MARKERS = Colocar un marcador en el recorrido en cada kilómetro, o milla en unidades de EE. UU.  Al hacer clic en un marcador de vuelta o de distancia, la posición se mueve allí y la vuelta se resalta en el resumen.
+TOOLTIP_RECORDS_BUTTON = Listar la actividad más rápida de la biblioteca en cada distancia, de 400 m al maratón.
+TOOLTIP_LOAD_BUTTON = Graficar la forma física, la fatiga y la frescura de la biblioteca a partir del estrés cardíaco de cada actividad.
+TOOLTIP_BEST_EFFORT_DROPDOWN = Resaltar en los gráficos y el mapa el tramo más rápido de la actividad en una distancia.
+TOOLTIP_EXPORT_BUTTON = Guardar la actividad cargada en un formato que otros programas puedan leer.
+
+TOOLTIP_EXPORT_GPX_BUTTON = 
+    Guardar el recorrido GPS de la actividad cargada como archivo GPX.
+    Se incluyen la altitud, la hora, la frecuencia cardíaca, la cadencia y la temperatura para otras herramientas de mapas.
+
+TOOLTIP_EXPORT_CSV_BUTTON = 
+    Guardar todas las mediciones registradas como tabla CSV para hojas de cálculo.
+    Los valores se convierten al sistema de unidades seleccionado.
+
+TOOLTIP_EXPORT_JSON_BUTTON = 
+    Guardar los valores de la sesión, las vueltas y las zonas cardíacas como documento JSON para otros programas.
+    Cada valor se etiqueta con su unidad en el sistema de unidades seleccionado.
+
 TOOLTIP_UNITS_DROPDOWN = 
     Seleccione su sistema de unidades preferido.
     Este será el valor predeterminado la próxima vez que inicie el programa, pero se puede cambiar en cualquier momento.
@@ -29,6 +168,7 @@ TOOLTIP_POSITION_SCALE =
     Utilice las flechas derecha e izquierda del teclado para un control de alta precisión.
 
 TOOLTIP_ZOOM_SCALE = Zoom a los ejes y de los gráficos con este control.
+TOOLTIP_X_AXIS_DROPDOWN = Represente los gráficos según la distancia, el tiempo transcurrido o la hora del día.
 
 TOOLTIP_MAP_FRAME = 
     Esta sección muestra una ruta de carrera basada en los datos de GPS recopilados por su reloj.
@@ -43,9 +183,19 @@ GRAPH_CAPTION_HR = Frecuencia cardíaca
 GRAPH_CAPTION_CADENCE = Cadencia
 GRAPH_CAPTION_ELEVATION = Elevación
 GRAPH_CAPTION_TEMP = Temperatura
+GRAPH_CAPTION_POWER = Potencia
+GRAPH_CAPTION_VERTICAL_OSCILLATION = Oscilación vertical
+GRAPH_CAPTION_STANCE_TIME = Tiempo de contacto con el suelo
+GRAPH_CAPTION_STEP_LENGTH = Longitud de paso
+GRAPH_CAPTION_RESPIRATION_RATE = Frecuencia respiratoria
+GRAPH_CAPTION_GRADE = Pendiente
+GRAPH_CAPTION_EFFICIENCY_FACTOR = Factor de eficiencia
+GRAPH_OVERLAY_GAP = RAP
 
 LABEL_DISTANCE_MILES = Distancia (millas)
 LABEL_DISTANCE_KM = Distancia (km)
+LABEL_ELAPSED_TIME = Tiempo transcurrido (h:mm:ss)
+LABEL_CLOCK_TIME = Hora del día (h:mm:ss)
 LABEL_PACE_US = Ritmo (min/milla)
 LABEL_PACE_METRIC = Ritmo (min/km)
 LABEL_HR_BPM = Frecuencia cardíaca (lpm)
@@ -60,6 +210,35 @@ SUMMARY_SESSION_HEADER = ============================ Sesión ==================
 SUMMARY_LAP_HEADER = Vuelta
 SUMMARY_HR_ZONE_HEADER = =================== Tiempo en zonas de FC por sesión ========
 SUMMARY_HR_ZONE_LABEL = Zona
+SUMMARY_PACE_ZONE_HEADER = ============ Tiempo en zonas de ritmo para la sesión  ==========
+PACE_ZONE_SLOWER = Más lento
+PACE_ZONE_EASY = Suave
+PACE_ZONE_MARATHON = Maratón
+PACE_ZONE_THRESHOLD = Umbral
+PACE_ZONE_INTERVAL = Intervalos
+PACE_ZONE_REPETITION = Repeticiones
+SUMMARY_SPLITS_HEADER = =========================== Parciales ==================================
+SPLITS_COLUMN_SPLIT = N.º
+SPLITS_COLUMN_DISTANCE = Distancia
+SPLITS_COLUMN_TIME = Tiempo
+SPLITS_COLUMN_PACE = Ritmo
+SPLITS_COLUMN_HEART_RATE = FC
+SPLITS_COLUMN_CADENCE = Cadencia
+SPLITS_COLUMN_ASCENT = Subida
+SPLITS_COLUMN_DESCENT = Bajada
+SUMMARY_BEST_EFFORTS_HEADER = ========================== Mejores esfuerzos ===========================
+BEST_EFFORT_NONE = Ningún mejor esfuerzo
+BEST_EFFORT_400M = 400 m
+BEST_EFFORT_1K = 1 km
+BEST_EFFORT_MILE = 1 milla
+BEST_EFFORT_5K = 5 km
+BEST_EFFORT_10K = 10 km
+BEST_EFFORT_HALF_MARATHON = Media maratón
+BEST_EFFORT_MARATHON = Maratón
+SUMMARY_DECOUPLING_HEADER = ============================ Desacoplamiento aeróbico ==================
+DECOUPLING_FIRST_HALF = Factor de eficiencia, 1ª mitad
+DECOUPLING_SECOND_HALF = Factor de eficiencia, 2ª mitad
+DECOUPLING_PERCENT = Desacoplamiento (ritmo:FC)
 
 UNIT_FEET = pies
 UNIT_METERS = metros
@@ -94,6 +273,9 @@ PRETTY_TOTAL_DISTANCE = Distancia total
 PRETTY_TOTAL_ELAPSED_TIME = Tiempo total transcurrido
 PRETTY_TOTAL_TIMER_TIME = Tiempo total del cronómetro
 PRETTY_ENHANCED_AVG_SPEED = Ritmo promedio
+PRETTY_AVG_GRADE_ADJUSTED_PACE = Ritmo medio ajustado a la pendiente
+PRETTY_TRIMP = Impulso de entrenamiento (TRIMP)
+PRETTY_HR_TSS = Estrés cardíaco (hrTSS)
 PRETTY_ENHANCED_MAX_SPEED = Ritmo más rápido
 PRETTY_MIN_TEMPERATURE = Temperatura mínima
 PRETTY_MAX_TEMPERATURE = Temperatura máxima
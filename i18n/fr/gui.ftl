@@ -2,11 +2,96 @@ PROGRAM_TITLE = NOM_DU_PROGRAMME
 COPYRIGHT = Droits d'auteur
 UNITS_METRIC = ⚽ Métrique
 UNITS_US = 🏈 US
+X_AXIS_DISTANCE = Distance
+X_AXIS_ELAPSED_TIME = Temps écoulé
+X_AXIS_CLOCK_TIME = Heure
 ABOUT_BUTTON_LABEL = À propos
 ABOUT_DIALOG_COMMENT = Consultez vos fichiers de course à pied sur le bureau de votre ordinateur.
-OPEN_FILE_BUTTON_LABEL = Ouvrir un fichier FIT...
+OPEN_FILE_BUTTON_LABEL = Ouvrir un fichier d'activité...
+LIBRARY_BUTTON_LABEL = Bibliothèque
+LIBRARY_WINDOW_TITLE = Bibliothèque d'activités
+LIBRARY_CHOOSE_FOLDER_BUTTON_LABEL = Choisir un dossier
+LIBRARY_RESCAN_BUTTON_LABEL = Réindexer
+LIBRARY_NO_FOLDER = Aucun dossier d'activités choisi.
+LIBRARY_SCAN_PROGRESS = Lecture des fichiers d'activité { $done }/{ $total }
+LIBRARY_COLUMN_DATE = Date
+LIBRARY_COLUMN_SPORT = Sport
+LIBRARY_COLUMN_DISTANCE = Distance
+LIBRARY_COLUMN_DURATION = Durée
+LIBRARY_COLUMN_AVG_HEART_RATE = Fréquence cardiaque moyenne
+VOLUME_BUTTON_LABEL = Volume d'entraînement
+VOLUME_WINDOW_TITLE = Volume d'entraînement
+VOLUME_PERIOD_WEEK = Par semaine
+VOLUME_PERIOD_MONTH = Par mois
+VOLUME_PERIOD_YEAR = Par année
+VOLUME_ALL_SPORTS = Tous les sports
+VOLUME_COLUMN_PERIOD = Période
+VOLUME_COLUMN_ACTIVITIES = Activités
+VOLUME_COLUMN_DISTANCE = Distance
+VOLUME_COLUMN_TIME = Temps
+VOLUME_COLUMN_ASCENT = Dénivelé
+VOLUME_COLUMN_PACE = Allure moyenne
+VOLUME_TOTAL = Total
+COMPARE_BUTTON_LABEL = Comparer
+COMPARE_CLEAR_BUTTON_LABEL = Effacer la comparaison
+GRAPHS_BUTTON_LABEL = Graphiques
+SMOOTHING_BUTTON_LABEL = Lissage
+SMOOTHING_RAW = Brut
+SMOOTHING_ROLLING_MEAN = Moyenne glissante
+SMOOTHING_ROLLING_MEDIAN = Médiane glissante
+SMOOTHING_EXPONENTIAL = Exponentiel
+SMOOTHING_WINDOW_LABEL = Fenêtre (points)
+SMOOTHING_REMOVE_SPIKES = Supprimer les pics
+MAP_BUTTON_LABEL = Carte
+MAP_OFFLINE_LABEL = Hors ligne
+MAP_PREFETCH_LABEL = Télécharger les tuiles de cette activité
+MAP_PREFETCH_PROGRESS = Téléchargement des tuiles { $done }/{ $total }
+MAP_OPEN_MBTILES_LABEL = Ouvrir un fichier MBTiles…
+MAP_ONLINE_LABEL = Utiliser la carte en ligne
+MAP_SOURCE_ADD_LABEL = Ajouter une source de carte…
+MAP_SOURCE_REMOVE_LABEL = Supprimer cette source de carte
+MAP_SOURCE_WINDOW_TITLE = Ajouter une source de carte
+MAP_SOURCE_NAME = Nom
+MAP_SOURCE_URL = Modèle d'URL
+MAP_SOURCE_ATTRIBUTION = Attribution
+MAP_SOURCE_MIN_ZOOM = Niveau de zoom minimal
+MAP_SOURCE_MAX_ZOOM = Niveau de zoom maximal
+MAP_SOURCE_ADD_BUTTON = Ajouter
+TRACK_COLOR_LABEL = Colorer le parcours selon
+TRACK_COLOR_SINGLE = Couleur unique
+TRACK_COLOR_PACE = Allure
+TRACK_COLOR_HEART_RATE_ZONE = Zone de fréquence cardiaque
+TRACK_COLOR_ELEVATION = Altitude
+TRACK_COLOR_GRADE = Pente
+MAP_DISTANCE_MARKERS_LABEL = Marquer chaque kilomètre ou mile
+MAP_LAP_MARKER = Tour { $lap }
+MAP_NO_POSITION_DATA = Aucune donnée de position dans cette activité
+RECORDS_BUTTON_LABEL = Records personnels
+RECORDS_WINDOW_TITLE = Records personnels
+LOAD_BUTTON_LABEL = Charge d'entraînement
+LOAD_WINDOW_TITLE = Charge d'entraînement
+LOAD_AXIS_LABEL = hrTSS par jour
+LOAD_CHRONIC = Forme physique (CTL)
+LOAD_ACUTE = Fatigue (ATL)
+LOAD_FORM = Fraîcheur (TSB)
+RECORDS_NONE = Aucune activité de la bibliothèque ne couvre une distance de meilleur effort.
+EXPORT_BUTTON_LABEL = Exporter
+EXPORT_GPX_BUTTON_LABEL = Trace GPX
+EXPORT_CSV_BUTTON_LABEL = Série temporelle CSV
+EXPORT_JSON_BUTTON_LABEL = Résumé JSON
 MESSAGE_FILE_NOT_FOUND = Fichier introuvable.
 MESSAGE_PERMISSIONS = Erreur inconnue. Problème d'autorisations?
+MESSAGE_NOT_A_FIT_FILE = Impossible de lire le fichier. Ce n'est pas un fichier FIT, TCX ou GPX?
+MESSAGE_PREFETCH_FAILED = { $failed } tuiles sur { $total } n'ont pas pu être téléchargées.
+MESSAGE_NOT_AN_MBTILES_FILE = Impossible de lire le fichier MBTiles : { $error }
+MESSAGE_NOT_AN_ACTIVITY_FILE = Impossible de lire l'activité : { $error }. Les formats pris en charge sont FIT, TCX et GPX.
+MESSAGE_BAD_MAP_SOURCE = Indiquez un nom et un modèle d'URL contenant { "{z}" }, { "{x}" } et { "{y}" }.
+CLI_INVALID_ARGUMENT = Argument invalide
+CLI_USAGE = 
+    Utilisation: siliconsneaker2 [fichier]
+                 siliconsneaker2 summary <fichier> [--units metric|us]
+                 siliconsneaker2 volume [dossier] [--period week|month|year] [--sport <sport>] [--units metric|us]
+                 siliconsneaker2 records [dossier] [--sport <sport>] [--units metric|us]
 
 TOOLTIP_TEXT_VIEW = 
     Cette section contient un rapport sur les tours, les zones de fréquence cardiaque et les informations de résumé de session.
@@ -15,9 +100,63 @@ TOOLTIP_TEXT_VIEW =
 TOOLTIP_ABOUT_BUTTON = Afficher les crédits du programme, la licence et les informations sur le droit d'auteur.
 
 TOOLTIP_OPEN_BUTTON = 
-    Ouvrir un fichier Garmin Activity Fit.
+    Ouvrir un fichier Garmin Activity FIT, un fichier Training Center (TCX) ou une trace GPX.
     Veuillez vous assurer d'avoir d'abord copié le fichier de la montre vers le système de fichiers.
 
+TOOLTIP_LIBRARY_BUTTON = 
+    Parcourir les activités d'un dossier, par exemple le dossier GARMIN/Activity d'une montre connectée.
+    Le dossier est indexé une seule fois et mémorisé pour la prochaine fois.
+
+TOOLTIP_LIBRARY_CHOOSE_FOLDER_BUTTON = Choisir le dossier contenant vos fichiers d'activité et l'indexer.
+
+TOOLTIP_LIBRARY_RESCAN_BUTTON = Indexer les fichiers nouveaux ou modifiés du dossier d'activités.
+
+TOOLTIP_LIBRARY_VIEW = 
+    Cliquez sur un en-tête de colonne pour trier les activités.
+    Double-cliquez sur une activité pour l'ouvrir.
+
+TOOLTIP_VOLUME_BUTTON = Afficher la distance, le temps, le dénivelé, les activités et l'allure de la bibliothèque par semaine, mois ou année.
+
+TOOLTIP_COMPARE_BUTTON = 
+    Ouvrir un second fichier d'activité à superposer en magenta sur les graphiques et la carte.
+    Pratique pour comparer des courses sur le même parcours.
+
+TOOLTIP_COMPARE_CLEAR_BUTTON = Retirer l'activité de comparaison des graphiques et de la carte.
+
+TOOLTIP_COMPARE_LOADED = Comparaison avec { $file }. Cliquez pour retirer la comparaison.
+
+TOOLTIP_GRAPHS_BUTTON = Choisissez les mesures enregistrées à représenter.
+TOOLTIP_SMOOTHING_BUTTON = Lisser les graphiques ou supprimer les pics, par exemple de l'allure GPS.  Brut affiche les valeurs enregistrées.
+TOOLTIP_SMOOTHING_WINDOW = Nombre de mesures sur lesquelles chaque valeur lissée est calculée.
+TOOLTIP_SMOOTHING_REMOVE_SPIKES = Remplacer les mesures éloignées de leurs voisines par la médiane de la fenêtre.
+TOOLTIP_MAP_BUTTON = Tuiles de la carte : mode hors ligne, téléchargement à l'avance et fichiers MBTiles locaux.
+TOOLTIP_MAP_OFFLINE = Afficher seulement les tuiles enregistrées sur le disque, sans utiliser le réseau.
+TOOLTIP_MAP_PREFETCH = Télécharger les tuiles couvrant cette activité pour pouvoir afficher sa carte hors ligne. Seulement pour les sources de cartes que vous avez ajoutées, car les serveurs d'OpenStreetMap interdisent les téléchargements en masse.
+TOOLTIP_MAP_OPEN_MBTILES = Afficher la carte d'un fichier MBTiles local de tuiles images.
+TOOLTIP_MAP_ONLINE = Ne plus utiliser le fichier MBTiles et afficher la carte en ligne.
+TOOLTIP_MAP_SOURCE = Choisir la carte dont les tuiles sont affichées.
+TOOLTIP_MAP_SOURCE_ADD = Ajouter une carte provenant d'un serveur de tuiles XYZ, par exemple sur cet ordinateur.
+TOOLTIP_MAP_SOURCE_REMOVE = Supprimer la source de carte choisie que vous avez ajoutée.
+TOOLTIP_MAP_SOURCE_URL = Adresse des tuiles, avec { "{z}" } pour le niveau de zoom et { "{x}" } et { "{y}" } pour la colonne et la ligne de la tuile.
+TOOLTIP_TRACK_COLOR = Colorer le parcours sur la carte selon l'allure, la zone de fréquence cardiaque, l'altitude ou la pente, du bleu pour le plus bas au rouge pour le plus haut.
+TOOLTIP_MAP_DISTANCE_MARKERS = Placer un repère sur le parcours à chaque kilomètre, ou mile en unités américaines.  Cliquer sur un repère de tour ou de distance y déplace la position et met le tour en évidence dans le résumé.
+TOOLTIP_RECORDS_BUTTON = Lister l'activité la plus rapide de la bibliothèque sur chaque distance, du 400 m au marathon.
+TOOLTIP_LOAD_BUTTON = Tracer la forme physique, la fatigue et la fraîcheur de la bibliothèque à partir du stress cardiaque de chaque activité.
+TOOLTIP_BEST_EFFORT_DROPDOWN = Mettre en évidence sur les graphiques et la carte le passage le plus rapide de l'activité sur une distance.
+TOOLTIP_EXPORT_BUTTON = Enregistrer l'activité chargée dans un format lisible par d'autres programmes.
+
+TOOLTIP_EXPORT_GPX_BUTTON = 
+    Enregistrer le tracé GPS de l'activité chargée dans un fichier GPX.
+    L'altitude, l'heure, la fréquence cardiaque, la cadence et la température sont incluses pour d'autres outils de cartographie.
+
+TOOLTIP_EXPORT_CSV_BUTTON = 
+    Enregistrer toutes les mesures dans un tableau CSV pour les tableurs.
+    Les valeurs sont converties dans le système d'unités sélectionné.
+
+TOOLTIP_EXPORT_JSON_BUTTON = 
+    Enregistrer les valeurs de la session, des tours et des zones cardiaques dans un document JSON pour d'autres programmes.
+    Chaque valeur est accompagnée de son unité dans le système d'unités sélectionné.
+
 TOOLTIP_UNITS_DROPDOWN = 
     Sélectionnez votre système d'unités préféré.
     Ce sera le système par défaut lors du prochain démarrage du programme, mais il peut être modifié à tout moment.
@@ -29,6 +168,7 @@ TOOLTIP_POSITION_SCALE =
     Utilisez les flèches droite et gauche de votre clavier pour un contrôle de haute précision.
 
 TOOLTIP_ZOOM_SCALE = Zoomez sur les axes y des graphiques avec cette commande.
+TOOLTIP_X_AXIS_DROPDOWN = Tracez les graphiques en fonction de la distance, du temps écoulé ou de l'heure.
 
 TOOLTIP_MAP_FRAME = 
     Cette section affiche un parcours basé sur les données GPS collectées par votre montre.
@@ -43,9 +183,19 @@ GRAPH_CAPTION_HR = Fréquence cardiaque
 GRAPH_CAPTION_CADENCE = Cadence
 GRAPH_CAPTION_ELEVATION = Altitude
 GRAPH_CAPTION_TEMP = Température
+GRAPH_CAPTION_POWER = Puissance
+GRAPH_CAPTION_VERTICAL_OSCILLATION = Oscillation verticale
+GRAPH_CAPTION_STANCE_TIME = Temps de contact au sol
+GRAPH_CAPTION_STEP_LENGTH = Longueur de foulée
+GRAPH_CAPTION_RESPIRATION_RATE = Fréquence respiratoire
+GRAPH_CAPTION_GRADE = Pente
+GRAPH_CAPTION_EFFICIENCY_FACTOR = Facteur d'efficacité
+GRAPH_OVERLAY_GAP = AAP
 
 LABEL_DISTANCE_MILES = Distance (milles)
 LABEL_DISTANCE_KM = Distance (km)
+LABEL_ELAPSED_TIME = Temps écoulé (h:mm:ss)
+LABEL_CLOCK_TIME = Heure (h:mm:ss)
 LABEL_PACE_US = Allure (min/mille)
 LABEL_PACE_METRIC = Allure (min/km)
 LABEL_HR_BPM = Fréquence cardiaque (bpm)
@@ -60,6 +210,35 @@ SUMMARY_SESSION_HEADER = ============================ Session ==================
 SUMMARY_LAP_HEADER = Tour
 SUMMARY_HR_ZONE_HEADER = ========= Temps dans les zones de fréquence cardiaque pour la session ========
 SUMMARY_HR_ZONE_LABEL = Zone
+SUMMARY_PACE_ZONE_HEADER = ========= Temps dans les zones d'allure pour la session ========
+PACE_ZONE_SLOWER = Plus lent
+PACE_ZONE_EASY = Facile
+PACE_ZONE_MARATHON = Marathon
+PACE_ZONE_THRESHOLD = Seuil
+PACE_ZONE_INTERVAL = Fractionné
+PACE_ZONE_REPETITION = Répétition
+SUMMARY_SPLITS_HEADER = ======================= Temps intermédiaires ==========================
+SPLITS_COLUMN_SPLIT = N°
+SPLITS_COLUMN_DISTANCE = Distance
+SPLITS_COLUMN_TIME = Temps
+SPLITS_COLUMN_PACE = Allure
+SPLITS_COLUMN_HEART_RATE = FC
+SPLITS_COLUMN_CADENCE = Cadence
+SPLITS_COLUMN_ASCENT = D+
+SPLITS_COLUMN_DESCENT = D-
+SUMMARY_BEST_EFFORTS_HEADER = ========================= Meilleurs efforts ============================
+BEST_EFFORT_NONE = Aucun meilleur effort
+BEST_EFFORT_400M = 400 m
+BEST_EFFORT_1K = 1 km
+BEST_EFFORT_MILE = 1 mille
+BEST_EFFORT_5K = 5 km
+BEST_EFFORT_10K = 10 km
+BEST_EFFORT_HALF_MARATHON = Semi-marathon
+BEST_EFFORT_MARATHON = Marathon
+SUMMARY_DECOUPLING_HEADER = ============================ Découplage aérobie ========================
+DECOUPLING_FIRST_HALF = Facteur d'efficacité, 1re moitié
+DECOUPLING_SECOND_HALF = Facteur d'efficacité, 2e moitié
+DECOUPLING_PERCENT = Découplage (allure:FC)
 
 UNIT_FEET = pieds
 UNIT_METERS = mètres
@@ -94,6 +273,9 @@ PRETTY_TOTAL_DISTANCE = Distance totale
 PRETTY_TOTAL_ELAPSED_TIME = Temps total écoulé
 PRETTY_TOTAL_TIMER_TIME =  Temps total du chronomètre
 PRETTY_ENHANCED_AVG_SPEED = Rythme moyen
+PRETTY_AVG_GRADE_ADJUSTED_PACE = Allure moyenne ajustée à la pente
+PRETTY_TRIMP = Impulsion d'entraînement (TRIMP)
+PRETTY_HR_TSS = Stress cardiaque (hrTSS)
 PRETTY_ENHANCED_MAX_SPEED =  Rythme le plus rapide
 PRETTY_MIN_TEMPERATURE = Température minimale
 PRETTY_MAX_TEMPERATURE = Température maximale
@@ -0,0 +1,135 @@
+// Module containing CSV export of the per-record time series.
+
+use crate::{
+    Units, cvt_altitude, cvt_distance, cvt_pace, cvt_speed, cvt_temperature, get_field_value,
+    semi_to_degrees,
+};
+use chrono::{DateTime, Local};
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+
+// Column headings annotated with the units the values are written in.
+fn csv_header(user_unit: &Units) -> String {
+    let (distance, speed, pace, altitude, temperature) = match user_unit {
+        Units::US => ("mi", "mph", "min/mi", "ft", "°F"),
+        Units::Metric => ("km", "km/h", "min/km", "m", "°C"),
+        Units::None => ("m", "m/s", "m/s", "m", "°C"),
+    };
+    return format!(
+        "timestamp,distance ({}),position_lat (deg),position_long (deg),enhanced_speed ({}),pace ({}),heart_rate (bpm),cadence (rpm),enhanced_altitude ({}),temperature ({})\n",
+        distance, speed, pace, altitude, temperature
+    );
+}
+
+// Format an optional value, leaving the cell empty when the record lacks it.
+fn cell(value: Option<f64>, precision: usize) -> String {
+    match value {
+        Some(v) => return format!("{:.*}", precision, v),
+        None => return String::new(),
+    }
+}
+
+// Build a CSV table with one row for every MesgNum::Record message, converted
+// to the selected unit system.
+pub fn fit_to_csv(data: &Vec<FitDataRecord>, user_unit: &Units) -> String {
+    let mut csv = csv_header(user_unit);
+    for item in data {
+        if item.kind() != MesgNum::Record {
+            continue;
+        }
+        let timestamp = get_field_value(item, "timestamp")
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+            .map(|t| {
+                t.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let distance =
+            get_field_value(item, "distance").map(|d| cvt_distance(d as f32, user_unit) as f64);
+        let lat = get_field_value(item, "position_lat").map(semi_to_degrees);
+        let lon = get_field_value(item, "position_long").map(semi_to_degrees);
+        let raw_speed = get_field_value(item, "enhanced_speed");
+        let speed = raw_speed.map(|s| cvt_speed(s as f32, user_unit) as f64);
+        let pace = raw_speed.map(|s| cvt_pace(s as f32, user_unit) as f64);
+        let heart_rate = get_field_value(item, "heart_rate");
+        let cadence = get_field_value(item, "cadence");
+        let altitude = get_field_value(item, "enhanced_altitude")
+            .map(|a| cvt_altitude(a as f32, user_unit) as f64);
+        let temperature = get_field_value(item, "temperature")
+            .map(|t| cvt_temperature(t as f32, user_unit) as f64);
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            timestamp,
+            cell(distance, 3),
+            cell(lat, 7),
+            cell(lon, 7),
+            cell(speed, 2),
+            cell(pace, 2),
+            cell(heart_rate, 0),
+            cell(cadence, 0),
+            cell(altitude, 1),
+            cell(temperature, 1),
+        ));
+    }
+    return csv;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    // Return the cells of a row after its time stamp.
+    fn values(row: &str) -> Vec<&str> {
+        return row.split(',').skip(1).collect();
+    }
+
+    #[test]
+    fn header_names_the_units_of_each_column() {
+        let csv = fit_to_csv(&Vec::new(), &Units::US);
+        assert_eq!(csv.lines().count(), 1);
+        assert!(csv.starts_with("timestamp,distance (mi),"));
+        assert!(csv.contains(",pace (min/mi),"));
+        assert!(csv.ends_with(",temperature (°F)\n"));
+    }
+
+    #[test]
+    fn every_record_is_a_converted_row() {
+        let csv = fit_to_csv(&steady_run(3, 2.5, 150.0), &Units::Metric);
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            values(rows[2]),
+            vec![
+                "0.005",
+                "0.0000449",
+                "0.0000000",
+                "9.00",
+                "6.67",
+                "150",
+                "85",
+                "100.0",
+                "20.0"
+            ]
+        );
+        assert!(rows[0].starts_with("2024-"));
+        let csv = fit_to_csv(&steady_run(3, 2.5, 150.0), &Units::US);
+        let row = csv.lines().nth(3).unwrap();
+        assert_eq!(values(row)[0], "0.003");
+        assert_eq!(values(row)[3], "5.59");
+        assert_eq!(values(row)[8], "68.0");
+    }
+
+    #[test]
+    fn missing_values_leave_the_cell_empty() {
+        let mut points = run_points(2, |_| (2.5, 150.0, 100.0));
+        points[1].heart_rate = None;
+        points[1].latitude = None;
+        let csv = fit_to_csv(&activity(&points), &Units::None);
+        let row = csv.lines().nth(2).unwrap();
+        assert_eq!(
+            values(row),
+            vec!["2.500", "", "", "2.50", "2.50", "", "85", "100.0", "20.0"]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+// Module containing the aerobic decoupling analysis, the drift of the
+// efficiency factor (speed per heart beat) between the halves of a steady run.
+
+use crate::get_field_value;
+use crate::zones::MAX_RECORD_GAP;
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+
+// Length of the stretch the rolling efficiency factor is averaged over, seconds.
+const ROLLING_WINDOW: f64 = 300.0;
+
+// Units of the efficiency factor.
+pub const EFFICIENCY_FACTOR_UNITS: &str = "m/min/bpm";
+
+// The efficiency factors of the two halves of an activity.
+#[derive(Clone, Debug)]
+pub struct Decoupling {
+    pub first_half: f64,  // m/min/bpm
+    pub second_half: f64, // m/min/bpm
+}
+
+impl Decoupling {
+    // The loss of efficiency from the first to the second half in percent.
+    // Positive values mean the heart rate drifted up relative to the speed.
+    pub fn percent(&self) -> f64 {
+        return (self.first_half - self.second_half) / self.first_half * 100.0;
+    }
+}
+
+// The speed, heart rate and duration of a record, and its index among the
+// records.  The duration of a record is the time since the previous record.
+struct Sample {
+    index: usize,
+    speed: f64,      // m/s
+    heart_rate: f64, // bpm
+    dt: f64,         // seconds, 0 across pauses
+}
+
+// Return a sample for every record with both speed and heart rate.  The
+// values and timestamp are taken from the same record so that records
+// missing a field do not shift the others.
+fn timed_samples(data: &Vec<FitDataRecord>) -> Vec<Sample> {
+    let mut samples: Vec<Sample> = Vec::new();
+    let mut last_time: Option<f64> = None;
+    let records = data.iter().filter(|item| item.kind() == MesgNum::Record);
+    for (index, item) in records.enumerate() {
+        let Some(time) = get_field_value(item, "timestamp") else {
+            continue;
+        };
+        let mut dt = time - last_time.unwrap_or(time);
+        last_time = Some(time);
+        let (Some(speed), Some(heart_rate)) = (
+            get_field_value(item, "enhanced_speed"),
+            get_field_value(item, "heart_rate"),
+        ) else {
+            continue;
+        };
+        if dt < 0.0 || dt > MAX_RECORD_GAP || heart_rate <= 0.0 {
+            dt = 0.0;
+        }
+        samples.push(Sample {
+            index,
+            speed,
+            heart_rate,
+            dt,
+        });
+    }
+    return samples;
+}
+
+// Return the efficiency factor of a stretch of samples, the time weighted
+// mean speed in m/min over the time weighted mean heart rate.
+fn efficiency_factor(samples: &[Sample]) -> Option<f64> {
+    let distance: f64 = samples.iter().map(|s| s.speed * s.dt).sum();
+    let beats: f64 = samples.iter().map(|s| s.heart_rate * s.dt).sum();
+    if beats <= 0.0 {
+        return None;
+    }
+    return Some(distance * 60.0 / beats);
+}
+
+// Return the index among the records and efficiency factor of every record
+// with speed and heart rate, averaged over the preceding ROLLING_WINDOW
+// seconds, or an empty vector without speed or heart rate.
+pub fn efficiency_factors(data: &Vec<FitDataRecord>) -> Vec<(usize, f64)> {
+    let samples = timed_samples(data);
+    let mut result: Vec<(usize, f64)> = Vec::with_capacity(samples.len());
+    let mut start = 0;
+    // Running sums of the window's duration, distance and heart beats.
+    let (mut window, mut distance, mut beats) = (0.0, 0.0, 0.0);
+    for sample in samples.iter() {
+        window += sample.dt;
+        distance += sample.speed * sample.dt;
+        beats += sample.heart_rate * sample.dt;
+        while window - samples[start].dt >= ROLLING_WINDOW {
+            let old = &samples[start];
+            window -= old.dt;
+            distance -= old.speed * old.dt;
+            beats -= old.heart_rate * old.dt;
+            start += 1;
+        }
+        if beats > 0.0 {
+            result.push((sample.index, distance * 60.0 / beats));
+        } else {
+            result.push((sample.index, 0.0));
+        }
+    }
+    return result;
+}
+
+// Return the efficiency factors of the first and second half of an activity
+// by moving time, or None without speed or heart rate.
+pub fn decoupling(data: &Vec<FitDataRecord>) -> Option<Decoupling> {
+    let samples = timed_samples(data);
+    let total: f64 = samples.iter().map(|s| s.dt).sum();
+    let mut elapsed = 0.0;
+    let middle = samples
+        .iter()
+        .position(|s| {
+            elapsed += s.dt;
+            elapsed >= total / 2.0
+        })
+        .unwrap_or(0);
+    let first_half = efficiency_factor(&samples[..middle])?;
+    let second_half = efficiency_factor(&samples[middle..])?;
+    if first_half <= 0.0 {
+        return None;
+    }
+    return Some(Decoupling {
+        first_half,
+        second_half,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    #[test]
+    fn steady_run_does_not_decouple() {
+        let result = decoupling(&steady_run(1201, 3.0, 150.0)).unwrap();
+        assert!((result.first_half - 1.2).abs() < 1e-9);
+        assert!((result.second_half - 1.2).abs() < 1e-9);
+        assert!(result.percent().abs() < 1e-9);
+    }
+
+    #[test]
+    fn heart_rate_drift_decouples() {
+        let points = run_points(1201, |i| {
+            if i <= 600 {
+                (3.0, 150.0, 100.0)
+            } else {
+                (3.0, 165.0, 100.0)
+            }
+        });
+        let result = decoupling(&activity(&points)).unwrap();
+        assert!((result.percent() - (1.0 - 150.0 / 165.0) * 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn decoupling_needs_heart_rate() {
+        let mut points = run_points(61, |_| (3.0, 150.0, 100.0));
+        for point in points.iter_mut() {
+            point.heart_rate = None;
+        }
+        assert!(decoupling(&activity(&points)).is_none());
+        assert!(efficiency_factors(&activity(&points)).is_empty());
+    }
+
+    #[test]
+    fn efficiency_factors_are_averaged_over_the_window() {
+        // The speed doubles after 600 s.
+        let points = run_points(1201, |i| {
+            if i <= 600 {
+                (2.0, 150.0, 100.0)
+            } else {
+                (4.0, 150.0, 100.0)
+            }
+        });
+        let factors = efficiency_factors(&activity(&points));
+        assert_eq!(factors.len(), 1201);
+        assert!((factors[600].1 - 0.8).abs() < 1e-9);
+        // Halfway through the window after the change.
+        assert!((factors[750].1 - 1.2).abs() < 0.01);
+        assert!((factors[1200].1 - 1.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn efficiency_factors_carry_the_record_index() {
+        let mut points = run_points(11, |_| (3.0, 150.0, 100.0));
+        points[4].heart_rate = None;
+        let indices: Vec<usize> = efficiency_factors(&activity(&points))
+            .iter()
+            .map(|(index, _)| *index)
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 3, 5, 6, 7, 8, 9, 10]);
+    }
+}
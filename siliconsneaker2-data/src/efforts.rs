@@ -0,0 +1,149 @@
+// Module containing the best effort analysis, the fastest stretch of an
+// activity over each of the standard race distances.
+
+use crate::get_field_value;
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+use serde::{Deserialize, Serialize};
+
+// Name and length in meters of the distances best efforts are searched for.
+pub const BEST_EFFORT_DISTANCES: [(&str, f64); 7] = [
+    ("400m", 400.0),
+    ("1k", 1000.0),
+    ("mile", 1609.344),
+    ("5k", 5000.0),
+    ("10k", 10000.0),
+    ("half_marathon", 21097.5),
+    ("marathon", 42195.0),
+];
+
+// The fastest stretch of an activity over one distance.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BestEffort {
+    pub name: String,  // see BEST_EFFORT_DISTANCES
+    pub distance: f64, // meters
+    pub time: f64,     // elapsed seconds
+    #[serde(skip)]
+    pub start_index: usize, // first record of the stretch, by index among the records
+    #[serde(skip)]
+    pub end_index: usize, // last record of the stretch, by index among the records
+}
+
+impl BestEffort {
+    // Average speed in m/s.
+    pub fn avg_speed(&self) -> f64 {
+        if self.time > 0.0 {
+            return self.distance / self.time;
+        }
+        return 0.0;
+    }
+}
+
+// Return the fastest stretch covering a distance, or None when the activity is
+// shorter.  The time of a stretch that overshoots the distance between two
+// records is scaled down to the distance.
+fn fastest_stretch(distance: &[f64], time: &[f64], length: f64) -> Option<(f64, usize, usize)> {
+    let mut best: Option<(f64, usize, usize)> = None;
+    let mut start = 0;
+    for end in 0..distance.len() {
+        // Move the start up while the stretch still covers the distance.
+        while start + 1 < end && distance[end] - distance[start + 1] >= length {
+            start += 1;
+        }
+        let covered = distance[end] - distance[start];
+        if covered < length {
+            continue;
+        }
+        let elapsed = (time[end] - time[start]) * length / covered;
+        if elapsed > 0.0 && best.is_none_or(|b| elapsed < b.0) {
+            best = Some((elapsed, start, end));
+        }
+    }
+    return best;
+}
+
+// Return the best efforts of an activity over every distance it covers,
+// shortest distance first.  The distance and time are taken from the same
+// record, and the stretches given by index among all the records.
+pub fn best_efforts(data: &Vec<FitDataRecord>) -> Vec<BestEffort> {
+    let mut record_indices: Vec<usize> = Vec::new();
+    let mut distance: Vec<f64> = Vec::new();
+    let mut time: Vec<f64> = Vec::new();
+    let records = data.iter().filter(|item| item.kind() == MesgNum::Record);
+    for (index, item) in records.enumerate() {
+        if let (Some(d), Some(t)) = (
+            get_field_value(item, "distance"),
+            get_field_value(item, "timestamp"),
+        ) {
+            record_indices.push(index);
+            distance.push(d);
+            time.push(t);
+        }
+    }
+    let mut efforts: Vec<BestEffort> = Vec::new();
+    for (name, length) in BEST_EFFORT_DISTANCES {
+        if let Some((elapsed, start, end)) = fastest_stretch(&distance, &time, length) {
+            efforts.push(BestEffort {
+                name: name.to_string(),
+                distance: length,
+                time: elapsed,
+                start_index: record_indices[start],
+                end_index: record_indices[end],
+            });
+        }
+    }
+    return efforts;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    #[test]
+    fn efforts_cover_only_the_distances_run() {
+        // 2000 m at 4 m/s.
+        let efforts = best_efforts(&steady_run(501, 4.0, 150.0));
+        let names: Vec<&str> = efforts.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["400m", "1k", "mile"]);
+        assert!((efforts[0].time - 100.0).abs() < 1e-9);
+        assert!((efforts[1].time - 250.0).abs() < 1e-9);
+        assert!((efforts[2].time - 1609.344 / 4.0).abs() < 1e-9);
+        assert!((efforts[2].avg_speed() - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fastest_stretch_is_found() {
+        // 600 s at 3 m/s, then 200 s at 5 m/s.
+        let points = run_points(801, |i| {
+            if i <= 600 {
+                (3.0, 150.0, 100.0)
+            } else {
+                (5.0, 170.0, 100.0)
+            }
+        });
+        let efforts = best_efforts(&activity(&points));
+        assert_eq!(efforts[0].name, "400m");
+        assert!((efforts[0].time - 80.0).abs() < 1e-9);
+        assert!(efforts[0].start_index >= 600);
+        assert_eq!(efforts[0].end_index - efforts[0].start_index, 80);
+    }
+
+    #[test]
+    fn stretch_indices_count_all_the_records() {
+        // The first records have no distance, so the indices among the
+        // records are ahead of those among the distances.
+        let mut points = run_points(501, |i| {
+            if i <= 420 {
+                (4.0, 150.0, 100.0)
+            } else {
+                (5.0, 150.0, 100.0)
+            }
+        });
+        for point in points.iter_mut().take(10) {
+            point.distance = None;
+        }
+        let efforts = best_efforts(&activity(&points));
+        assert_eq!(efforts[0].end_index, 500);
+        assert_eq!(efforts[0].start_index, 420);
+    }
+}
@@ -0,0 +1,176 @@
+// Module containing the grade adjusted pace (GAP), the flat ground speed that
+// would take the same effort as the recorded speed on the recorded slope.
+
+use crate::get_field_value;
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+
+// Half the length of the stretch of track the grade is measured over, meters.
+// Shorter stretches follow the altitude noise rather than the slope.
+const GRADE_HALF_WINDOW: f64 = 25.0;
+
+// Steepest grade the cost of running model was measured for.
+const MAX_GRADE: f64 = 0.45;
+
+// Energy cost of running in J/kg/m on a grade (rise over run), after
+// Minetti et al., "Energy cost of walking and running at extreme uphill and
+// downhill slopes", J Appl Physiol 2002.
+fn cost_of_running(grade: f64) -> f64 {
+    let i = grade.clamp(-MAX_GRADE, MAX_GRADE);
+    return 155.4 * i.powi(5) - 30.4 * i.powi(4) - 43.3 * i.powi(3)
+        + 46.3 * i.powi(2)
+        + 19.5 * i
+        + 3.6;
+}
+
+// Return the grade at each point, measured between the points about
+// GRADE_HALF_WINDOW meters before and after it.
+pub(crate) fn grades(distance: &[f64], altitude: &[f64]) -> Vec<f64> {
+    let mut result: Vec<f64> = Vec::with_capacity(distance.len());
+    let (mut lo, mut hi) = (0, 0);
+    for k in 0..distance.len() {
+        while distance[lo] < distance[k] - GRADE_HALF_WINDOW {
+            lo += 1;
+        }
+        while hi + 1 < distance.len() && distance[hi + 1] <= distance[k] + GRADE_HALF_WINDOW {
+            hi += 1;
+        }
+        let run = distance[hi] - distance[lo];
+        if run > 1.0 {
+            result.push((altitude[hi] - altitude[lo]) / run);
+        } else {
+            result.push(0.0);
+        }
+    }
+    return result;
+}
+
+// Return the index among the records and grade adjusted speed (m/s) of every
+// record with distance, speed and altitude, or an empty vector without them.
+// The values are taken from the same record so that records missing a field
+// do not shift the others.
+pub fn grade_adjusted_speeds(data: &Vec<FitDataRecord>) -> Vec<(usize, f64)> {
+    let (mut indices, mut distance, mut speed, mut altitude) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let records = data.iter().filter(|item| item.kind() == MesgNum::Record);
+    for (index, item) in records.enumerate() {
+        if let (Some(d), Some(s), Some(a)) = (
+            get_field_value(item, "distance"),
+            get_field_value(item, "enhanced_speed"),
+            get_field_value(item, "enhanced_altitude"),
+        ) {
+            indices.push(index);
+            distance.push(d);
+            speed.push(s);
+            altitude.push(a);
+        }
+    }
+    let flat_cost = cost_of_running(0.0);
+    return grades(&distance, &altitude)
+        .iter()
+        .zip(speed.iter())
+        .zip(indices)
+        .map(|((grade, speed), index)| (index, speed * cost_of_running(*grade) / flat_cost))
+        .collect();
+}
+
+// Return the grade adjusted average speed (m/s) of a session or lap message.
+// The recorded average speed is scaled by the ratio of the grade adjusted to
+// the recorded distance covered by the records between the message's start
+// time and timestamp, so that pauses are treated as the device treated them.
+pub fn message_grade_adjusted_speed(
+    data: &Vec<FitDataRecord>,
+    message: &FitDataRecord,
+) -> Option<f64> {
+    let start = get_field_value(message, "start_time")?;
+    let end = get_field_value(message, "timestamp")?;
+    let avg_speed =
+        get_field_value(message, "enhanced_avg_speed").or(get_field_value(message, "avg_speed"))?;
+    let records: Vec<&FitDataRecord> = data
+        .iter()
+        .filter(|item| item.kind() == MesgNum::Record)
+        .collect();
+    let (mut adjusted, mut recorded) = (0.0, 0.0);
+    for (index, gap) in grade_adjusted_speeds(data) {
+        // The duration of a record is the time since the previous record.
+        let (Some(time), Some(previous), Some(speed)) = (
+            get_field_value(records[index], "timestamp"),
+            index
+                .checked_sub(1)
+                .and_then(|i| get_field_value(records[i], "timestamp")),
+            get_field_value(records[index], "enhanced_speed"),
+        ) else {
+            continue;
+        };
+        if time <= start || time > end {
+            continue;
+        }
+        let dt = time - previous;
+        adjusted += gap * dt;
+        recorded += speed * dt;
+    }
+    if recorded <= 0.0 {
+        return None;
+    }
+    return Some(avg_speed * adjusted / recorded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    #[test]
+    fn grade_is_measured_across_the_window() {
+        let distance: Vec<f64> = (0..101).map(|i| i as f64).collect();
+        let altitude: Vec<f64> = distance.iter().map(|d| 100.0 + 0.1 * d).collect();
+        let result = grades(&distance, &altitude);
+        assert_eq!(result.len(), distance.len());
+        assert!(result.iter().all(|g| (g - 0.1).abs() < 1e-9));
+        assert_eq!(grades(&[0.0, 0.0], &[100.0, 110.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn flat_ground_keeps_the_speed() {
+        let speeds = grade_adjusted_speeds(&steady_run(101, 3.0, 150.0));
+        assert_eq!(speeds.len(), 101);
+        assert!(speeds.iter().all(|(_, s)| (s - 3.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn uphill_is_adjusted_up_and_downhill_down() {
+        let uphill = run_points(101, |i| (3.0, 150.0, 100.0 + 0.3 * i as f64));
+        let downhill = run_points(101, |i| (3.0, 150.0, 100.0 - 0.3 * i as f64));
+        let up = grade_adjusted_speeds(&activity(&uphill));
+        let down = grade_adjusted_speeds(&activity(&downhill));
+        assert!(up[50].1 > 3.0);
+        assert!(down[50].1 < 3.0);
+    }
+
+    #[test]
+    fn records_missing_a_field_keep_their_own_speed() {
+        // On flat ground each record's grade adjusted speed is its own speed.
+        let speed = |i: usize| 2.0 + 0.1 * i as f64;
+        let mut points = run_points(20, |i| (speed(i), 150.0, 100.0));
+        points[5].altitude = None;
+        let speeds = grade_adjusted_speeds(&activity(&points));
+        assert_eq!(speeds.len(), 19);
+        assert!(speeds.iter().all(|(index, _)| *index != 5));
+        for (index, gap) in speeds {
+            assert!((gap - speed(index)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn session_speed_is_scaled_by_the_adjustment() {
+        let data = steady_run(101, 3.0, 150.0);
+        let session = data.last().unwrap();
+        let speed = message_grade_adjusted_speed(&data, session).unwrap();
+        assert!((speed - 3.0).abs() < 1e-9);
+        // A record without altitude leaves the others as they are.
+        let mut points = run_points(101, |_| (3.0, 150.0, 100.0));
+        points[50].altitude = None;
+        let data = activity(&points);
+        let speed = message_grade_adjusted_speed(&data, data.last().unwrap()).unwrap();
+        assert!((speed - 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,284 @@
+// Module containing GPX (GPS Exchange Format) 1.1 export of the recorded track,
+// and import of GPX tracks as viewable activities.
+
+use crate::records::{
+    LapValues, TrackPoint, derive_distance_and_speed, lap_message, record_message, session_message,
+};
+use crate::xml::{child, descendant, number, parse_time};
+use crate::{get_field_value, semi_to_degrees};
+use chrono::DateTime;
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+use roxmltree::Node;
+use std::error::Error;
+
+const GPX_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="SiliconSneaker2"
+  xmlns="http://www.topografix.com/GPX/1/1"
+  xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v2"
+  xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+  xsi:schemaLocation="http://www.topografix.com/GPX/1/1 http://www.topografix.com/GPX/1/1/gpx.xsd http://www.garmin.com/xmlschemas/TrackPointExtension/v2 http://www.garmin.com/xmlschemas/TrackPointExtensionv2.xsd">
+"#;
+
+// Format seconds since the Unix epoch as an ISO 8601 UTC time.
+fn format_gpx_time(epoch_secs: f64) -> Option<String> {
+    let date_time = DateTime::from_timestamp(epoch_secs as i64, 0)?;
+    return Some(date_time.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+}
+
+// Build a GPX 1.1 document with a trkpt for every record containing a position.
+// Heart rate, cadence and temperature are written to the Garmin TrackPointExtension.
+pub fn fit_to_gpx(data: &Vec<FitDataRecord>) -> String {
+    let mut gpx = String::from(GPX_HEADER);
+    let mut start_time = String::new();
+    let mut points = String::new();
+    for item in data {
+        if item.kind() != MesgNum::Record {
+            continue;
+        }
+        let (Some(lat), Some(lon)) = (
+            get_field_value(item, "position_lat"),
+            get_field_value(item, "position_long"),
+        ) else {
+            continue;
+        };
+        points.push_str(&format!(
+            "      <trkpt lat=\"{:.7}\" lon=\"{:.7}\">\n",
+            semi_to_degrees(lat),
+            semi_to_degrees(lon)
+        ));
+        let elevation =
+            get_field_value(item, "enhanced_altitude").or(get_field_value(item, "altitude"));
+        if let Some(ele) = elevation {
+            points.push_str(&format!("        <ele>{:.1}</ele>\n", ele));
+        }
+        if let Some(time) = get_field_value(item, "timestamp").and_then(format_gpx_time) {
+            if start_time.is_empty() {
+                start_time = time.clone();
+            }
+            points.push_str(&format!("        <time>{}</time>\n", time));
+        }
+        let mut extension = String::new();
+        if let Some(temperature) = get_field_value(item, "temperature") {
+            extension.push_str(&format!(
+                "            <gpxtpx:atemp>{:.1}</gpxtpx:atemp>\n",
+                temperature
+            ));
+        }
+        if let Some(heart_rate) = get_field_value(item, "heart_rate") {
+            extension.push_str(&format!(
+                "            <gpxtpx:hr>{}</gpxtpx:hr>\n",
+                heart_rate as u32
+            ));
+        }
+        if let Some(cadence) = get_field_value(item, "cadence") {
+            extension.push_str(&format!(
+                "            <gpxtpx:cad>{}</gpxtpx:cad>\n",
+                cadence as u32
+            ));
+        }
+        if !extension.is_empty() {
+            points.push_str("        <extensions>\n");
+            points.push_str("          <gpxtpx:TrackPointExtension>\n");
+            points.push_str(&extension);
+            points.push_str("          </gpxtpx:TrackPointExtension>\n");
+            points.push_str("        </extensions>\n");
+        }
+        points.push_str("      </trkpt>\n");
+    }
+    if !start_time.is_empty() {
+        gpx.push_str(&format!(
+            "  <metadata>\n    <time>{}</time>\n  </metadata>\n",
+            start_time
+        ));
+    }
+    gpx.push_str("  <trk>\n");
+    if !start_time.is_empty() {
+        gpx.push_str(&format!("    <name>{}</name>\n", start_time));
+    }
+    gpx.push_str("    <trkseg>\n");
+    gpx.push_str(&points);
+    gpx.push_str("    </trkseg>\n");
+    gpx.push_str("  </trk>\n");
+    gpx.push_str("</gpx>\n");
+    return gpx;
+}
+
+// Read the values of a single trkpt element, including the Garmin
+// TrackPointExtension values when present.
+fn parse_trkpt(node: Node) -> TrackPoint {
+    return TrackPoint {
+        time: child(node, "time")
+            .and_then(|n| n.text())
+            .and_then(parse_time),
+        latitude: node.attribute("lat").and_then(|v| v.trim().parse().ok()),
+        longitude: node.attribute("lon").and_then(|v| v.trim().parse().ok()),
+        altitude: number(child(node, "ele")),
+        distance: None,
+        speed: None,
+        heart_rate: number(descendant(node, "hr")),
+        cadence: number(descendant(node, "cad")),
+        temperature: number(descendant(node, "atemp")),
+    };
+}
+
+// Convert a GPX document into the records, lap and session of a FIT activity.
+// Distance, speed and the summaries are derived from the positions and times.
+pub fn parse_gpx(text: &str) -> Result<Vec<FitDataRecord>, Box<dyn Error>> {
+    let doc = roxmltree::Document::parse(text)?;
+    let track = descendant(doc.root(), "trk").ok_or("No track in GPX file.")?;
+    let sport = match child(track, "type").and_then(|n| n.text()) {
+        Some(kind) => kind.trim().to_lowercase(),
+        None => String::from("generic"),
+    };
+    let mut points: Vec<TrackPoint> = doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "trkpt")
+        .map(parse_trkpt)
+        .collect();
+    if points.is_empty() {
+        return Err("No track points in GPX file.".into());
+    }
+    derive_distance_and_speed(&mut points);
+    let mut data: Vec<FitDataRecord> = points.iter().map(record_message).collect();
+    // GPX has no laps; the whole track is a single lap.
+    let recorded = LapValues::default();
+    data.push(lap_message(&points, &sport, &recorded));
+    data.push(session_message(&points, &sport, 1, &recorded));
+    return Ok(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_sess_record_field;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1"
+  xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v2">
+  <trk>
+    <type>Running</type>
+    <trkseg>
+      <trkpt lat="45.0" lon="7.0">
+        <ele>250.0</ele>
+        <time>2024-05-01T08:00:00Z</time>
+        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>140</gpxtpx:hr><gpxtpx:cad>85</gpxtpx:cad></gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+      <trkpt lat="45.0" lon="7.0">
+        <ele>251.0</ele>
+        <time>2024-05-01T08:00:10Z</time>
+      </trkpt>
+      <trkpt lat="45.00027" lon="7.0">
+        <ele>252.0</ele>
+        <time>2024-05-01T08:00:20Z</time>
+        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>150</gpxtpx:hr><gpxtpx:atemp>18.0</gpxtpx:atemp></gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn distance_and_speed_are_derived_from_the_positions() {
+        let data = parse_gpx(GPX).unwrap();
+        let records: Vec<&FitDataRecord> = data
+            .iter()
+            .filter(|item| item.kind() == MesgNum::Record)
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(get_field_value(records[1], "distance"), Some(0.0));
+        let distance = get_field_value(records[2], "distance").unwrap();
+        assert!((distance - 30.0).abs() < 0.1);
+        let speed = get_field_value(records[2], "enhanced_speed").unwrap();
+        // Speed is measured over the ten seconds before the point.
+        assert!((speed - 3.0).abs() < 0.01);
+        assert_eq!(get_field_value(records[0], "heart_rate"), Some(140.0));
+        assert_eq!(get_field_value(records[1], "heart_rate"), None);
+        assert_eq!(get_field_value(records[2], "temperature"), Some(18.0));
+    }
+
+    #[test]
+    fn track_is_a_single_lap_of_its_type() {
+        let data = parse_gpx(GPX).unwrap();
+        let laps = data
+            .iter()
+            .filter(|item| item.kind() == MesgNum::Lap)
+            .count();
+        assert_eq!(laps, 1);
+        assert_eq!(get_sess_record_field(&data, "total_elapsed_time"), 20.0);
+        assert_eq!(get_sess_record_field(&data, "total_ascent"), 2.0);
+        let session = data.last().unwrap();
+        let sport = session
+            .fields()
+            .iter()
+            .find(|f| f.name() == "sport")
+            .unwrap();
+        assert_eq!(sport.value().to_string(), "running");
+    }
+
+    #[test]
+    fn files_without_track_points_are_refused() {
+        assert!(parse_gpx(r#"<gpx version="1.1"><trk><trkseg/></trk></gpx>"#).is_err());
+        assert!(parse_gpx(r#"<gpx version="1.1"/>"#).is_err());
+    }
+
+    #[test]
+    fn every_positioned_record_is_a_track_point() {
+        let gpx = fit_to_gpx(&steady_run(11, 3.0, 150.0));
+        assert_eq!(gpx.matches("<trkpt ").count(), 11);
+        assert!(gpx.contains("<trkpt lat=\"0.0000000\" lon=\"0.0000000\">"));
+        // 30 m north of the start.
+        assert!(gpx.contains("<trkpt lat=\"0.0002698\" lon=\"0.0000000\">"));
+        assert!(gpx.contains("<ele>100.0</ele>"));
+        assert_eq!(gpx.matches("<gpxtpx:hr>150</gpxtpx:hr>").count(), 11);
+        assert_eq!(gpx.matches("<gpxtpx:cad>85</gpxtpx:cad>").count(), 11);
+    }
+
+    #[test]
+    fn track_is_named_after_its_start_time() {
+        let gpx = fit_to_gpx(&steady_run(3, 3.0, 150.0));
+        assert!(gpx.contains("<metadata>\n    <time>2024-05-01T08:00:00Z</time>"));
+        assert!(gpx.contains("<name>2024-05-01T08:00:00Z</name>"));
+        assert!(gpx.contains("<time>2024-05-01T08:00:02Z</time>"));
+        assert!(gpx.ends_with("</trkseg>\n  </trk>\n</gpx>\n"));
+    }
+
+    #[test]
+    fn records_without_a_position_are_skipped() {
+        let mut points = run_points(3, |_| (3.0, 150.0, 100.0));
+        points[1].latitude = None;
+        let gpx = fit_to_gpx(&activity(&points));
+        assert_eq!(gpx.matches("<trkpt ").count(), 2);
+        assert!(!fit_to_gpx(&Vec::new()).contains("<metadata>"));
+    }
+
+    #[test]
+    fn exported_track_reads_back() {
+        let data = steady_run(11, 3.0, 150.0);
+        let gpx = fit_to_gpx(&data);
+        assert!(gpx.contains("<time>2024-05-01T08:00:00Z</time>"));
+        let read = parse_gpx(&gpx).unwrap();
+        let original: Vec<&FitDataRecord> = data
+            .iter()
+            .filter(|item| item.kind() == MesgNum::Record)
+            .collect();
+        let copies: Vec<&FitDataRecord> = read
+            .iter()
+            .filter(|item| item.kind() == MesgNum::Record)
+            .collect();
+        assert_eq!(copies.len(), original.len());
+        for (copy, item) in copies.iter().zip(original.iter()) {
+            for field in ["timestamp", "heart_rate", "cadence"] {
+                assert_eq!(get_field_value(copy, field), get_field_value(item, field));
+            }
+            // Positions are written to seven decimals of a degree, about a
+            // semicircle.
+            for field in ["position_lat", "position_long"] {
+                let copied = get_field_value(copy, field).unwrap();
+                let recorded = get_field_value(item, field).unwrap();
+                assert!((copied - recorded).abs() <= 2.0);
+            }
+        }
+        let distance = get_sess_record_field(&read, "total_distance");
+        assert!((distance - 30.0).abs() < 0.1);
+    }
+}
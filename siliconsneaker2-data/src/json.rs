@@ -0,0 +1,279 @@
+// Module containing the JSON export of the session, lap and time in zone values.
+// Every number is written together with the units it is expressed in.
+
+use crate::zones::{HeartRateZones, heart_rate_time_in_zone, heart_rate_zone_top};
+use crate::{
+    Units, cvt_altitude, cvt_distance, cvt_pace, cvt_speed, cvt_temperature, extract_f64,
+    hr_zone_bounds, semi_to_degrees,
+};
+use fitparser::{FitDataField, FitDataRecord, Value, profile::field_types::MesgNum};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// A number and the units it is expressed in.
+#[derive(Serialize)]
+pub struct Quantity {
+    pub value: f64,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub units: String,
+}
+
+// A session or lap field, either a number with units or a plain value such as
+// the sport name or a timestamp.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum JsonField {
+    Quantity(Quantity),
+    Value(Value),
+}
+
+// Time spent in one heart rate zone.
+#[derive(Serialize)]
+pub struct HeartRateZone {
+    pub zone: usize,
+    pub low: Quantity,
+    pub high: Quantity,
+    pub time: Quantity,
+}
+
+// The document written by fit_to_json.
+#[derive(Serialize)]
+pub struct ActivityJson {
+    pub unit_system: String,
+    pub session: Option<BTreeMap<String, JsonField>>,
+    pub laps: Vec<BTreeMap<String, JsonField>>,
+    pub time_in_zone: Vec<HeartRateZone>,
+}
+
+// Build a Quantity from a value and a unit name.
+fn quantity(value: f64, units: &str) -> JsonField {
+    return JsonField::Quantity(Quantity {
+        value,
+        units: units.to_string(),
+    });
+}
+
+// Return the name of the unit system.
+fn unit_system_name(user_unit: &Units) -> String {
+    match user_unit {
+        Units::US => return String::from("us"),
+        Units::Metric => return String::from("metric"),
+        Units::None => return String::from("none"),
+    }
+}
+
+// Convert a numeric field to user units and add it to the message fields.
+// Speeds are additionally written as a pace.
+fn convert_field(
+    fld: &FitDataField,
+    val: f64,
+    user_unit: &Units,
+    fields: &mut BTreeMap<String, JsonField>,
+) {
+    let name = fld.name();
+    let (distance_unit, altitude_unit, speed_unit, pace_unit, temperature_unit) = match user_unit {
+        Units::US => ("mi", "ft", "mph", "min/mi", "°F"),
+        Units::Metric => ("km", "m", "km/h", "min/km", "°C"),
+        Units::None => ("m", "m", "m/s", "", "°C"),
+    };
+    if fld.units() == "semicircles" {
+        fields.insert(name.to_string(), quantity(semi_to_degrees(val), "deg"));
+    } else if name == "total_distance" {
+        let val_cvt = cvt_distance(val as f32, user_unit) as f64;
+        fields.insert(name.to_string(), quantity(val_cvt, distance_unit));
+    } else if name == "total_ascent" || name == "total_descent" || name.ends_with("_altitude") {
+        let val_cvt = cvt_altitude(val as f32, user_unit) as f64;
+        fields.insert(name.to_string(), quantity(val_cvt, altitude_unit));
+    } else if name.ends_with("_speed") {
+        let val_cvt = cvt_speed(val as f32, user_unit) as f64;
+        fields.insert(name.to_string(), quantity(val_cvt, speed_unit));
+        if !pace_unit.is_empty() {
+            let pace = cvt_pace(val as f32, user_unit) as f64;
+            fields.insert(name.replace("_speed", "_pace"), quantity(pace, pace_unit));
+        }
+    } else if name.ends_with("_temperature") {
+        let val_cvt = cvt_temperature(val as f32, user_unit) as f64;
+        fields.insert(name.to_string(), quantity(val_cvt, temperature_unit));
+    } else {
+        fields.insert(name.to_string(), quantity(val, fld.units()));
+    }
+}
+
+// Collect the known fields of a session or lap message.
+fn message_fields(item: &FitDataRecord, user_unit: &Units) -> BTreeMap<String, JsonField> {
+    let mut fields: BTreeMap<String, JsonField> = BTreeMap::new();
+    for fld in item.fields().iter() {
+        if fld.name().starts_with("unknown_field") {
+            continue;
+        }
+        match extract_f64(fld.value()) {
+            Some(val) => convert_field(fld, val, user_unit, &mut fields),
+            None => {
+                fields.insert(
+                    fld.name().to_string(),
+                    JsonField::Value(fld.value().clone()),
+                );
+            }
+        }
+    }
+    return fields;
+}
+
+// Build the JSON document from the session, lap and time_in_zone messages.
+pub fn build_activity_json(
+    data: &Vec<FitDataRecord>,
+    user_unit: &Units,
+    hr_zones: &HeartRateZones,
+) -> ActivityJson {
+    let mut activity = ActivityJson {
+        unit_system: unit_system_name(user_unit),
+        session: None,
+        laps: Vec::new(),
+        time_in_zone: Vec::new(),
+    };
+    for item in data {
+        match item.kind() {
+            MesgNum::Session => {
+                if activity.session.is_none() {
+                    activity.session = Some(message_fields(item, user_unit));
+                }
+            }
+            MesgNum::Lap => activity.laps.push(message_fields(item, user_unit)),
+            _ => (), // matches other patterns
+        }
+    }
+    if let Some((zone_times, zone_limits)) = heart_rate_time_in_zone(data, hr_zones) {
+        let top_heart_rate = heart_rate_zone_top(data, hr_zones);
+        for (z, val) in zone_times.iter().enumerate() {
+            let (ll, ul) = hr_zone_bounds(&zone_limits, z, top_heart_rate);
+            activity.time_in_zone.push(HeartRateZone {
+                zone: z,
+                low: Quantity {
+                    value: ll,
+                    units: String::from("bpm"),
+                },
+                high: Quantity {
+                    value: ul,
+                    units: String::from("bpm"),
+                },
+                time: Quantity {
+                    value: *val,
+                    units: String::from("s"),
+                },
+            });
+        }
+    }
+    return activity;
+}
+
+// Write the session, laps and time in zone values as a JSON document.
+pub fn fit_to_json(
+    data: &Vec<FitDataRecord>,
+    user_unit: &Units,
+    hr_zones: &HeartRateZones,
+) -> String {
+    let activity = build_activity_json(data, user_unit, hr_zones);
+    return serde_json::to_string_pretty(&activity).unwrap_or_default() + "\n";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::steady_run;
+
+    // Return a time_in_zone message for the session with the time spent in
+    // each zone and the upper limits of all but the last zone.
+    fn time_in_zone(times: &[f64], limits: &[u8]) -> FitDataRecord {
+        let mut record = FitDataRecord::new(MesgNum::TimeInZone);
+        let fields = [
+            ("reference_mesg", Value::String(String::from("session"))),
+            ("reference_index", Value::UInt16(0)),
+            (
+                "time_in_hr_zone",
+                Value::Array(times.iter().map(|t| Value::Float64(*t)).collect()),
+            ),
+            (
+                "hr_zone_high_boundary",
+                Value::Array(limits.iter().map(|l| Value::UInt8(*l)).collect()),
+            ),
+        ];
+        for (name, value) in fields {
+            record.push(FitDataField::new(
+                name.to_string(),
+                0,
+                None,
+                value,
+                String::new(),
+            ));
+        }
+        return record;
+    }
+
+    // Parse the document written for the data.
+    fn written(data: &Vec<FitDataRecord>, user_unit: &Units) -> serde_json::Value {
+        let zones = HeartRateZones::default();
+        return serde_json::from_str(&fit_to_json(data, user_unit, &zones)).unwrap();
+    }
+
+    #[test]
+    fn session_values_carry_their_units() {
+        let json = written(&steady_run(101, 2.5, 150.0), &Units::Metric);
+        assert_eq!(json["unit_system"], "metric");
+        let session = &json["session"];
+        assert_eq!(session["total_distance"]["units"], "km");
+        let distance = session["total_distance"]["value"].as_f64().unwrap();
+        assert!((distance - 0.25).abs() < 1e-6);
+        assert_eq!(session["enhanced_avg_speed"]["units"], "km/h");
+        let pace = session["enhanced_avg_pace"]["value"].as_f64().unwrap();
+        assert!((pace - 6.6667).abs() < 1e-3);
+        assert_eq!(session["avg_heart_rate"]["value"], 150.0);
+        assert_eq!(session["avg_heart_rate"]["units"], "bpm");
+        assert_eq!(session["start_position_lat"]["units"], "deg");
+        assert_eq!(session["sport"], "running");
+    }
+
+    #[test]
+    fn unitless_values_have_no_pace() {
+        let json = written(&steady_run(11, 2.5, 150.0), &Units::None);
+        let session = &json["session"];
+        assert_eq!(session["total_distance"]["units"], "m");
+        assert_eq!(session["enhanced_avg_speed"]["units"], "m/s");
+        assert!(session.get("enhanced_avg_pace").is_none());
+    }
+
+    #[test]
+    fn every_lap_is_written() {
+        let mut data = steady_run(11, 2.5, 150.0);
+        let lap = data[data.len() - 2].clone();
+        data.insert(0, lap);
+        let json = written(&data, &Units::US);
+        assert_eq!(json["laps"].as_array().unwrap().len(), 2);
+        assert_eq!(json["laps"][1]["total_distance"]["units"], "mi");
+    }
+
+    #[test]
+    fn zones_span_the_heart_rate_limits() {
+        let mut data = steady_run(11, 2.5, 150.0);
+        data.push(time_in_zone(&[60.0, 300.0, 120.0], &[120, 160]));
+        let json = written(&data, &Units::Metric);
+        let zones = json["time_in_zone"].as_array().unwrap();
+        assert_eq!(zones.len(), 3);
+        assert_eq!(zones[0]["low"]["value"], 0.0);
+        assert_eq!(zones[1]["low"]["value"], 120.0);
+        assert_eq!(zones[1]["high"]["value"], 160.0);
+        assert_eq!(zones[1]["time"]["value"], 300.0);
+        assert_eq!(zones[1]["time"]["units"], "s");
+        // The maximum of the device's zones is not known.
+        assert_eq!(zones[2]["high"]["value"], 220.0);
+    }
+
+    #[test]
+    fn zones_are_computed_without_a_time_in_zone_message() {
+        let json = written(&steady_run(11, 2.5, 150.0), &Units::Metric);
+        let zones = json["time_in_zone"].as_array().unwrap();
+        assert_eq!(zones.len(), 6);
+        // 150 bpm is zone 3 of a 190 bpm maximum.
+        assert_eq!(zones[3]["time"]["value"], 10.0);
+        assert_eq!(zones[5]["high"]["value"], 190.0);
+    }
+}
@@ -0,0 +1,935 @@
+// Library containing the FIT data extraction, conversion and processing logic.
+// It has no GTK dependency so it may be used by other tools and run headless.
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use fitparser::{FitDataRecord, Value, profile::field_types::MesgNum};
+use std::error::Error;
+use std::io::Read;
+
+pub mod csv;
+pub mod decoupling;
+pub mod efforts;
+pub mod gap;
+pub mod gpx;
+pub mod json;
+pub mod library;
+pub mod mbtiles;
+pub mod records;
+pub mod smoothing;
+pub mod splits;
+pub mod tcx;
+#[cfg(test)]
+mod test_data;
+pub mod tiles;
+pub mod track;
+pub mod training_load;
+pub mod volume;
+mod xml;
+pub mod zones;
+
+// Unit of measure system.
+#[derive(Clone, Copy)]
+pub enum Units {
+    Metric,
+    US,
+    None,
+}
+
+//Useful values for plotting a graph. */
+pub struct GraphAttributes {
+    pub field: String, // record field plotted on the y axis
+    pub plotvals: Vec<(f32, f32)>,
+    pub caption: String,
+    pub xlabel: String,
+    pub ylabel: String,
+    pub plot_range: (std::ops::Range<f32>, std::ops::Range<f32>),
+    pub y_formatter: Box<dyn Fn(&f32) -> String>,
+    pub x_formatter: Option<Box<dyn Fn(&f32) -> String>>, // None keeps the default labels
+    pub overlay: Vec<(f32, f32)>, // derived series drawn over plotvals, may be empty
+    pub overlay_label: String,
+    pub bands: Vec<(f32, f32)>, // lower and upper y of each zone shaded behind plotvals
+}
+
+// In memory cache to speed up redraws.
+pub struct GraphCache {
+    pub graphs: Vec<GraphAttributes>, // in the order the user chose
+    pub time_stamps: Vec<NaiveDateTime>,
+    pub record_x: Vec<Option<f32>>, // x value of every record, see get_record_x_values
+}
+
+// In memory cache to speed up redraws.
+pub struct MapCache {
+    pub run_path: Vec<(f64, f64)>,
+    pub record_points: Vec<Option<usize>>, // index into run_path of every record
+}
+
+// Calculate the vector mean and standard deviation.
+fn mean_and_standard_deviation(data: &Vec<f32>) -> (Option<f32>, Option<f32>) {
+    let count = data.len();
+    // Handle empty data case to prevent division by zero.
+    if count == 0 {
+        return (None, None);
+    }
+    // Calculate the mean (average).
+    let sum: f32 = data.iter().sum();
+    let mean = sum / (count as f32);
+    // Calculate the variance.
+    // Variance is the average of the squared differences from the Mean.
+    let squared_differences_sum: f32 = data
+        .iter()
+        // Map each element to its squared difference from the mean
+        .map(|&x| {
+            let diff = x - mean;
+            diff * diff
+        })
+        // Sum all the squared differences
+        .sum();
+    let variance = squared_differences_sum / (count as f32);
+    // Standard deviation is the square root of the variance.
+    return (Some(mean), Some(variance.sqrt()));
+}
+
+// Find the largest non-NaN in vector, or NaN otherwise.
+fn max_vec(vector: &Vec<f32>) -> f32 {
+    let v = vector.iter().copied().fold(f32::NAN, f32::max);
+    return v;
+}
+
+// Find the largest non-NaN in vector, or NaN otherwise.
+fn min_vec(vector: &Vec<f32>) -> f32 {
+    let v = vector.iter().copied().fold(f32::NAN, f32::min);
+    return v;
+}
+
+// Decode an activity file.  The format (FIT, TCX or GPX) is recognized from the
+// content so files with a missing or wrong extension still open.
+pub fn read_activity<T: Read>(source: &mut T) -> Result<Vec<FitDataRecord>, Box<dyn Error>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    source.read_to_end(&mut bytes)?;
+    // A FIT header carries ".FIT" at bytes 8-11.
+    if bytes.len() >= 12 && &bytes[8..12] == b".FIT" {
+        return Ok(fitparser::from_bytes(&bytes)?);
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    if text.contains("TrainingCenterDatabase") {
+        return tcx::parse_tcx(&text);
+    }
+    if text.contains("<gpx") {
+        return gpx::parse_gpx(&text);
+    }
+    return Ok(fitparser::from_bytes(&bytes)?);
+}
+
+// Percentage of the values at either end of a smoothed series left out of
+// the plot range, see set_filtered_plot_range.
+const PLOT_RANGE_PERCENTILE: f32 = 1.0;
+
+// Find the plot range values.
+pub fn set_plot_range(
+    data: &Vec<(f32, f32)>,
+    zoom_x: f32,
+    zoom_y: f32,
+) -> (std::ops::Range<f32>, std::ops::Range<f32>) {
+    if data.len() == 0 {
+        return (0.0..0.0, 0.0..0.0);
+    };
+    if (zoom_x < 0.01) | (zoom_y < 0.01) {
+        panic!("Invalid zoom.")
+    }
+    // Split vector of tuples into two vecs
+    let (x, y): (Vec<_>, Vec<_>) = data.iter().map(|(a, b)| (a, b)).unzip();
+    // Find the range of the chart, statistics says 95% should lie between +/3 sigma
+    // for a normal distribution.  Let's go with that for the range.
+    // Disallow zero, negative values of zoom.
+    let xrange: std::ops::Range<f32> = min_vec(&x)..1.0 / zoom_x * max_vec(&x);
+    if let (Some(mean_y), Some(sigma_y)) = mean_and_standard_deviation(&y) {
+        let yrange: std::ops::Range<f32> =
+            mean_y - 2.0 / zoom_y * sigma_y..mean_y + 2.0 / zoom_y * sigma_y;
+        return (xrange, yrange);
+    } else {
+        panic!("Could not determine ranges. Reason unknown.")
+    }
+}
+
+// Find the plot range values of a smoothed or despiked series.  Its y range
+// spans the values with the extreme PLOT_RANGE_PERCENTILE percent left out at
+// either end, rather than mean +/- 2 sigma, so that real peaks such as sprints
+// stay on the graph.
+pub fn set_filtered_plot_range(
+    data: &Vec<(f32, f32)>,
+    zoom_x: f32,
+    zoom_y: f32,
+) -> (std::ops::Range<f32>, std::ops::Range<f32>) {
+    if data.len() == 0 {
+        return (0.0..0.0, 0.0..0.0);
+    };
+    if (zoom_x < 0.01) | (zoom_y < 0.01) {
+        panic!("Invalid zoom.")
+    }
+    let (x, mut y): (Vec<f32>, Vec<f32>) = data.iter().cloned().unzip();
+    let xrange: std::ops::Range<f32> = min_vec(&x)..1.0 / zoom_x * max_vec(&x);
+    y.sort_by(|a, b| a.total_cmp(b));
+    let cut = (y.len() as f32 * PLOT_RANGE_PERCENTILE / 100.0) as usize;
+    let (low, high) = (y[cut], y[y.len() - 1 - cut]);
+    // Zoom about the middle of the range, with a margin so the peaks show.
+    let middle = (low + high) / 2.0;
+    let half = ((high - low) / 2.0 * 1.1).max(0.5) / zoom_y;
+    return (xrange, middle - half..middle + half);
+}
+
+// Convert speed (m/s) to pace(min/mile, min/km).
+pub fn cvt_pace(speed: f32, units: &Units) -> f32 {
+    match units {
+        Units::US => {
+            if speed < 1.00 {
+                return 26.8224; //avoid divide by zero
+            } else {
+                return 26.8224 / speed;
+            }
+        }
+        Units::Metric => {
+            if speed < 1.00 {
+                return 16.666667; //avoid divide by zero
+            } else {
+                return 16.666667 / speed;
+            }
+        }
+        Units::None => {
+            return speed;
+        }
+    }
+}
+
+// Convert speed (m/s) to mph, km/h.
+pub fn cvt_speed(speed: f32, units: &Units) -> f32 {
+    match units {
+        Units::US => {
+            return speed * 2.2369363;
+        }
+        Units::Metric => {
+            return speed * 3.6;
+        }
+        Units::None => {
+            return speed;
+        }
+    }
+}
+
+// Convert distance meters to miles, km.
+pub fn cvt_distance(distance: f32, units: &Units) -> f32 {
+    match units {
+        Units::US => {
+            return distance * 0.00062137119;
+        }
+        Units::Metric => {
+            return distance * 0.001;
+        }
+        Units::None => {
+            return distance;
+        }
+    }
+}
+
+// Convert altitude meters to feet, m.
+pub fn cvt_altitude(altitude: f32, units: &Units) -> f32 {
+    match units {
+        Units::US => {
+            return altitude * 3.2808399;
+        }
+        Units::Metric => {
+            return altitude * 1.0;
+        }
+        Units::None => {
+            return altitude;
+        }
+    }
+}
+
+// Convert temperature deg C  to deg F, deg C.
+pub fn cvt_temperature(temperature: f32, units: &Units) -> f32 {
+    match units {
+        Units::US => {
+            return temperature * 1.8 + 32.0;
+        }
+        Units::Metric => {
+            return temperature * 1.0;
+        }
+        Units::None => {
+            return temperature;
+        }
+    }
+}
+
+// Convert semi-circles to degrees.  The conversion is done in f64 as f32 only
+// holds positions to about a meter.
+pub fn semi_to_degrees(semi: f64) -> f64 {
+    let factor: f64 = 2i64.pow(31u32) as f64;
+    let deg_val: f64 = semi * 180f64 / factor;
+    return deg_val;
+}
+
+// Convert elapsed time in secs to hr,:min,sec.
+pub fn cvt_elapsed_time(time_in_sec: f32) -> (i32, i32, i32) {
+    let t = time_in_sec / 3600.0;
+    let hr = t.trunc();
+    let minsec = t.fract() * 60.0;
+    let min = minsec.trunc();
+    let s = minsec.fract() * 60.0;
+    let sec = s.trunc();
+    return (hr as i32, min as i32, sec as i32);
+}
+
+// Retrieve converted values to plot from fit file.
+pub fn get_xy(
+    data: &Vec<FitDataRecord>,
+    user_unit: &Units,
+    x_field_name: &str,
+    y_field_name: &str,
+) -> Vec<(f32, f32)> {
+    let mut x_user: Vec<f32> = Vec::new();
+    let mut y_user: Vec<f32> = Vec::new();
+    let mut xy_pairs: Vec<(f32, f32)> = Vec::new();
+    // Parameter can be distance, heart_rate, enhanced_speed, enhanced_altitude.
+    // The x parameter may also be elapsed_time or clock_time (see get_time_axis_as_vec).
+    let x: Vec<f64> = match x_field_name {
+        "elapsed_time" => get_time_axis_as_vec(&data, false),
+        "clock_time" => get_time_axis_as_vec(&data, true),
+        _ => get_msg_record_field_as_vec(&data, x_field_name),
+    };
+    // The y parameter may also be efficiency_factor (see decoupling.rs) or
+    // grade_adjusted_speed (see gap.rs).  These are only had for the records
+    // with the fields they are computed from, so each is paired with the x
+    // value of its own record.
+    if y_field_name == "efficiency_factor" || y_field_name == "grade_adjusted_speed" {
+        let x_values = get_record_x_values(&data, user_unit, x_field_name);
+        let y_values = match y_field_name {
+            "efficiency_factor" => decoupling::efficiency_factors(&data),
+            _ => gap::grade_adjusted_speeds(&data),
+        };
+        return y_values
+            .iter()
+            .filter_map(|(index, y)| {
+                let x = (*x_values.get(*index)?)?;
+                match y_field_name {
+                    "grade_adjusted_speed" => return Some((x, cvt_pace(*y as f32, user_unit))),
+                    _ => return Some((x, *y as f32)),
+                }
+            })
+            .collect();
+    }
+    let y: Vec<f64> = get_msg_record_field_as_vec(&data, y_field_name);
+    //  Convert values to 32 bit and create a tuple.
+    // Occasionally we see off by one errors in the data.
+    // If true, Chop the last one. Must be careful comparing usize values.
+    let mut data_range = 0..0;
+    if x.len() != 0 {
+        data_range = 0..x.len() - 1;
+    }
+    if x.len() > y.len() && (x.len() != 0) && (y.len() != 0) {
+        data_range = 0..y.len() - 1;
+    }
+    // Doing this in a single pass is less expensive.
+    if (x.len() != 0) && (y.len() != 0) {
+        for index in data_range.clone() {
+            match x_field_name {
+                "distance" => {
+                    x_user.push(cvt_distance(x[index] as f32, user_unit));
+                }
+                _ => {
+                    x_user.push(x[index] as f32);
+                }
+            }
+            match y_field_name {
+                "enhanced_speed" => {
+                    y_user.push(cvt_pace(y[index] as f32, user_unit));
+                }
+                "enhanced_altitude" => {
+                    y_user.push(cvt_altitude(y[index] as f32, user_unit));
+                }
+                "temperature" => {
+                    y_user.push(cvt_temperature(y[index] as f32, user_unit));
+                }
+                _ => {
+                    y_user.push(y[index] as f32);
+                }
+            }
+        }
+    }
+    if (x_user.len() != 0) && (y_user.len() != 0) {
+        for index in data_range.clone() {
+            xy_pairs.push((x_user[index], y_user[index]));
+        }
+    }
+    return xy_pairs;
+}
+
+// Return a session values of "field_name".
+pub fn get_sess_record_field(data: &Vec<FitDataRecord>, field_name: &str) -> f64 {
+    for item in data {
+        match item.kind() {
+            // Individual msgnum::records
+            MesgNum::Session => {
+                // Retrieve the FitDataField struct.
+                for fld in item.fields().iter() {
+                    if fld.name() == field_name {
+                        return fld.value().clone().try_into().unwrap();
+                    }
+                }
+            }
+            _ => (), // matches other patterns
+        }
+    }
+    return f64::NAN;
+}
+
+// Return a vector of values of "field_name".
+pub(crate) fn get_msg_record_field_as_vec(data: &Vec<FitDataRecord>, field_name: &str) -> Vec<f64> {
+    let mut field_vals: Vec<f64> = Vec::new();
+    for item in data {
+        match item.kind() {
+            // Individual msgnum::records
+            MesgNum::Record => {
+                // Retrieve the FitDataField struct.
+                for fld in item.fields().iter() {
+                    // Values that are not numbers, e.g. of an enum field
+                    // graphed by the user, are skipped.
+                    if fld.name() == field_name
+                        && let Ok(v64) = fld.value().clone().try_into()
+                    {
+                        field_vals.push(v64);
+                    }
+                }
+            }
+            _ => (), // matches other patterns
+        }
+    }
+    return field_vals;
+}
+
+// Return the names and units of the numeric record fields in the data, in the
+// order they first appear.  Timestamps, positions, distance and undocumented
+// fields are left out as they are not worth a graph of their own.
+pub fn get_record_numeric_fields(data: &Vec<FitDataRecord>) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for item in data {
+        if item.kind() != MesgNum::Record {
+            continue;
+        }
+        for fld in item.fields().iter() {
+            let name = fld.name();
+            if name == "distance"
+                || name.starts_with("position_")
+                || name.starts_with("unknown_field")
+                || extract_f64(fld.value()).is_none()
+                || fields.iter().any(|f| f.0 == name)
+            {
+                continue;
+            }
+            fields.push((name.to_string(), fld.units().to_string()));
+        }
+    }
+    return fields;
+}
+
+// Return the names and units of the fields that may be graphed: the numeric
+// record fields followed by the values derived from them.
+pub fn get_graph_fields(data: &Vec<FitDataRecord>) -> Vec<(String, String)> {
+    let mut fields = get_record_numeric_fields(data);
+    let has_field = |name: &str| fields.iter().any(|f| f.0 == name);
+    if has_field("enhanced_speed") && has_field("heart_rate") {
+        fields.push((
+            "efficiency_factor".to_string(),
+            decoupling::EFFICIENCY_FACTOR_UNITS.to_string(),
+        ));
+    }
+    return fields;
+}
+
+// Return the record timestamps as seconds since the first record (elapsed time)
+// or since local midnight of the day the activity started (clock time).
+// The offset is removed in f64 as epoch seconds do not fit the f32 plot values.
+fn get_time_axis_as_vec(data: &Vec<FitDataRecord>, clock_time: bool) -> Vec<f64> {
+    let stamps = get_msg_record_field_as_vec(&data, "timestamp");
+    let Some(first) = stamps.first().copied() else {
+        return stamps;
+    };
+    let origin = time_axis_origin(first, clock_time);
+    return stamps.iter().map(|t| t - origin).collect();
+}
+
+// Return the timestamp the time axis counts from given the first timestamp:
+// the start of the activity, or local midnight for clock time.
+fn time_axis_origin(first: f64, clock_time: bool) -> f64 {
+    if clock_time && let Some(start) = DateTime::from_timestamp(first as i64, 0) {
+        return first - start.with_timezone(&Local).num_seconds_from_midnight() as f64;
+    }
+    return first;
+}
+
+// Return the x axis value of every record in the user's units, None for
+// records without it, so that values computed for only some records, or a
+// stretch given by record index, can be placed on the graphs.
+pub fn get_record_x_values(
+    data: &Vec<FitDataRecord>,
+    user_unit: &Units,
+    x_field_name: &str,
+) -> Vec<Option<f32>> {
+    let field_name = match x_field_name {
+        "elapsed_time" | "clock_time" => "timestamp",
+        _ => x_field_name,
+    };
+    let values: Vec<Option<f64>> = data
+        .iter()
+        .filter(|item| item.kind() == MesgNum::Record)
+        .map(|item| get_field_value(item, field_name))
+        .collect();
+    let mut origin = 0.0;
+    if field_name == "timestamp"
+        && let Some(first) = values.iter().flatten().next().copied()
+    {
+        origin = time_axis_origin(first, x_field_name == "clock_time");
+    }
+    return values
+        .iter()
+        .map(|x| match x_field_name {
+            "distance" => x.map(|x| cvt_distance(x as f32, user_unit)),
+            _ => x.map(|x| (x - origin) as f32),
+        })
+        .collect();
+}
+
+// Return the position (semicircles) of every record, None for records without
+// one.  The positions are kept in f64 as f32 only holds them to about a meter.
+pub fn get_record_positions(data: &Vec<FitDataRecord>) -> Vec<Option<(f64, f64)>> {
+    return data
+        .iter()
+        .filter(|item| item.kind() == MesgNum::Record)
+        .map(|item| {
+            return Some((
+                get_field_value(item, "position_lat")?,
+                get_field_value(item, "position_long")?,
+            ));
+        })
+        .collect();
+}
+
+// Return the numeric value of "field_name" from a single message, if present.
+pub fn get_field_value(item: &FitDataRecord, field_name: &str) -> Option<f64> {
+    for fld in item.fields().iter() {
+        if fld.name() == field_name {
+            return fld.value().clone().try_into().ok();
+        }
+    }
+    return None;
+}
+
+// Convert various numeric Value variants to f64.
+pub(crate) fn extract_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float64(v) => Some(*v),
+        Value::Float32(v) => Some(*v as f64),
+        Value::UInt8(v) => Some(*v as f64),
+        Value::UInt16(v) => Some(*v as f64),
+        Value::UInt32(v) => Some(*v as f64),
+        Value::UInt64(v) => Some(*v as f64),
+        Value::SInt8(v) => Some(*v as f64),
+        Value::SInt16(v) => Some(*v as f64),
+        Value::SInt32(v) => Some(*v as f64),
+        Value::SInt64(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+// Extract a Value Array to a vector of f64.
+fn extract_vector_f64(value: &Value) -> Vec<f64> {
+    match value {
+        Value::Array(arr) => arr.iter().filter_map(extract_f64).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Return a time for heart rate in the time_in_zone record.
+pub fn get_time_in_zone_field(data: &Vec<FitDataRecord>) -> (Option<Vec<f64>>, Option<Vec<f64>>) {
+    let mut result: (Option<Vec<f64>>, Option<Vec<f64>>) = (None, None);
+    for item in data {
+        match item.kind() {
+            // Individual msgnum::records
+            MesgNum::TimeInZone => {
+                // Retrieve the FitDataField struct.
+                for fld in item.fields().iter() {
+                    if fld.name() == "reference_mesg" && fld.value().to_string() == "session" {
+                        let floats: Vec<f64> = extract_vector_f64(item.fields()[2].value());
+                        let hr_limits: Vec<f64> = extract_vector_f64(item.fields()[3].value());
+                        result = (Some(floats), Some(hr_limits));
+                    }
+                }
+            }
+            _ => (), // matches other patterns
+        }
+    }
+    return result;
+}
+
+// Return the lower and upper heart rate of a time_in_zone zone.  There is one
+// more zone than there are upper limits, so the last zone ends at top_heart_rate
+// (see zones::heart_rate_zone_top).
+pub fn hr_zone_bounds(zone_limits: &Vec<f64>, zone: usize, top_heart_rate: f64) -> (f64, f64) {
+    let ll = if zone == 0 {
+        0.0
+    } else {
+        zone_limits[zone - 1]
+    };
+    let ul = if zone < zone_limits.len() {
+        zone_limits[zone]
+    } else {
+        top_heart_rate.max(ll)
+    };
+    return (ll, ul);
+}
+
+// Return the date a run started on.
+pub fn get_run_start_date(data: &Vec<FitDataRecord>) -> (i32, u32, u32) {
+    let mut month = 0;
+    let mut day = 0;
+    let mut year = 0;
+    for item in data {
+        match item.kind() {
+            MesgNum::Session => {
+                for fld in item.fields().iter() {
+                    if fld.name() == "start_time" {
+                        let time_stamp = fld.value().clone().to_string();
+                        let from: Result<NaiveDateTime, chrono::ParseError> =
+                            NaiveDateTime::parse_from_str(&time_stamp, "%Y-%m-%d %H:%M:%S %z");
+                        match from {
+                            Ok(date_time) => {
+                                year = date_time.date().year();
+                                month = date_time.date().month();
+                                day = date_time.date().day();
+                            }
+                            Err(_e) => {
+                                panic!("Couldn't parse time_stamp.");
+                            }
+                        };
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    return (year, month, day);
+}
+
+// Return the date a run started on.
+pub fn get_timestamps(data: &Vec<FitDataRecord>) -> Vec<NaiveDateTime> {
+    let mut timestamps: Vec<NaiveDateTime> = vec![];
+    for item in data {
+        match item.kind() {
+            MesgNum::Record => {
+                for fld in item.fields().iter() {
+                    if fld.name() == "timestamp" {
+                        let time_stamp = fld.value().clone().to_string();
+                        let from: Result<NaiveDateTime, chrono::ParseError> =
+                            NaiveDateTime::parse_from_str(&time_stamp, "%Y-%m-%d %H:%M:%S %z");
+                        match from {
+                            Ok(date_time) => {
+                                timestamps.push(date_time);
+                            }
+                            Err(_e) => {
+                                panic!("Couldn't parse time_stamp.");
+                            }
+                        };
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    return timestamps;
+}
+// Determines if a given year, month, and day corresponds to American Thanksgiving.
+pub fn is_american_thanksgiving(year: i32, month: u32, day: u32) -> bool {
+    // Thanksgiving is always in November
+    if month != 11 {
+        return false;
+    }
+    // Basic validation for the day range (Nov 22 - Nov 28)
+    if day < 22 || day > 28 {
+        return false;
+    }
+    // Get the date object for November 1st of that year
+    if let Some(first_of_nov) = NaiveDate::from_ymd_opt(year, 11, 1) {
+        let first_weekday = first_of_nov.weekday();
+        // Calculate days from the 1st to the first Thursday
+        // Weekday::Thu is represented as 3 (0-indexed starting Mon)
+        let days_until_first_thursday =
+            (Weekday::Thu.num_days_from_monday() + 7 - first_weekday.num_days_from_monday()) % 7;
+        // The first Thursday is (1 + offset)
+        // The fourth Thursday is (1 + offset + 21)
+        let thanksgiving_day = 1 + days_until_first_thursday + 21;
+        return day == thanksgiving_day;
+    }
+    false
+}
+
+// Calculate if a given year, month, and  day falls on easter.
+pub fn is_easter(year: i32, month: u32, day: u32) -> bool {
+    //Algorithm from https://en.wikipedia.org/wiki/Date_of_Easter#Algorithms
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    // type conversion to unsigned int
+    let easter_mon: u32 = ((h + l - 7 * m + 114) / 31).try_into().unwrap();
+    let easter_day: u32 = (((h + l - 7 * m + 114) % 31) + 1).try_into().unwrap();
+    if day == easter_day && month == easter_mon {
+        return true;
+    } else {
+        return false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::degrees_to_semi;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    #[test]
+    fn pace_is_minutes_per_unit_distance() {
+        assert!((cvt_pace(1000.0 / 300.0, &Units::Metric) - 5.0).abs() < 1e-4);
+        assert!((cvt_pace(1609.344 / 480.0, &Units::US) - 8.0).abs() < 1e-4);
+        assert_eq!(cvt_pace(3.0, &Units::None), 3.0);
+    }
+
+    #[test]
+    fn speed_converts_to_distance_per_hour() {
+        assert!((cvt_speed(10.0, &Units::Metric) - 36.0).abs() < 1e-4);
+        assert!((cvt_speed(1.0, &Units::US) - 2.2369363).abs() < 1e-6);
+        assert_eq!(cvt_speed(3.0, &Units::None), 3.0);
+    }
+
+    #[test]
+    fn distance_altitude_and_temperature_convert() {
+        assert!((cvt_distance(1609.344, &Units::US) - 1.0).abs() < 1e-5);
+        assert!((cvt_distance(2500.0, &Units::Metric) - 2.5).abs() < 1e-5);
+        assert!((cvt_altitude(100.0, &Units::US) - 328.08399).abs() < 1e-3);
+        assert_eq!(cvt_altitude(100.0, &Units::Metric), 100.0);
+        assert_eq!(cvt_temperature(100.0, &Units::US), 212.0);
+        assert_eq!(cvt_temperature(-40.0, &Units::US), -40.0);
+        assert_eq!(cvt_temperature(20.0, &Units::None), 20.0);
+    }
+
+    #[test]
+    fn elapsed_time_splits_into_hours_minutes_and_seconds() {
+        assert_eq!(cvt_elapsed_time(5400.0), (1, 30, 0));
+        assert_eq!(cvt_elapsed_time(90.0), (0, 1, 30));
+        assert_eq!(cvt_elapsed_time(0.0), (0, 0, 0));
+    }
+
+    #[test]
+    fn semicircles_convert_to_degrees() {
+        assert_eq!(semi_to_degrees(2f64.powi(30)), 90.0);
+        assert_eq!(semi_to_degrees(-(2f64.powi(31))), -180.0);
+    }
+
+    #[test]
+    fn plot_range_of_no_data_is_empty() {
+        assert_eq!(set_plot_range(&Vec::new(), 1.0, 1.0), (0.0..0.0, 0.0..0.0));
+    }
+
+    #[test]
+    fn plot_range_spans_two_standard_deviations() {
+        let data = vec![(0.0, 1.0), (5.0, 3.0), (10.0, 1.0), (20.0, 3.0)];
+        let (x, y) = set_plot_range(&data, 1.0, 1.0);
+        assert_eq!(x, 0.0..20.0);
+        assert_eq!(y, 0.0..4.0);
+        // Zooming in narrows both ranges.
+        let (x, y) = set_plot_range(&data, 2.0, 2.0);
+        assert_eq!(x, 0.0..10.0);
+        assert_eq!(y, 1.0..3.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn plot_range_refuses_zero_zoom() {
+        set_plot_range(&vec![(0.0, 1.0)], 0.0, 1.0);
+    }
+
+    #[test]
+    fn filtered_plot_range_keeps_a_sprint_on_the_graph() {
+        // A steady series ending in a sprint, plus a single glitch.
+        let mut data: Vec<(f32, f32)> = (0..100).map(|i| (i as f32, 100.0)).collect();
+        data.extend((100..110).map(|i| (i as f32, 200.0)));
+        data[50].1 = 1000.0;
+        let (x, y) = set_filtered_plot_range(&data, 1.0, 1.0);
+        assert_eq!(x, 0.0..109.0);
+        assert!(y.start < 100.0 && y.end > 200.0);
+        assert!(y.end < 1000.0);
+        assert_eq!(
+            set_filtered_plot_range(&Vec::new(), 1.0, 1.0),
+            (0.0..0.0, 0.0..0.0)
+        );
+    }
+
+    #[test]
+    fn xy_pairs_are_converted_to_the_unit_system() {
+        let data = steady_run(11, 1000.0 / 300.0, 150.0);
+        let xy = get_xy(&data, &Units::Metric, "distance", "enhanced_speed");
+        assert!(!xy.is_empty());
+        for (k, (x, y)) in xy.iter().enumerate() {
+            assert!((x - k as f32 / 300.0).abs() < 1e-5);
+            assert!((y - 5.0).abs() < 1e-4);
+        }
+        let xy = get_xy(&data, &Units::None, "distance", "heart_rate");
+        assert_eq!(xy[3], (10.0, 150.0));
+    }
+
+    #[test]
+    fn time_axis_counts_from_the_start_or_midnight() {
+        let data = steady_run(11, 2.5, 150.0);
+        let xy = get_xy(&data, &Units::None, "elapsed_time", "heart_rate");
+        assert_eq!(xy[0], (0.0, 150.0));
+        assert_eq!(xy[3], (3.0, 150.0));
+        let start = DateTime::from_timestamp(1_714_550_400, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let midnight = start.num_seconds_from_midnight() as f32;
+        let xy = get_xy(&data, &Units::None, "clock_time", "heart_rate");
+        assert_eq!(xy[0].0, midnight);
+        assert_eq!(xy[3].0, midnight + 3.0);
+    }
+
+    #[test]
+    fn numeric_record_fields_are_offered_for_graphs() {
+        let fields = get_record_numeric_fields(&steady_run(3, 2.5, 150.0));
+        let names: Vec<&str> = fields.iter().map(|f| f.0.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "heart_rate",
+                "cadence",
+                "temperature",
+                "enhanced_speed",
+                "enhanced_altitude"
+            ]
+        );
+        assert_eq!(fields[0].1, "bpm");
+        assert!(get_record_numeric_fields(&Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn record_x_values_keep_the_records_without_them() {
+        let mut points = run_points(5, |_| (1000.0 / 300.0, 150.0, 100.0));
+        points[2].distance = None;
+        let data = activity(&points);
+        let x = get_record_x_values(&data, &Units::Metric, "distance");
+        assert_eq!(x.len(), 5);
+        assert_eq!(x[2], None);
+        assert!((x[4].unwrap() - 4.0 / 300.0).abs() < 1e-6);
+        let x = get_record_x_values(&data, &Units::Metric, "elapsed_time");
+        assert_eq!(x[0], Some(0.0));
+        assert_eq!(x[4], Some(4.0));
+    }
+
+    #[test]
+    fn record_positions_keep_the_records_without_them() {
+        let mut points = run_points(3, |_| (3.0, 150.0, 100.0));
+        points[1].latitude = None;
+        points[2].latitude = Some(45.1234567);
+        points[2].longitude = Some(7.7654321);
+        let positions = get_record_positions(&activity(&points));
+        assert_eq!(positions.len(), 3);
+        assert!(positions[1].is_none());
+        // The positions are kept to the semicircle.
+        assert_eq!(
+            positions[2],
+            Some((
+                degrees_to_semi(45.1234567) as f64,
+                degrees_to_semi(7.7654321) as f64
+            ))
+        );
+    }
+
+    #[test]
+    fn efficiency_factor_keeps_the_x_of_its_record() {
+        let mut points = run_points(601, |_| (3.0, 150.0, 100.0));
+        for point in points.iter_mut().take(300) {
+            point.heart_rate = None;
+        }
+        let xy = get_xy(
+            &activity(&points),
+            &Units::None,
+            "elapsed_time",
+            "efficiency_factor",
+        );
+        assert_eq!(xy.len(), 301);
+        assert_eq!(xy[0].0, 300.0);
+        assert_eq!(xy[300].0, 600.0);
+        assert!((xy[300].1 - 1.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn grade_adjusted_speed_keeps_the_x_of_its_record() {
+        let mut points = run_points(101, |i| (2.0 + 0.01 * i as f64, 150.0, 100.0));
+        points[10].altitude = None;
+        let xy = get_xy(
+            &activity(&points),
+            &Units::None,
+            "elapsed_time",
+            "grade_adjusted_speed",
+        );
+        assert_eq!(xy.len(), 100);
+        assert_eq!(xy[10].0, 11.0);
+        assert!((xy[10].1 - 2.11).abs() < 1e-4);
+    }
+
+    #[test]
+    fn session_fields_are_read() {
+        let data = steady_run(101, 2.5, 150.0);
+        assert_eq!(get_sess_record_field(&data, "total_distance"), 250.0);
+        assert_eq!(get_sess_record_field(&data, "total_elapsed_time"), 100.0);
+        assert_eq!(get_sess_record_field(&data, "avg_heart_rate"), 150.0);
+        assert!(get_sess_record_field(&data, "total_calories").is_nan());
+    }
+
+    #[test]
+    fn last_heart_rate_zone_ends_at_the_maximum() {
+        let limits = vec![120.0, 160.0];
+        assert_eq!(hr_zone_bounds(&limits, 0, 190.0), (0.0, 120.0));
+        assert_eq!(hr_zone_bounds(&limits, 1, 190.0), (120.0, 160.0));
+        assert_eq!(hr_zone_bounds(&limits, 2, 190.0), (160.0, 190.0));
+        assert_eq!(hr_zone_bounds(&limits, 2, 150.0), (160.0, 160.0));
+    }
+
+    #[test]
+    fn start_date_and_timestamps_are_read() {
+        let data = steady_run(3, 2.5, 150.0);
+        let start = DateTime::from_timestamp(1_714_550_400, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        assert_eq!(
+            get_run_start_date(&data),
+            (start.year(), start.month(), start.day())
+        );
+        let timestamps = get_timestamps(&data);
+        assert_eq!(timestamps.len(), 3);
+        assert_eq!((timestamps[2] - timestamps[0]).num_seconds(), 2);
+    }
+
+    #[test]
+    fn holidays_are_recognized() {
+        assert!(is_american_thanksgiving(2024, 11, 28));
+        assert!(is_american_thanksgiving(2025, 11, 27));
+        assert!(!is_american_thanksgiving(2024, 11, 21));
+        assert!(is_easter(2024, 3, 31));
+        assert!(is_easter(2025, 4, 20));
+        assert!(!is_easter(2025, 3, 31));
+    }
+}
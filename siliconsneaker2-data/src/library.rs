@@ -0,0 +1,370 @@
+// Module containing the activity library, an index of the session values of
+// every activity file found in a folder.
+
+use crate::efforts::{BEST_EFFORT_DISTANCES, BestEffort, best_efforts};
+use crate::training_load::trimp;
+use crate::zones::HeartRateZones;
+use crate::{get_field_value, get_sess_record_field, read_activity};
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// File extensions read_activity understands.
+const ACTIVITY_EXTENSIONS: [&str; 3] = ["fit", "tcx", "gpx"];
+
+// Bumped whenever LibraryEntry gains a value, so that older indexes are rebuilt.
+pub const LIBRARY_INDEX_VERSION: u32 = 3;
+
+// The indexed values of one activity file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub path: String,
+    pub modified: u64,   // file modification time, seconds since the epoch
+    pub start_time: i64, // seconds since the epoch
+    pub sport: String,
+    pub distance: f64, // meters
+    pub duration: f64, // timer time, seconds
+    #[serde(default)]
+    pub ascent: f64, // meters
+    pub avg_heart_rate: Option<f64>,
+    #[serde(default)]
+    pub best_efforts: Vec<BestEffort>,
+    #[serde(default)]
+    pub trimp: Option<f64>, // from the index's resting and maximum heart rate
+}
+
+// The fastest activity in the library over one distance.
+pub struct PersonalRecord<'a> {
+    pub effort: &'a BestEffort,
+    pub entry: &'a LibraryEntry,
+}
+
+// The index of an activity folder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    #[serde(default)]
+    pub version: u32,
+    pub folder: String,
+    #[serde(default)]
+    pub resting_heart_rate: f64, // bpm the TRIMP of the entries was computed with
+    #[serde(default)]
+    pub max_heart_rate: f64, // bpm the TRIMP of the entries was computed with
+    pub entries: Vec<LibraryEntry>,
+}
+
+// Return a file's modification time in seconds since the epoch.
+fn modified_secs(path: &Path) -> u64 {
+    return fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+}
+
+// Return true for files with an activity file extension.
+fn is_activity_file(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => return ACTIVITY_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+        None => return false,
+    }
+}
+
+// Return a session total, or zero when the session does not record it.
+fn session_total(data: &Vec<FitDataRecord>, field_name: &str) -> f64 {
+    let total = get_sess_record_field(data, field_name);
+    if total.is_nan() {
+        return 0.0;
+    }
+    return total;
+}
+
+// Build an index entry from the session message of a decoded activity.
+pub fn session_entry(data: &Vec<FitDataRecord>, hr_zones: &HeartRateZones) -> Option<LibraryEntry> {
+    let session = data.iter().find(|item| item.kind() == MesgNum::Session)?;
+    let sport = session
+        .fields()
+        .iter()
+        .find(|fld| fld.name() == "sport")
+        .map(|fld| fld.value().to_string())
+        .unwrap_or_default();
+    return Some(LibraryEntry {
+        path: String::new(),
+        modified: 0,
+        start_time: get_field_value(session, "start_time")
+            .or(get_field_value(session, "timestamp"))
+            .unwrap_or(0.0) as i64,
+        sport,
+        distance: session_total(data, "total_distance"),
+        duration: session_total(data, "total_timer_time"),
+        ascent: session_total(data, "total_ascent"),
+        avg_heart_rate: get_field_value(session, "avg_heart_rate"),
+        best_efforts: best_efforts(data),
+        trimp: trimp(data, hr_zones),
+    });
+}
+
+// Read an activity file and index its session.  Returns None for files that
+// cannot be decoded or hold no session, e.g. settings or monitoring files.
+pub fn index_activity_file(path: &Path, hr_zones: &HeartRateZones) -> Option<LibraryEntry> {
+    let mut file = File::open(path).ok()?;
+    let data = read_activity(&mut file).ok()?;
+    let mut entry = session_entry(&data, hr_zones)?;
+    entry.path = path.to_string_lossy().to_string();
+    entry.modified = modified_secs(path);
+    return Some(entry);
+}
+
+// Collect the activity files below a folder.
+fn find_activity_files(folder: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(dir) = fs::read_dir(folder) else {
+        return;
+    };
+    for dir_entry in dir.flatten() {
+        let path = dir_entry.path();
+        if path.is_dir() {
+            find_activity_files(&path, files);
+        } else if is_activity_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+// Index every activity file in a folder and its sub-folders.  Files already in
+// the previous index with an unchanged modification time are not read again.
+// progress is called with the number of files done and the total after each
+// file.
+pub fn scan_folder(
+    folder: &Path,
+    previous: &LibraryIndex,
+    hr_zones: &HeartRateZones,
+    progress: impl Fn(usize, usize),
+) -> LibraryIndex {
+    let mut files = Vec::new();
+    find_activity_files(folder, &mut files);
+    let mut index = LibraryIndex {
+        version: LIBRARY_INDEX_VERSION,
+        folder: folder.to_string_lossy().to_string(),
+        resting_heart_rate: hr_zones.resting_heart_rate,
+        max_heart_rate: hr_zones.max_heart_rate,
+        entries: Vec::new(),
+    };
+    // Entries of an older index lack values, and the TRIMP of an index built
+    // with other heart rate settings is wrong, so these must be read again.
+    let reusable: &[LibraryEntry] = if previous.version == LIBRARY_INDEX_VERSION
+        && previous.resting_heart_rate == index.resting_heart_rate
+        && previous.max_heart_rate == index.max_heart_rate
+    {
+        &previous.entries
+    } else {
+        &[]
+    };
+    let total = files.len();
+    for (done, path) in files.into_iter().enumerate() {
+        progress(done, total);
+        let path_str = path.to_string_lossy().to_string();
+        let modified = modified_secs(&path);
+        let known = reusable
+            .iter()
+            .find(|e| e.path == path_str && e.modified == modified);
+        match known {
+            Some(entry) => index.entries.push(entry.clone()),
+            None => {
+                if let Some(entry) = index_activity_file(&path, hr_zones) {
+                    index.entries.push(entry);
+                }
+            }
+        }
+    }
+    progress(total, total);
+    index.entries.sort_by_key(|e| e.start_time);
+    return index;
+}
+
+// Return the fastest effort over each best effort distance among the library
+// entries, shortest distance first.  Only activities of the given sport are
+// considered when one is given.
+pub fn personal_records<'a>(
+    entries: &'a [LibraryEntry],
+    sport: Option<&str>,
+) -> Vec<PersonalRecord<'a>> {
+    let mut records: Vec<PersonalRecord> = Vec::new();
+    for (name, _) in BEST_EFFORT_DISTANCES {
+        let fastest = entries
+            .iter()
+            .filter(|e| sport.is_none_or(|s| e.sport == s))
+            .flat_map(|e| e.best_efforts.iter().map(move |b| (b, e)))
+            .filter(|(b, _)| b.name == name && b.time > 0.0)
+            .min_by(|a, b| a.0.time.total_cmp(&b.0.time));
+        if let Some((effort, entry)) = fastest {
+            records.push(PersonalRecord { effort, entry });
+        }
+    }
+    return records;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpx::fit_to_gpx;
+    use crate::test_data::steady_run;
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+
+    // Return an empty folder of the temporary directory for one test.
+    fn empty_folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("siliconsneaker2-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        return folder;
+    }
+
+    #[test]
+    fn entry_holds_the_session_values() {
+        let entry =
+            session_entry(&steady_run(101, 2.5, 150.0), &HeartRateZones::default()).unwrap();
+        assert_eq!(entry.start_time, 1_714_550_400);
+        assert_eq!(entry.sport, "running");
+        assert_eq!(entry.distance, 250.0);
+        assert_eq!(entry.duration, 100.0);
+        assert_eq!(entry.ascent, 0.0);
+        assert_eq!(entry.avg_heart_rate, Some(150.0));
+        assert!(entry.trimp.unwrap() > 0.0);
+        assert!(session_entry(&Vec::new(), &HeartRateZones::default()).is_none());
+    }
+
+    #[test]
+    fn activity_files_are_known_by_extension() {
+        assert!(is_activity_file(Path::new("run.FIT")));
+        assert!(is_activity_file(Path::new("a/walk.gpx")));
+        assert!(is_activity_file(Path::new("ride.tcx")));
+        assert!(!is_activity_file(Path::new("notes.txt")));
+        assert!(!is_activity_file(Path::new("fit")));
+    }
+
+    #[test]
+    fn scan_indexes_sub_folders_in_start_order() {
+        let folder = empty_folder("scan");
+        let late = steady_run(11, 2.5, 150.0);
+        fs::create_dir(folder.join("2024")).unwrap();
+        fs::write(folder.join("2024/late.gpx"), fit_to_gpx(&late)).unwrap();
+        fs::write(folder.join("notes.txt"), "not an activity").unwrap();
+        fs::write(folder.join("broken.fit"), "not a fit file").unwrap();
+        let reported = RefCell::new(Vec::new());
+        let index = scan_folder(
+            &folder,
+            &LibraryIndex::default(),
+            &HeartRateZones::default(),
+            |done, total| reported.borrow_mut().push((done, total)),
+        );
+        // The broken file counts, the text file is not an activity file.
+        assert_eq!(*reported.borrow(), vec![(0, 2), (1, 2), (2, 2)]);
+        assert_eq!(index.entries.len(), 1);
+        assert!(index.entries[0].path.ends_with("late.gpx"));
+        assert_eq!(index.entries[0].start_time, 1_714_550_400);
+        assert!(index.entries[0].modified > 0);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn unchanged_files_are_not_read_again() {
+        let folder = empty_folder("rescan");
+        let path = folder.join("run.gpx");
+        fs::write(&path, fit_to_gpx(&steady_run(11, 2.5, 150.0))).unwrap();
+        let mut previous = scan_folder(
+            &folder,
+            &LibraryIndex::default(),
+            &HeartRateZones::default(),
+            |_, _| {},
+        );
+        previous.entries[0].sport = String::from("cached");
+        let index = scan_folder(&folder, &previous, &HeartRateZones::default(), |_, _| {});
+        assert_eq!(index.entries[0].sport, "cached");
+        // A changed modification time has the file read again.  The exported
+        // track has no type, so it reads back as a generic activity.
+        previous.entries[0].modified -= 1;
+        let index = scan_folder(&folder, &previous, &HeartRateZones::default(), |_, _| {});
+        assert_eq!(index.entries[0].sport, "generic");
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn older_indexes_are_read_again() {
+        let folder = empty_folder("version");
+        fs::write(
+            folder.join("run.gpx"),
+            fit_to_gpx(&steady_run(11, 2.5, 150.0)),
+        )
+        .unwrap();
+        let mut previous = scan_folder(
+            &folder,
+            &LibraryIndex::default(),
+            &HeartRateZones::default(),
+            |_, _| {},
+        );
+        assert_eq!(previous.version, LIBRARY_INDEX_VERSION);
+        previous.version = 0;
+        previous.entries[0].sport = String::from("cached");
+        let index = scan_folder(&folder, &previous, &HeartRateZones::default(), |_, _| {});
+        assert_eq!(index.entries[0].sport, "generic");
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn entry_holds_the_best_efforts() {
+        let entry =
+            session_entry(&steady_run(501, 4.0, 150.0), &HeartRateZones::default()).unwrap();
+        let names: Vec<&str> = entry.best_efforts.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["400m", "1k", "mile"]);
+    }
+
+    #[test]
+    fn personal_records_are_the_fastest_of_the_sport() {
+        let mut slow =
+            session_entry(&steady_run(501, 3.0, 150.0), &HeartRateZones::default()).unwrap();
+        slow.path = String::from("slow.fit");
+        let mut fast =
+            session_entry(&steady_run(501, 4.0, 150.0), &HeartRateZones::default()).unwrap();
+        fast.path = String::from("fast.fit");
+        let mut ride =
+            session_entry(&steady_run(501, 8.0, 150.0), &HeartRateZones::default()).unwrap();
+        ride.sport = String::from("cycling");
+        let entries = vec![slow, fast, ride];
+        let records = personal_records(&entries, Some("running"));
+        let names: Vec<&str> = records.iter().map(|r| r.effort.name.as_str()).collect();
+        // Only the faster run covers a mile.
+        assert_eq!(names, vec!["400m", "1k", "mile"]);
+        assert!(records.iter().all(|r| r.entry.path == "fast.fit"));
+        assert_eq!(records[0].effort.time, 100.0);
+        let records = personal_records(&entries, None);
+        assert_eq!(records[0].entry.sport, "cycling");
+        assert!(personal_records(&entries, Some("swimming")).is_empty());
+    }
+
+    #[test]
+    fn changed_heart_rate_settings_have_the_files_read_again() {
+        let folder = empty_folder("settings");
+        fs::write(
+            folder.join("run.gpx"),
+            fit_to_gpx(&steady_run(11, 2.5, 150.0)),
+        )
+        .unwrap();
+        let zones = HeartRateZones::default();
+        let mut previous = scan_folder(&folder, &LibraryIndex::default(), &zones, |_, _| {});
+        assert_eq!(previous.max_heart_rate, zones.max_heart_rate);
+        previous.entries[0].sport = String::from("cached");
+        let index = scan_folder(&folder, &previous, &zones, |_, _| {});
+        assert_eq!(index.entries[0].sport, "cached");
+        let older = HeartRateZones {
+            max_heart_rate: 180.0,
+            ..HeartRateZones::default()
+        };
+        let index = scan_folder(&folder, &previous, &older, |_, _| {});
+        assert_eq!(index.entries[0].sport, "generic");
+        assert_eq!(index.max_heart_rate, 180.0);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+// Module reading raster map tiles from an MBTiles file, an SQLite database
+// holding the tiles by zoom level, column and row.
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Mutex;
+
+// An open MBTiles file.
+pub struct MbTiles {
+    connection: Mutex<Connection>,
+    metadata: HashMap<String, String>,
+    zoom_range: (u32, u32),
+}
+
+impl MbTiles {
+    // Open an MBTiles file, read only.  Files of vector tiles are refused as
+    // the map can only draw images.
+    pub fn open(path: &Path) -> Result<MbTiles, Box<dyn Error>> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        // Deduplicated files have a tiles view over their map and images
+        // tables, so the one query serves both layouts.
+        let has_tiles: bool = connection.query_row(
+            "SELECT count(*) > 0 FROM sqlite_master
+             WHERE name = 'tiles' AND type IN ('table', 'view')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_tiles {
+            return Err("The MBTiles file has no tiles table.".into());
+        }
+        let mut metadata: HashMap<String, String> = HashMap::new();
+        if let Ok(mut statement) = connection.prepare("SELECT name, value FROM metadata") {
+            let rows = statement.query_map([], |row| {
+                return Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?));
+            })?;
+            for (name, value) in rows.flatten() {
+                metadata.insert(name, value);
+            }
+        }
+        if metadata.get("format").is_some_and(|f| f == "pbf") {
+            return Err("MBTiles files of vector tiles are not supported.".into());
+        }
+        let zoom = |name: &str| -> Option<u32> {
+            return metadata.get(name).and_then(|z| z.trim().parse().ok());
+        };
+        let zoom_range = match (zoom("minzoom"), zoom("maxzoom")) {
+            (Some(min_zoom), Some(max_zoom)) => (min_zoom, max_zoom),
+            (min_zoom, max_zoom) => {
+                let (lowest, highest): (Option<u32>, Option<u32>) = connection.query_row(
+                    "SELECT min(zoom_level), max(zoom_level) FROM tiles",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                (
+                    min_zoom.or(lowest).unwrap_or(0),
+                    max_zoom.or(highest).unwrap_or(0),
+                )
+            }
+        };
+        return Ok(MbTiles {
+            connection: Mutex::new(connection),
+            metadata,
+            zoom_range,
+        });
+    }
+
+    // Return the image of a tile in XYZ numbering, rows counted from the
+    // north, or None if the file does not hold it.
+    pub fn tile(&self, zoom: u32, x: u32, y: u32) -> Option<Vec<u8>> {
+        if zoom >= 32 || y >= 1 << zoom {
+            return None;
+        }
+        // MBTiles count the rows from the south.
+        let row = (1 << zoom) - 1 - y;
+        let connection = self.connection.lock().ok()?;
+        let mut statement = connection
+            .prepare_cached(
+                "SELECT tile_data FROM tiles
+                 WHERE zoom_level = ? AND tile_column = ? AND tile_row = ?",
+            )
+            .ok()?;
+        return statement
+            .query_row((zoom, x, row), |row| row.get(0))
+            .optional()
+            .ok()?;
+    }
+
+    // Return a value of the metadata table, e.g. name or attribution.
+    pub fn metadata(&self, name: &str) -> Option<&str> {
+        return self.metadata.get(name).map(|v| v.as_str());
+    }
+
+    // Return the lowest and highest zoom level of the tiles.
+    pub fn zoom_range(&self) -> (u32, u32) {
+        return self.zoom_range;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Create an MBTiles file holding the given metadata and a tile at zoom
+    // level 1, column 0 and row 1, i.e. the north west tile.
+    fn mbtiles_file(name: &str, metadata: &[(&str, &str)]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "siliconsneaker2-{}-{}.mbtiles",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE metadata (name TEXT, value TEXT);
+                 CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER,
+                                     tile_row INTEGER, tile_data BLOB);
+                 INSERT INTO tiles VALUES (1, 0, 1, x'89504e47');",
+            )
+            .unwrap();
+        for (name, value) in metadata {
+            connection
+                .execute("INSERT INTO metadata VALUES (?, ?)", (name, value))
+                .unwrap();
+        }
+        return path;
+    }
+
+    #[test]
+    fn tiles_are_read_in_xyz_numbering() {
+        let path = mbtiles_file(
+            "xyz",
+            &[
+                ("name", "Alps"),
+                ("format", "png"),
+                ("minzoom", "0"),
+                ("maxzoom", "5"),
+            ],
+        );
+        let mbtiles = MbTiles::open(&path).unwrap();
+        assert_eq!(mbtiles.tile(1, 0, 0), Some(vec![0x89, 0x50, 0x4e, 0x47]));
+        assert_eq!(mbtiles.tile(1, 0, 1), None);
+        assert_eq!(mbtiles.tile(1, 0, 2), None);
+        assert_eq!(mbtiles.tile(40, 0, 0), None);
+        assert_eq!(mbtiles.metadata("name"), Some("Alps"));
+        assert_eq!(mbtiles.metadata("attribution"), None);
+        assert_eq!(mbtiles.zoom_range(), (0, 5));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn zoom_range_falls_back_to_the_tiles() {
+        let path = mbtiles_file("zoom", &[("maxzoom", "3")]);
+        assert_eq!(MbTiles::open(&path).unwrap().zoom_range(), (1, 3));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn vector_tiles_and_other_databases_are_refused() {
+        let path = mbtiles_file("vector", &[("format", "pbf")]);
+        assert!(MbTiles::open(&path).is_err());
+        let connection = Connection::open(&path).unwrap();
+        connection.execute_batch("DROP TABLE tiles;").unwrap();
+        assert!(MbTiles::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(MbTiles::open(&path).is_err());
+    }
+}
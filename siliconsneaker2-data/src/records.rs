@@ -0,0 +1,598 @@
+// Module containing construction of FIT style messages for activities read
+// from other file formats, so the rest of the program can treat them alike.
+
+use chrono::{DateTime, Local, Utc};
+use fitparser::{FitDataField, FitDataRecord, Value, profile::field_types::MesgNum};
+
+// Mean radius of the earth in meters.
+const EARTH_RADIUS: f64 = 6371008.8;
+// Shortest interval used to derive speed from positions.
+const SPEED_WINDOW_SECS: i64 = 10;
+
+// A single sample along the track, in meters, seconds and degrees.
+#[derive(Clone, Default)]
+pub struct TrackPoint {
+    pub time: Option<DateTime<Utc>>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>,
+    pub distance: Option<f64>,
+    pub speed: Option<f64>,
+    pub heart_rate: Option<f64>,
+    pub cadence: Option<f64>,
+    pub temperature: Option<f64>,
+}
+
+// Convert degrees to semi-circles.
+pub fn degrees_to_semi(degrees: f64) -> i32 {
+    return (degrees * 2f64.powi(31) / 180.0).round() as i32;
+}
+
+// Great circle distance in meters between two points given in degrees.
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    return 2.0 * EARTH_RADIUS * a.sqrt().asin();
+}
+
+// Fill in cumulative distance and speed where the source format lacks them.
+// Distance is derived from the positions, speed from distance over time.
+pub fn derive_distance_and_speed(points: &mut [TrackPoint]) {
+    let has_distance = points.iter().any(|pt| pt.distance.is_some());
+    let mut total = 0.0;
+    let mut last_position: Option<(f64, f64)> = None;
+    for pt in points.iter_mut() {
+        if has_distance {
+            // Carry the last known value over gaps.
+            match pt.distance {
+                Some(d) => total = d,
+                None => pt.distance = Some(total),
+            }
+            continue;
+        }
+        if let (Some(lat), Some(lon)) = (pt.latitude, pt.longitude) {
+            if let Some((last_lat, last_lon)) = last_position {
+                total += haversine_distance(last_lat, last_lon, lat, lon);
+            }
+            last_position = Some((lat, lon));
+        }
+        pt.distance = Some(total);
+    }
+    let mut last_speed = 0.0;
+    for idx in 0..points.len() {
+        if let Some(speed) = points[idx].speed {
+            last_speed = speed;
+            continue;
+        }
+        // Look back far enough that the GPS jitter averages out.
+        if let (Some(t1), Some(d1)) = (points[idx].time, points[idx].distance) {
+            let mut back = idx;
+            while back > 0 {
+                back -= 1;
+                if let Some(t0) = points[back].time
+                    && (t1 - t0).num_seconds() >= SPEED_WINDOW_SECS
+                {
+                    break;
+                }
+            }
+            if let (Some(t0), Some(d0)) = (points[back].time, points[back].distance) {
+                let dt = (t1 - t0).num_milliseconds() as f64 / 1000.0;
+                if dt > 0.0 {
+                    last_speed = (d1 - d0) / dt;
+                }
+            }
+        }
+        points[idx].speed = Some(last_speed);
+    }
+}
+
+// Create a field carrying the FIT profile name, number and units.
+fn push_field(record: &mut FitDataRecord, name: &str, number: u8, value: Value, units: &str) {
+    record.push(FitDataField::new(
+        name.to_string(),
+        number,
+        None,
+        value,
+        units.to_string(),
+    ));
+}
+
+// Convert a UTC time to the Value variant fitparser produces.
+fn timestamp_value(time: DateTime<Utc>) -> Value {
+    return Value::Timestamp(time.with_timezone(&Local));
+}
+
+// Build a MesgNum::Record message from a track point.
+pub fn record_message(pt: &TrackPoint) -> FitDataRecord {
+    let mut record = FitDataRecord::new(MesgNum::Record);
+    if let Some(time) = pt.time {
+        push_field(&mut record, "timestamp", 253, timestamp_value(time), "s");
+    }
+    if let (Some(lat), Some(lon)) = (pt.latitude, pt.longitude) {
+        let lat_semi = Value::SInt32(degrees_to_semi(lat));
+        let lon_semi = Value::SInt32(degrees_to_semi(lon));
+        push_field(&mut record, "position_lat", 0, lat_semi, "semicircles");
+        push_field(&mut record, "position_long", 1, lon_semi, "semicircles");
+    }
+    if let Some(heart_rate) = pt.heart_rate {
+        push_field(
+            &mut record,
+            "heart_rate",
+            3,
+            Value::UInt8(heart_rate as u8),
+            "bpm",
+        );
+    }
+    if let Some(cadence) = pt.cadence {
+        let cadence = Value::UInt8(cadence as u8);
+        push_field(&mut record, "cadence", 4, cadence, "rpm");
+    }
+    if let Some(distance) = pt.distance {
+        push_field(&mut record, "distance", 5, Value::Float64(distance), "m");
+    }
+    if let Some(temperature) = pt.temperature {
+        let temperature = Value::SInt8(temperature.round() as i8);
+        push_field(&mut record, "temperature", 13, temperature, "C");
+    }
+    if let Some(speed) = pt.speed {
+        push_field(
+            &mut record,
+            "enhanced_speed",
+            73,
+            Value::Float64(speed),
+            "m/s",
+        );
+    }
+    if let Some(altitude) = pt.altitude {
+        push_field(
+            &mut record,
+            "enhanced_altitude",
+            78,
+            Value::Float64(altitude),
+            "m",
+        );
+    }
+    return record;
+}
+
+// Totals recorded by the source format which take precedence over the
+// values calculated from the track points.
+#[derive(Clone, Default)]
+pub struct LapValues {
+    pub timer_time: Option<f64>,
+    pub distance: Option<f64>,
+    pub calories: Option<f64>,
+}
+
+// Values summarizing a run of track points, shared by lap and session messages.
+struct Totals {
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    start_position: Option<(f64, f64)>,
+    end_position: Option<(f64, f64)>,
+    elapsed_time: f64,
+    distance: f64,
+    avg_heart_rate: Option<f64>,
+    max_heart_rate: Option<f64>,
+    avg_cadence: Option<f64>,
+    max_cadence: Option<f64>,
+    ascent: f64,
+    descent: f64,
+    max_speed: f64,
+    // north east and south west corners (lat, lon)
+    nec: Option<(f64, f64)>,
+    swc: Option<(f64, f64)>,
+}
+
+// Average and maximum of the values present.
+fn avg_max(values: &[f64]) -> (Option<f64>, Option<f64>) {
+    if values.is_empty() {
+        return (None, None);
+    }
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    let max = values.iter().copied().fold(f64::MIN, f64::max);
+    return (Some(avg), Some(max));
+}
+
+// Calculate the totals for a run of track points.
+fn calculate_totals(points: &[TrackPoint]) -> Totals {
+    let start_time = points.iter().find_map(|pt| pt.time);
+    let end_time = points.iter().rev().find_map(|pt| pt.time);
+    let mut elapsed_time = 0.0;
+    if let (Some(start), Some(end)) = (start_time, end_time) {
+        elapsed_time = (end - start).num_milliseconds() as f64 / 1000.0;
+    }
+    let positions: Vec<(f64, f64)> = points
+        .iter()
+        .filter_map(|pt| Some((pt.latitude?, pt.longitude?)))
+        .collect();
+    let mut nec = None;
+    let mut swc = None;
+    if !positions.is_empty() {
+        let lats = positions.iter().map(|p| p.0);
+        let lons = positions.iter().map(|p| p.1);
+        nec = Some((
+            lats.clone().fold(f64::MIN, f64::max),
+            lons.clone().fold(f64::MIN, f64::max),
+        ));
+        swc = Some((lats.fold(f64::MAX, f64::min), lons.fold(f64::MAX, f64::min)));
+    }
+    let distances: Vec<f64> = points.iter().filter_map(|pt| pt.distance).collect();
+    let mut distance = 0.0;
+    if let (Some(first), Some(last)) = (distances.first(), distances.last()) {
+        distance = last - first;
+    }
+    let heart_rates: Vec<f64> = points.iter().filter_map(|pt| pt.heart_rate).collect();
+    let (avg_heart_rate, max_heart_rate) = avg_max(&heart_rates);
+    let cadences: Vec<f64> = points.iter().filter_map(|pt| pt.cadence).collect();
+    let (avg_cadence, max_cadence) = avg_max(&cadences);
+    let altitudes: Vec<f64> = points.iter().filter_map(|pt| pt.altitude).collect();
+    let mut ascent = 0.0;
+    let mut descent = 0.0;
+    for pair in altitudes.windows(2) {
+        let change = pair[1] - pair[0];
+        if change > 0.0 {
+            ascent += change;
+        } else {
+            descent -= change;
+        }
+    }
+    let max_speed = points.iter().filter_map(|pt| pt.speed).fold(0.0, f64::max);
+    return Totals {
+        start_time,
+        end_time,
+        start_position: positions.first().copied(),
+        end_position: positions.last().copied(),
+        elapsed_time,
+        distance,
+        avg_heart_rate,
+        max_heart_rate,
+        avg_cadence,
+        max_cadence,
+        ascent,
+        descent,
+        max_speed,
+        nec,
+        swc,
+    };
+}
+
+// Field numbers differ between the lap and session messages.
+fn summary_field_number(kind: MesgNum, name: &str) -> u8 {
+    let session = kind == MesgNum::Session;
+    match name {
+        "start_time" => return 2,
+        "start_position_lat" => return 3,
+        "start_position_long" => return 4,
+        "end_position_lat" => return 5,
+        "end_position_long" => return 6,
+        "total_elapsed_time" => return 7,
+        "total_timer_time" => return 8,
+        "total_distance" => return 9,
+        "total_calories" => return 11,
+        "avg_heart_rate" => return if session { 16 } else { 15 },
+        "max_heart_rate" => return if session { 17 } else { 16 },
+        "avg_running_cadence" => return if session { 18 } else { 17 },
+        "max_running_cadence" => return if session { 19 } else { 18 },
+        "total_ascent" => return if session { 22 } else { 21 },
+        "total_descent" => return if session { 23 } else { 22 },
+        "sport" => return if session { 5 } else { 25 },
+        "first_lap_index" => return 25,
+        "num_laps" => return 26,
+        "nec_lat" => return 29,
+        "nec_long" => return 30,
+        "swc_lat" => return 31,
+        "swc_long" => return 32,
+        "enhanced_avg_speed" => return if session { 124 } else { 110 },
+        "enhanced_max_speed" => return if session { 125 } else { 111 },
+        _ => return 253, // timestamp
+    }
+}
+
+// Build a lap or session message, in the field order a Garmin watch writes.
+fn summary_message(
+    kind: MesgNum,
+    points: &[TrackPoint],
+    sport: &str,
+    recorded: &LapValues,
+) -> FitDataRecord {
+    let mut totals = calculate_totals(points);
+    if let Some(distance) = recorded.distance {
+        totals.distance = distance;
+    }
+    let mut record = FitDataRecord::new(kind);
+    let push = |record: &mut FitDataRecord, name: &str, value: Value, units: &str| {
+        let number = summary_field_number(kind, name);
+        push_field(record, name, number, value, units);
+    };
+    if let Some(start_time) = totals.start_time {
+        push(&mut record, "start_time", timestamp_value(start_time), "");
+    }
+    if let Some((lat, lon)) = totals.start_position {
+        push(
+            &mut record,
+            "start_position_lat",
+            Value::SInt32(degrees_to_semi(lat)),
+            "semicircles",
+        );
+        push(
+            &mut record,
+            "start_position_long",
+            Value::SInt32(degrees_to_semi(lon)),
+            "semicircles",
+        );
+    }
+    if kind == MesgNum::Lap
+        && let Some((lat, lon)) = totals.end_position
+    {
+        push(
+            &mut record,
+            "end_position_lat",
+            Value::SInt32(degrees_to_semi(lat)),
+            "semicircles",
+        );
+        push(
+            &mut record,
+            "end_position_long",
+            Value::SInt32(degrees_to_semi(lon)),
+            "semicircles",
+        );
+    }
+    let timer_time = recorded.timer_time.unwrap_or(totals.elapsed_time);
+    push(
+        &mut record,
+        "total_elapsed_time",
+        Value::Float64(totals.elapsed_time),
+        "s",
+    );
+    push(
+        &mut record,
+        "total_timer_time",
+        Value::Float64(timer_time),
+        "s",
+    );
+    push(
+        &mut record,
+        "total_distance",
+        Value::Float64(totals.distance),
+        "m",
+    );
+    if let Some(calories) = recorded.calories {
+        push(
+            &mut record,
+            "total_calories",
+            Value::UInt16(calories as u16),
+            "kcal",
+        );
+    }
+    if let Some(avg_heart_rate) = totals.avg_heart_rate {
+        push(
+            &mut record,
+            "avg_heart_rate",
+            Value::UInt8(avg_heart_rate.round() as u8),
+            "bpm",
+        );
+    }
+    if let Some(max_heart_rate) = totals.max_heart_rate {
+        push(
+            &mut record,
+            "max_heart_rate",
+            Value::UInt8(max_heart_rate as u8),
+            "bpm",
+        );
+    }
+    if let Some(avg_cadence) = totals.avg_cadence {
+        push(
+            &mut record,
+            "avg_running_cadence",
+            Value::UInt8(avg_cadence.round() as u8),
+            "strides/min",
+        );
+    }
+    if let Some(max_cadence) = totals.max_cadence {
+        push(
+            &mut record,
+            "max_running_cadence",
+            Value::UInt8(max_cadence as u8),
+            "strides/min",
+        );
+    }
+    push(
+        &mut record,
+        "total_ascent",
+        Value::UInt16(totals.ascent.round() as u16),
+        "m",
+    );
+    push(
+        &mut record,
+        "total_descent",
+        Value::UInt16(totals.descent.round() as u16),
+        "m",
+    );
+    if kind == MesgNum::Session {
+        push(&mut record, "first_lap_index", Value::UInt16(0), "");
+        if let (Some(nec), Some(swc)) = (totals.nec, totals.swc) {
+            push(
+                &mut record,
+                "nec_lat",
+                Value::SInt32(degrees_to_semi(nec.0)),
+                "semicircles",
+            );
+            push(
+                &mut record,
+                "nec_long",
+                Value::SInt32(degrees_to_semi(nec.1)),
+                "semicircles",
+            );
+            push(
+                &mut record,
+                "swc_lat",
+                Value::SInt32(degrees_to_semi(swc.0)),
+                "semicircles",
+            );
+            push(
+                &mut record,
+                "swc_long",
+                Value::SInt32(degrees_to_semi(swc.1)),
+                "semicircles",
+            );
+        }
+    }
+    push(&mut record, "sport", Value::String(sport.to_string()), "");
+    if timer_time > 0.0 {
+        let avg_speed = totals.distance / timer_time;
+        push(
+            &mut record,
+            "enhanced_avg_speed",
+            Value::Float64(avg_speed),
+            "m/s",
+        );
+    }
+    push(
+        &mut record,
+        "enhanced_max_speed",
+        Value::Float64(totals.max_speed),
+        "m/s",
+    );
+    if let Some(end_time) = totals.end_time {
+        push(&mut record, "timestamp", timestamp_value(end_time), "s");
+    }
+    return record;
+}
+
+// Build a MesgNum::Lap message summarizing the track points of one lap.
+pub fn lap_message(points: &[TrackPoint], sport: &str, recorded: &LapValues) -> FitDataRecord {
+    return summary_message(MesgNum::Lap, points, sport, recorded);
+}
+
+// Build a MesgNum::Session message summarizing the whole activity.
+pub fn session_message(
+    points: &[TrackPoint],
+    sport: &str,
+    num_laps: usize,
+    recorded: &LapValues,
+) -> FitDataRecord {
+    let mut record = summary_message(MesgNum::Session, points, sport, recorded);
+    push_field(
+        &mut record,
+        "num_laps",
+        summary_field_number(MesgNum::Session, "num_laps"),
+        Value::UInt16(num_laps as u16),
+        "",
+    );
+    return record;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_field_value;
+
+    // Return a track point at a time into the run and a position in degrees.
+    fn point(second: i64, latitude: f64, longitude: f64) -> TrackPoint {
+        return TrackPoint {
+            time: DateTime::from_timestamp(1_714_550_400 + second, 0),
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            ..Default::default()
+        };
+    }
+
+    // Return the value of a field of a lap or session message.
+    fn value(record: &FitDataRecord, name: &str) -> Option<f64> {
+        return get_field_value(record, name);
+    }
+
+    #[test]
+    fn degrees_convert_to_semicircles() {
+        assert_eq!(degrees_to_semi(90.0), 1 << 30);
+        assert_eq!(degrees_to_semi(-180.0), i32::MIN);
+        assert_eq!(degrees_to_semi(0.0), 0);
+    }
+
+    #[test]
+    fn haversine_distance_of_a_degree_of_latitude() {
+        let meters = haversine_distance(0.0, 0.0, 1.0, 0.0);
+        assert!((meters - 111_195.0).abs() < 1.0);
+        assert_eq!(haversine_distance(45.0, 7.0, 45.0, 7.0), 0.0);
+    }
+
+    #[test]
+    fn distance_and_speed_are_derived_from_the_positions() {
+        // 30 m north every ten seconds.
+        let step = 30.0 / 111_195.0;
+        let mut points: Vec<TrackPoint> = (0..4)
+            .map(|i| point(i * 10, i as f64 * step, 0.0))
+            .collect();
+        derive_distance_and_speed(&mut points);
+        for (i, pt) in points.iter().enumerate() {
+            assert!((pt.distance.unwrap() - 30.0 * i as f64).abs() < 0.01);
+        }
+        assert_eq!(points[0].speed, Some(0.0));
+        assert!((points[3].speed.unwrap() - 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn recorded_distance_is_carried_over_gaps() {
+        let mut points = vec![point(0, 0.0, 0.0), point(10, 1.0, 0.0), point(20, 2.0, 0.0)];
+        points[0].distance = Some(0.0);
+        points[2].distance = Some(50.0);
+        points[1].speed = Some(4.0);
+        derive_distance_and_speed(&mut points);
+        assert_eq!(points[1].distance, Some(0.0));
+        assert_eq!(points[2].distance, Some(50.0));
+        // The recorded speed is kept, the missing one is measured.
+        assert_eq!(points[1].speed, Some(4.0));
+        assert_eq!(points[2].speed, Some(5.0));
+    }
+
+    #[test]
+    fn record_message_holds_the_values_present() {
+        let mut pt = point(0, 45.0, 7.0);
+        pt.heart_rate = Some(150.0);
+        pt.temperature = Some(18.4);
+        let record = record_message(&pt);
+        assert_eq!(record.kind(), MesgNum::Record);
+        assert_eq!(value(&record, "position_lat"), Some(536_870_912.0));
+        assert_eq!(value(&record, "heart_rate"), Some(150.0));
+        assert_eq!(value(&record, "temperature"), Some(18.0));
+        assert_eq!(value(&record, "cadence"), None);
+        assert_eq!(value(&record, "enhanced_speed"), None);
+    }
+
+    #[test]
+    fn lap_and_session_summarize_the_points() {
+        let mut points: Vec<TrackPoint> = (0..5).map(|i| point(i * 10, 0.0, 0.0)).collect();
+        for (i, pt) in points.iter_mut().enumerate() {
+            pt.distance = Some(25.0 * i as f64);
+            pt.speed = Some(2.5);
+            pt.heart_rate = Some(140.0 + 5.0 * i as f64);
+            pt.altitude = Some([100.0, 104.0, 102.0, 107.0, 101.0][i]);
+        }
+        let lap = lap_message(&points, "running", &LapValues::default());
+        assert_eq!(lap.kind(), MesgNum::Lap);
+        assert_eq!(value(&lap, "total_elapsed_time"), Some(40.0));
+        assert_eq!(value(&lap, "total_distance"), Some(100.0));
+        assert_eq!(value(&lap, "enhanced_avg_speed"), Some(2.5));
+        assert_eq!(value(&lap, "avg_heart_rate"), Some(150.0));
+        assert_eq!(value(&lap, "max_heart_rate"), Some(160.0));
+        assert_eq!(value(&lap, "total_ascent"), Some(9.0));
+        assert_eq!(value(&lap, "total_descent"), Some(8.0));
+        assert!(value(&lap, "num_laps").is_none());
+        let recorded = LapValues {
+            timer_time: Some(50.0),
+            distance: Some(110.0),
+            calories: Some(7.0),
+        };
+        let session = session_message(&points, "running", 3, &recorded);
+        assert_eq!(session.kind(), MesgNum::Session);
+        assert_eq!(value(&session, "total_elapsed_time"), Some(40.0));
+        assert_eq!(value(&session, "total_timer_time"), Some(50.0));
+        assert_eq!(value(&session, "total_distance"), Some(110.0));
+        assert_eq!(value(&session, "total_calories"), Some(7.0));
+        assert_eq!(value(&session, "enhanced_avg_speed"), Some(2.2));
+        assert_eq!(value(&session, "num_laps"), Some(3.0));
+    }
+}
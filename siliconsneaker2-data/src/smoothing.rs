@@ -0,0 +1,190 @@
+// Module containing the smoothing and spike removal filters applied to the
+// graphed series.  The filters return new series so that the raw values stay
+// available.
+
+// Filter applied to a graphed series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmoothingFilter {
+    Raw,
+    RollingMean,
+    RollingMedian,
+    Exponential,
+}
+
+// Deviation from the rolling median, in scaled median absolute deviations,
+// beyond which a point is taken as a spike (Hampel filter).
+const SPIKE_THRESHOLD: f32 = 3.0;
+
+// Scale making the median absolute deviation an estimate of the standard
+// deviation of normally distributed values.
+const MAD_SCALE: f32 = 1.4826;
+
+// Smallest scaled median absolute deviation as a share of the range of the
+// series.  Where the window is flat the deviation is 0, and every small step,
+// e.g. a heart rate changing by 1 bpm, would otherwise be taken as a spike.
+const MIN_MAD_SHARE: f32 = 0.02;
+
+// Return the median of a list of values, reordering them.
+fn median(values: &mut Vec<f32>) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        return (values[mid - 1] + values[mid]) / 2.0;
+    }
+    return values[mid];
+}
+
+// Return the range of indexes of a window of points centred on a point.
+fn centred_window(index: usize, window: usize, len: usize) -> std::ops::Range<usize> {
+    let half = window / 2;
+    return index.saturating_sub(half)..(index + half + 1).min(len);
+}
+
+// Return a series smoothed by a filter over a window of points.  The x values
+// are kept; the rolling filters are centred so that features do not shift.
+pub fn smooth(data: &Vec<(f32, f32)>, filter: SmoothingFilter, window: usize) -> Vec<(f32, f32)> {
+    if window < 2 || data.is_empty() {
+        return data.clone();
+    }
+    match filter {
+        SmoothingFilter::Raw => return data.clone(),
+        SmoothingFilter::RollingMean => {
+            // Prefix sums make each mean a subtraction.
+            let mut sums: Vec<f64> = Vec::with_capacity(data.len() + 1);
+            sums.push(0.0);
+            for (_, y) in data.iter() {
+                sums.push(sums[sums.len() - 1] + *y as f64);
+            }
+            return (0..data.len())
+                .map(|i| {
+                    let range = centred_window(i, window, data.len());
+                    let mean = (sums[range.end] - sums[range.start]) / range.len() as f64;
+                    (data[i].0, mean as f32)
+                })
+                .collect();
+        }
+        SmoothingFilter::RollingMedian => {
+            return (0..data.len())
+                .map(|i| {
+                    let mut values: Vec<f32> = data[centred_window(i, window, data.len())]
+                        .iter()
+                        .map(|p| p.1)
+                        .collect();
+                    (data[i].0, median(&mut values))
+                })
+                .collect();
+        }
+        SmoothingFilter::Exponential => {
+            // The weight giving the same mean age of the points as a rolling
+            // mean over the window.
+            let alpha = 2.0 / (window as f32 + 1.0);
+            let mut level = data[0].1;
+            return data
+                .iter()
+                .map(|(x, y)| {
+                    level += alpha * (y - level);
+                    (*x, level)
+                })
+                .collect();
+        }
+    }
+}
+
+// Return a series with the spikes replaced by the rolling median.  A point is
+// a spike when it lies further from the median of the window centred on it
+// than SPIKE_THRESHOLD scaled median absolute deviations, the deviation being
+// at least MIN_MAD_SHARE of the range of the series.
+pub fn remove_spikes(data: &Vec<(f32, f32)>, window: usize) -> Vec<(f32, f32)> {
+    let window = window.max(3);
+    let (low, high) = data
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), p| {
+            return (low.min(p.1), high.max(p.1));
+        });
+    let min_mad = MIN_MAD_SHARE * (high - low).max(0.0);
+    return (0..data.len())
+        .map(|i| {
+            let mut values: Vec<f32> = data[centred_window(i, window, data.len())]
+                .iter()
+                .map(|p| p.1)
+                .collect();
+            let centre = median(&mut values);
+            let mut deviations: Vec<f32> = values.iter().map(|v| (v - centre).abs()).collect();
+            let mad = (MAD_SCALE * median(&mut deviations)).max(min_mad);
+            if (data[i].1 - centre).abs() > SPIKE_THRESHOLD * mad {
+                return (data[i].0, centre);
+            }
+            return data[i];
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Return a series of values at x = 0, 1, 2...
+    fn series(values: &[f32]) -> Vec<(f32, f32)> {
+        return values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i as f32, *v))
+            .collect();
+    }
+
+    // Return the values of a series.
+    fn values(data: &[(f32, f32)]) -> Vec<f32> {
+        return data.iter().map(|p| p.1).collect();
+    }
+
+    #[test]
+    fn raw_and_short_windows_keep_the_series() {
+        let data = series(&[1.0, 5.0, 2.0]);
+        assert_eq!(smooth(&data, SmoothingFilter::Raw, 5), data);
+        assert_eq!(smooth(&data, SmoothingFilter::RollingMean, 1), data);
+        assert!(smooth(&Vec::new(), SmoothingFilter::RollingMedian, 5).is_empty());
+    }
+
+    #[test]
+    fn rolling_mean_is_centered() {
+        let data = series(&[0.0, 0.0, 3.0, 0.0, 0.0]);
+        let result = smooth(&data, SmoothingFilter::RollingMean, 3);
+        assert_eq!(values(&result), vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+        // The x values are kept.
+        assert_eq!(result[2].0, 2.0);
+    }
+
+    #[test]
+    fn rolling_median_drops_a_single_peak() {
+        let data = series(&[1.0, 1.0, 9.0, 1.0, 1.0]);
+        let result = smooth(&data, SmoothingFilter::RollingMedian, 3);
+        assert_eq!(values(&result), vec![1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn exponential_moves_towards_a_step() {
+        let data = series(&[0.0, 0.0, 10.0, 10.0, 10.0, 10.0]);
+        let result = values(&smooth(&data, SmoothingFilter::Exponential, 3));
+        assert_eq!(result[1], 0.0);
+        assert_eq!(result[2], 5.0);
+        assert!(result.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(result[5] < 10.0 && result[5] > 9.0);
+    }
+
+    #[test]
+    fn spikes_are_replaced_by_the_median() {
+        let mut raw = vec![100.0; 21];
+        raw[10] = 200.0;
+        let result = values(&remove_spikes(&series(&raw), 5));
+        assert_eq!(result, vec![100.0; 21]);
+    }
+
+    #[test]
+    fn small_steps_are_not_spikes() {
+        // A heart rate holding steady then rising by 1 bpm at a time.
+        let mut raw = vec![140.0; 20];
+        raw.extend((0..20).map(|i| 140.0 + i as f32));
+        let data = series(&raw);
+        assert_eq!(remove_spikes(&data, 7), data);
+    }
+}
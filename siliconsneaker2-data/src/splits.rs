@@ -0,0 +1,188 @@
+// Module containing the automatic splits, the activity divided at every
+// kilometre or mile whatever laps the device recorded.
+
+use crate::{Units, get_field_value};
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+
+// Smallest altitude change counted as ascent or descent, meters.  Smaller
+// changes are mostly altimeter noise and would inflate the totals.
+const ALTITUDE_THRESHOLD: f64 = 3.0;
+
+// The values of one split.
+#[derive(Clone, Debug, Default)]
+pub struct Split {
+    pub distance: f64, // meters, shorter than the split length for the last split
+    pub time: f64,     // elapsed seconds
+    pub avg_heart_rate: Option<f64>,
+    pub avg_cadence: Option<f64>,
+    pub ascent: f64,  // meters
+    pub descent: f64, // meters
+    pub start_index: usize,
+    pub end_index: usize,
+}
+
+impl Split {
+    // Average speed in m/s.
+    pub fn avg_speed(&self) -> f64 {
+        if self.time > 0.0 {
+            return self.distance / self.time;
+        }
+        return 0.0;
+    }
+}
+
+// Return the split length in meters for a unit system, a mile for US units
+// and a kilometre otherwise.
+pub fn split_length(user_unit: &Units) -> f64 {
+    match user_unit {
+        Units::US => return 1609.344,
+        _ => return 1000.0,
+    }
+}
+
+// Running sums of the records of the split being built.
+#[derive(Default)]
+struct SplitSums {
+    heart_rate: (f64, usize),
+    cadence: (f64, usize),
+}
+
+// Return the mean of a sum and count, if anything was counted.
+fn mean(sum: (f64, usize)) -> Option<f64> {
+    if sum.1 == 0 {
+        return None;
+    }
+    return Some(sum.0 / sum.1 as f64);
+}
+
+// Divide the activity into splits of split_length meters.  A split ends at
+// the first record at or past the next multiple of split_length from the
+// start, so boundaries do not drift; the remainder is a shorter final split.
+pub fn splits(data: &Vec<FitDataRecord>, split_length: f64) -> Vec<Split> {
+    let mut result: Vec<Split> = Vec::new();
+    let mut current = Split::default();
+    let mut sums = SplitSums::default();
+    let mut start: Option<(f64, f64)> = None; // distance and time the split started at
+    let mut last: Option<(f64, f64)> = None; // distance and time of the previous record
+    let mut anchor_altitude: Option<f64> = None; // altitude changes are measured from
+    let mut origin: Option<f64> = None; // distance of the first record
+    let mut index = 0;
+    for item in data {
+        if item.kind() != MesgNum::Record {
+            continue;
+        }
+        let record_index = index;
+        index += 1;
+        let (Some(distance), Some(time)) = (
+            get_field_value(item, "distance"),
+            get_field_value(item, "timestamp"),
+        ) else {
+            continue;
+        };
+        let (start_distance, start_time) = *start.get_or_insert((distance, time));
+        let origin = *origin.get_or_insert(distance);
+        if last.is_none() {
+            current.start_index = record_index;
+        }
+        if let Some(heart_rate) = get_field_value(item, "heart_rate") {
+            sums.heart_rate = (sums.heart_rate.0 + heart_rate, sums.heart_rate.1 + 1);
+        }
+        if let Some(cadence) = get_field_value(item, "cadence") {
+            sums.cadence = (sums.cadence.0 + cadence, sums.cadence.1 + 1);
+        }
+        let altitude =
+            get_field_value(item, "enhanced_altitude").or(get_field_value(item, "altitude"));
+        if let Some(altitude) = altitude {
+            let anchor = *anchor_altitude.get_or_insert(altitude);
+            if altitude - anchor >= ALTITUDE_THRESHOLD {
+                current.ascent += altitude - anchor;
+                anchor_altitude = Some(altitude);
+            } else if anchor - altitude >= ALTITUDE_THRESHOLD {
+                current.descent += anchor - altitude;
+                anchor_altitude = Some(altitude);
+            }
+        }
+        last = Some((distance, time));
+        current.end_index = record_index;
+        if distance - origin >= (result.len() + 1) as f64 * split_length {
+            current.distance = distance - start_distance;
+            current.time = time - start_time;
+            current.avg_heart_rate = mean(sums.heart_rate);
+            current.avg_cadence = mean(sums.cadence);
+            result.push(current);
+            current = Split {
+                start_index: record_index,
+                end_index: record_index,
+                ..Default::default()
+            };
+            sums = SplitSums::default();
+            start = Some((distance, time));
+        }
+    }
+    // The remainder, if the activity did not end on a boundary.
+    if let (Some((start_distance, start_time)), Some((distance, time))) = (start, last)
+        && distance > start_distance
+    {
+        current.distance = distance - start_distance;
+        current.time = time - start_time;
+        current.avg_heart_rate = mean(sums.heart_rate);
+        current.avg_cadence = mean(sums.cadence);
+        result.push(current);
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    #[test]
+    fn split_length_follows_the_unit_system() {
+        assert_eq!(split_length(&Units::Metric), 1000.0);
+        assert_eq!(split_length(&Units::US), 1609.344);
+    }
+
+    #[test]
+    fn splits_end_at_every_multiple_with_a_shorter_remainder() {
+        // 2500 m at 2.5 m/s.
+        let result = splits(&steady_run(1001, 2.5, 150.0), 1000.0);
+        assert_eq!(result.len(), 3);
+        let distances: Vec<f64> = result.iter().map(|s| s.distance).collect();
+        assert_eq!(distances, vec![1000.0, 1000.0, 500.0]);
+        let times: Vec<f64> = result.iter().map(|s| s.time).collect();
+        assert_eq!(times, vec![400.0, 400.0, 200.0]);
+        assert_eq!((result[1].start_index, result[1].end_index), (400, 800));
+        assert_eq!(result[2].end_index, 1000);
+        for split in result.iter() {
+            assert_eq!(split.avg_speed(), 2.5);
+            assert_eq!(split.avg_heart_rate, Some(150.0));
+            assert_eq!(split.avg_cadence, Some(85.0));
+        }
+    }
+
+    #[test]
+    fn altitude_noise_is_not_counted_as_climbing() {
+        // Up 20 m over the first kilometer with 1 m of noise, then down again.
+        let points = run_points(801, |i| {
+            let noise = if i % 2 == 0 { 1.0 } else { 0.0 };
+            let altitude = 100.0 + 20.0 * (400.0 - (i as f64 - 400.0).abs()) / 400.0;
+            return (2.5, 150.0, altitude + noise);
+        });
+        let result = splits(&activity(&points), 1000.0);
+        assert_eq!(result.len(), 2);
+        assert!(result[0].ascent >= 15.0 && result[0].ascent <= 21.0);
+        assert!(result[0].descent <= 3.0);
+        assert!(result[1].descent >= 15.0 && result[1].descent <= 21.0);
+        assert!(result[1].ascent <= 3.0);
+    }
+
+    #[test]
+    fn activity_without_distance_has_no_splits() {
+        let mut points = run_points(100, |_| (2.5, 150.0, 100.0));
+        for point in points.iter_mut() {
+            point.distance = None;
+        }
+        assert!(splits(&activity(&points), 1000.0).is_empty());
+    }
+}
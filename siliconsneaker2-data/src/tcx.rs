@@ -0,0 +1,193 @@
+// Module containing the TCX (Garmin Training Center XML) reader.
+
+use crate::records::{
+    LapValues, TrackPoint, derive_distance_and_speed, lap_message, record_message, session_message,
+};
+use crate::xml::{child, descendant, number, parse_time};
+use fitparser::FitDataRecord;
+use roxmltree::Node;
+use std::error::Error;
+
+// Map the TCX sport attribute to the FIT sport name.
+fn fit_sport(tcx_sport: &str) -> String {
+    match tcx_sport {
+        "Running" => return String::from("running"),
+        "Biking" => return String::from("cycling"),
+        _ => return String::from("generic"),
+    }
+}
+
+// Read the values of a single Trackpoint element.
+fn parse_trackpoint(node: Node) -> TrackPoint {
+    let position = child(node, "Position");
+    return TrackPoint {
+        time: child(node, "Time")
+            .and_then(|n| n.text())
+            .and_then(parse_time),
+        latitude: position.and_then(|p| number(child(p, "LatitudeDegrees"))),
+        longitude: position.and_then(|p| number(child(p, "LongitudeDegrees"))),
+        altitude: number(child(node, "AltitudeMeters")),
+        distance: number(child(node, "DistanceMeters")),
+        // Speed and running cadence live in the ActivityExtension TPX element.
+        speed: number(descendant(node, "Speed")),
+        heart_rate: child(node, "HeartRateBpm").and_then(|n| number(child(n, "Value"))),
+        cadence: number(descendant(node, "RunCadence")).or(number(child(node, "Cadence"))),
+        temperature: None,
+    };
+}
+
+// Convert a TCX document into the records, laps and session of a FIT activity.
+pub fn parse_tcx(text: &str) -> Result<Vec<FitDataRecord>, Box<dyn Error>> {
+    let doc = roxmltree::Document::parse(text)?;
+    let activity = descendant(doc.root(), "Activity").ok_or("No Activity in TCX file.")?;
+    let sport = fit_sport(activity.attribute("Sport").unwrap_or(""));
+    // Each lap is the range of its track points plus the totals the lap holds.
+    let mut points: Vec<TrackPoint> = Vec::new();
+    let mut laps: Vec<(std::ops::Range<usize>, LapValues)> = Vec::new();
+    for lap in activity
+        .children()
+        .filter(|n| n.is_element() && n.tag_name().name() == "Lap")
+    {
+        let first = points.len();
+        for trackpoint in lap
+            .descendants()
+            .filter(|n| n.is_element() && n.tag_name().name() == "Trackpoint")
+        {
+            points.push(parse_trackpoint(trackpoint));
+        }
+        let recorded = LapValues {
+            timer_time: number(child(lap, "TotalTimeSeconds")),
+            distance: number(child(lap, "DistanceMeters")),
+            calories: number(child(lap, "Calories")),
+        };
+        laps.push((first..points.len(), recorded));
+    }
+    if points.is_empty() {
+        return Err("No Trackpoints in TCX file.".into());
+    }
+    derive_distance_and_speed(&mut points);
+    let mut data: Vec<FitDataRecord> = Vec::new();
+    let mut session = LapValues::default();
+    for (range, recorded) in laps.iter() {
+        for pt in points[range.clone()].iter() {
+            data.push(record_message(pt));
+        }
+        data.push(lap_message(&points[range.clone()], &sport, recorded));
+        if let Some(t) = recorded.timer_time {
+            session.timer_time = Some(session.timer_time.unwrap_or(0.0) + t);
+        }
+        if let Some(d) = recorded.distance {
+            session.distance = Some(session.distance.unwrap_or(0.0) + d);
+        }
+        if let Some(c) = recorded.calories {
+            session.calories = Some(session.calories.unwrap_or(0.0) + c);
+        }
+    }
+    data.push(session_message(&points, &sport, laps.len(), &session));
+    return Ok(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::degrees_to_semi;
+    use crate::{get_field_value, get_sess_record_field};
+    use fitparser::profile::field_types::MesgNum;
+
+    const TCX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+  xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+  <Activities>
+    <Activity Sport="Running">
+      <Id>2024-05-01T08:00:00Z</Id>
+      <Lap StartTime="2024-05-01T08:00:00Z">
+        <TotalTimeSeconds>10</TotalTimeSeconds>
+        <DistanceMeters>30</DistanceMeters>
+        <Calories>2</Calories>
+        <Track>
+          <Trackpoint>
+            <Time>2024-05-01T08:00:00Z</Time>
+            <Position>
+              <LatitudeDegrees>45.0</LatitudeDegrees>
+              <LongitudeDegrees>7.0</LongitudeDegrees>
+            </Position>
+            <AltitudeMeters>250.0</AltitudeMeters>
+            <DistanceMeters>0.0</DistanceMeters>
+            <HeartRateBpm><Value>140</Value></HeartRateBpm>
+            <Extensions><ns3:TPX><ns3:Speed>3.0</ns3:Speed><ns3:RunCadence>85</ns3:RunCadence></ns3:TPX></Extensions>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-05-01T08:00:10Z</Time>
+            <Position>
+              <LatitudeDegrees>45.00027</LatitudeDegrees>
+              <LongitudeDegrees>7.0</LongitudeDegrees>
+            </Position>
+            <AltitudeMeters>251.0</AltitudeMeters>
+            <DistanceMeters>30.0</DistanceMeters>
+            <HeartRateBpm><Value>150</Value></HeartRateBpm>
+            <Extensions><ns3:TPX><ns3:Speed>3.0</ns3:Speed><ns3:RunCadence>86</ns3:RunCadence></ns3:TPX></Extensions>
+          </Trackpoint>
+        </Track>
+      </Lap>
+      <Lap StartTime="2024-05-01T08:00:10Z">
+        <TotalTimeSeconds>10</TotalTimeSeconds>
+        <DistanceMeters>40</DistanceMeters>
+        <Track>
+          <Trackpoint>
+            <Time>2024-05-01T08:00:20Z</Time>
+            <DistanceMeters>70.0</DistanceMeters>
+            <HeartRateBpm><Value>160</Value></HeartRateBpm>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+
+    #[test]
+    fn trackpoints_become_records_and_laps() {
+        let data = parse_tcx(TCX).unwrap();
+        let kinds: Vec<MesgNum> = data.iter().map(|item| item.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                MesgNum::Record,
+                MesgNum::Record,
+                MesgNum::Lap,
+                MesgNum::Record,
+                MesgNum::Lap,
+                MesgNum::Session
+            ]
+        );
+        let first = &data[0];
+        let latitude = get_field_value(first, "position_lat");
+        assert_eq!(latitude, Some(degrees_to_semi(45.0) as f64));
+        assert_eq!(get_field_value(first, "heart_rate"), Some(140.0));
+        assert_eq!(get_field_value(first, "cadence"), Some(85.0));
+        assert_eq!(get_field_value(first, "enhanced_speed"), Some(3.0));
+        assert_eq!(get_field_value(first, "enhanced_altitude"), Some(250.0));
+        // The point without a position has no position fields.
+        assert_eq!(get_field_value(&data[3], "position_lat"), None);
+        assert_eq!(get_field_value(&data[3], "distance"), Some(70.0));
+    }
+
+    #[test]
+    fn session_adds_up_the_recorded_lap_totals() {
+        let data = parse_tcx(TCX).unwrap();
+        assert_eq!(get_sess_record_field(&data, "total_distance"), 70.0);
+        assert_eq!(get_sess_record_field(&data, "total_timer_time"), 20.0);
+        assert_eq!(get_sess_record_field(&data, "total_calories"), 2.0);
+        assert_eq!(get_sess_record_field(&data, "num_laps"), 2.0);
+        assert_eq!(get_sess_record_field(&data, "max_heart_rate"), 160.0);
+    }
+
+    #[test]
+    fn files_without_trackpoints_are_refused() {
+        assert!(parse_tcx("<TrainingCenterDatabase/>").is_err());
+        let empty = r#"<TrainingCenterDatabase><Activities><Activity Sport="Biking">
+            <Lap><TotalTimeSeconds>10</TotalTimeSeconds></Lap>
+            </Activity></Activities></TrainingCenterDatabase>"#;
+        assert!(parse_tcx(empty).is_err());
+        assert!(parse_tcx("not xml").is_err());
+    }
+}
@@ -0,0 +1,65 @@
+// Module containing the activities the unit tests are run on: synthetic runs
+// built from track points and the recorded runs in the runs folder.
+
+use crate::read_activity;
+use crate::records::{LapValues, TrackPoint, lap_message, record_message, session_message};
+use chrono::DateTime;
+use fitparser::FitDataRecord;
+use std::fs::File;
+use std::path::Path;
+
+// Time the synthetic runs start at, 2024-05-01 08:00:00 UTC.
+const START_TIME: i64 = 1_714_550_400;
+
+// Meters per degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_195.0;
+
+// Return n track points one second apart heading north along the prime
+// meridian.  The speed (m/s), heart rate (bpm) and altitude (m) of each second
+// are given by a function of its index; the distance is the speed summed.
+pub fn run_points(n: usize, values: impl Fn(usize) -> (f64, f64, f64)) -> Vec<TrackPoint> {
+    let mut points: Vec<TrackPoint> = Vec::with_capacity(n);
+    let mut distance = 0.0;
+    for i in 0..n {
+        let (speed, heart_rate, altitude) = values(i);
+        if i > 0 {
+            distance += speed;
+        }
+        points.push(TrackPoint {
+            time: DateTime::from_timestamp(START_TIME + i as i64, 0),
+            latitude: Some(distance / METERS_PER_DEGREE),
+            longitude: Some(0.0),
+            altitude: Some(altitude),
+            distance: Some(distance),
+            speed: Some(speed),
+            heart_rate: Some(heart_rate),
+            cadence: Some(85.0),
+            temperature: Some(20.0),
+        });
+    }
+    return points;
+}
+
+// Return the records of the track points followed by a single lap and the
+// session, as a FIT file of a running activity holds them.
+pub fn activity(points: &[TrackPoint]) -> Vec<FitDataRecord> {
+    let recorded = LapValues::default();
+    let mut data: Vec<FitDataRecord> = points.iter().map(record_message).collect();
+    data.push(lap_message(points, "running", &recorded));
+    data.push(session_message(points, "running", 1, &recorded));
+    return data;
+}
+
+// Return a run of n seconds at a steady speed (m/s) and heart rate (bpm) on
+// flat ground.
+pub fn steady_run(n: usize, speed: f64, heart_rate: f64) -> Vec<FitDataRecord> {
+    return activity(&run_points(n, |_| (speed, heart_rate, 100.0)));
+}
+
+// Read one of the recorded activities of the runs folder.
+pub fn recorded_run(name: &str) -> Vec<FitDataRecord> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../runs")
+        .join(name);
+    return read_activity(&mut File::open(path).unwrap()).unwrap();
+}
@@ -0,0 +1,225 @@
+// Module containing the slippy map tile arithmetic used to show a track and
+// keep map tiles on disk: the view fitting a track, the tiles covering it and
+// where a cached tile is stored.
+
+use crate::semi_to_degrees;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+// Latitude limit of the Web Mercator projection, degrees.
+const MAX_LATITUDE: f64 = 85.051_128_78;
+
+// Share of the view left around a track fitted to it.
+const FIT_MARGIN: f64 = 0.1;
+
+// The extent of a track in degrees.
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub north: f64,
+    pub south: f64,
+    pub east: f64,
+    pub west: f64,
+}
+
+// Return the extent of a path of positions in semicircles, or None for an
+// empty path.
+pub fn path_bounds(run_path: &Vec<(f64, f64)>) -> Option<Bounds> {
+    let (first_lat, first_lon) = run_path.first()?;
+    let mut bounds = Bounds {
+        north: semi_to_degrees(*first_lat),
+        south: semi_to_degrees(*first_lat),
+        east: semi_to_degrees(*first_lon),
+        west: semi_to_degrees(*first_lon),
+    };
+    for (lat, lon) in run_path.iter() {
+        let (lat, lon) = (semi_to_degrees(*lat), semi_to_degrees(*lon));
+        bounds.north = bounds.north.max(lat);
+        bounds.south = bounds.south.min(lat);
+        bounds.east = bounds.east.max(lon);
+        bounds.west = bounds.west.min(lon);
+    }
+    return Some(bounds);
+}
+
+// Return the Web Mercator position of a point as fractions of the width and
+// height of the world map, from the west and the north.
+fn mercator(latitude: f64, longitude: f64) -> (f64, f64) {
+    let latitude = latitude.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    return (
+        (longitude + 180.0) / 360.0,
+        (1.0 - latitude.tan().asinh() / PI) / 2.0,
+    );
+}
+
+// Return the latitude of a Web Mercator position from the north.
+fn mercator_latitude(y: f64) -> f64 {
+    return (PI * (1.0 - 2.0 * y)).sinh().atan().to_degrees();
+}
+
+// Return the column and row of the tile holding a position at a zoom level.
+pub fn tile_of(latitude: f64, longitude: f64, zoom: u32) -> (u32, u32) {
+    let count = (1u64 << zoom) as f64;
+    let (x, y) = mercator(latitude, longitude);
+    let (x, y) = (x * count, y * count);
+    let last = count - 1.0;
+    return (x.clamp(0.0, last) as u32, y.clamp(0.0, last) as u32);
+}
+
+// Return the latitude and longitude of the center of an extent and the
+// highest zoom level, up to max_zoom, at which it fits a view of width by
+// height pixels drawn with tiles of tile_size pixels, less a margin.
+pub fn fit_bounds(
+    bounds: &Bounds,
+    width: f64,
+    height: f64,
+    tile_size: f64,
+    max_zoom: f64,
+) -> (f64, f64, f64) {
+    let (west, north) = mercator(bounds.north, bounds.west);
+    let (east, south) = mercator(bounds.south, bounds.east);
+    let center = (
+        mercator_latitude((north + south) / 2.0),
+        (bounds.west + bounds.east) / 2.0,
+    );
+    // The extent is in world widths, which are tile_size pixels at zoom 0
+    // and twice as many at each level above.
+    let usable = 1.0 - 2.0 * FIT_MARGIN;
+    let scale = f64::min(
+        width * usable / ((east - west) * tile_size),
+        height * usable / ((south - north) * tile_size),
+    );
+    // A single point, or a view not laid out yet, is shown close up.
+    if !scale.is_finite() || scale <= 0.0 {
+        return (center.0, center.1, max_zoom);
+    }
+    return (
+        center.0,
+        center.1,
+        scale.log2().floor().clamp(0.0, max_zoom),
+    );
+}
+
+// Return the zoom level, column and row of every tile covering an extent
+// from min_zoom to max_zoom.  The highest zoom levels are left out until
+// there are no more than max_tiles tiles.
+pub fn tiles_for_bounds(
+    bounds: &Bounds,
+    min_zoom: u32,
+    max_zoom: u32,
+    max_tiles: usize,
+) -> Vec<(u32, u32, u32)> {
+    let mut tiles: Vec<(u32, u32, u32)> = Vec::new();
+    for zoom in min_zoom..=max_zoom {
+        let (west, north) = tile_of(bounds.north, bounds.west, zoom);
+        let (east, south) = tile_of(bounds.south, bounds.east, zoom);
+        let count = (east - west + 1) as usize * (south - north + 1) as usize;
+        if tiles.len() + count > max_tiles {
+            break;
+        }
+        for x in west..=east {
+            for y in north..=south {
+                tiles.push((zoom, x, y));
+            }
+        }
+    }
+    return tiles;
+}
+
+// Return the file a tile of a map source is cached in.
+pub fn cached_tile_path(cache_dir: &Path, source_id: &str, zoom: u32, x: u32, y: u32) -> PathBuf {
+    return cache_dir
+        .join(source_id)
+        .join(zoom.to_string())
+        .join(x.to_string())
+        .join(format!("{}.tile", y));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Return the extent of a square of sides of size degrees around a point.
+    fn square(latitude: f64, longitude: f64, size: f64) -> Bounds {
+        return Bounds {
+            north: latitude + size / 2.0,
+            south: latitude - size / 2.0,
+            east: longitude + size / 2.0,
+            west: longitude - size / 2.0,
+        };
+    }
+
+    #[test]
+    fn path_bounds_span_the_positions() {
+        assert!(path_bounds(&Vec::new()).is_none());
+        let semi = 2f64.powi(31) / 180.0;
+        let path = vec![(10.0 * semi, -5.0 * semi), (12.0 * semi, -3.0 * semi)];
+        let bounds = path_bounds(&path).unwrap();
+        assert!((bounds.north - 12.0).abs() < 1e-5);
+        assert!((bounds.south - 10.0).abs() < 1e-5);
+        assert!((bounds.east + 3.0).abs() < 1e-5);
+        assert!((bounds.west + 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tile_of_counts_from_the_north_west() {
+        assert_eq!(tile_of(0.0, 0.0, 0), (0, 0));
+        assert_eq!(tile_of(10.0, -10.0, 1), (0, 0));
+        assert_eq!(tile_of(-10.0, 10.0, 1), (1, 1));
+        // Positions beyond the projection fall in the edge tiles.
+        assert_eq!(tile_of(89.0, 180.0, 2), (3, 0));
+    }
+
+    #[test]
+    fn fit_bounds_centers_the_extent() {
+        let (latitude, longitude, zoom) =
+            fit_bounds(&square(0.0, 20.0, 1.0), 800.0, 600.0, 256.0, 19.0);
+        assert!(latitude.abs() < 1e-9);
+        assert!((longitude - 20.0).abs() < 1e-9);
+        // A degree is 2^zoom * 256 / 360 pixels, and fits 480 pixels at zoom 9.
+        assert_eq!(zoom, 9.0);
+    }
+
+    #[test]
+    fn fit_bounds_zooms_out_for_larger_extents() {
+        let (_, _, small) = fit_bounds(&square(45.0, 7.0, 0.01), 800.0, 600.0, 256.0, 19.0);
+        let (_, _, large) = fit_bounds(&square(45.0, 7.0, 1.0), 800.0, 600.0, 256.0, 19.0);
+        assert!(large < small);
+        let (_, _, world) = fit_bounds(&square(0.0, 0.0, 300.0), 800.0, 600.0, 256.0, 19.0);
+        assert_eq!(world, 0.0);
+    }
+
+    #[test]
+    fn fit_bounds_shows_a_single_point_close_up() {
+        let (latitude, longitude, zoom) =
+            fit_bounds(&square(45.0, 7.0, 0.0), 800.0, 600.0, 256.0, 17.0);
+        assert!((latitude - 45.0).abs() < 1e-9);
+        assert_eq!(longitude, 7.0);
+        assert_eq!(zoom, 17.0);
+        let (_, _, zoom) = fit_bounds(&square(45.0, 7.0, 1.0), 0.0, 0.0, 256.0, 17.0);
+        assert_eq!(zoom, 17.0);
+    }
+
+    #[test]
+    fn tiles_cover_the_extent_at_every_zoom_level() {
+        let tiles = tiles_for_bounds(&square(-10.0, 10.0, 2.0), 0, 2, 100);
+        assert_eq!(tiles, vec![(0, 0, 0), (1, 1, 1), (2, 2, 2)]);
+        // An extent across the equator and prime meridian takes four tiles a level.
+        let tiles = tiles_for_bounds(&square(0.0, 0.0, 2.0), 1, 3, 100);
+        assert_eq!(tiles.len(), 12);
+        assert!(tiles.contains(&(3, 3, 4)));
+    }
+
+    #[test]
+    fn tiles_leave_out_the_highest_zoom_levels_over_the_limit() {
+        let tiles = tiles_for_bounds(&square(0.0, 0.0, 2.0), 1, 3, 10);
+        assert_eq!(tiles.len(), 8);
+        assert!(tiles.iter().all(|(zoom, _, _)| *zoom <= 2));
+        assert!(tiles_for_bounds(&square(0.0, 0.0, 2.0), 1, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn cached_tile_path_nests_source_zoom_and_column() {
+        let path = cached_tile_path(Path::new("/cache"), "osm", 12, 2048, 1361);
+        assert_eq!(path, PathBuf::from("/cache/osm/12/2048/1361.tile"));
+    }
+}
@@ -0,0 +1,365 @@
+// Module containing the coloring of the map track by a metric of the
+// records: the class each point of the track falls in and the range of
+// values of each class.
+
+use crate::gap::grades;
+use crate::smoothing::{SmoothingFilter, smooth};
+use crate::zones::{HeartRateZones, heart_rate_time_in_zone, heart_rate_zone_top, zone_of};
+use crate::{get_field_value, hr_zone_bounds};
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+
+// Number of classes the pace, elevation and grade are divided into.
+pub const TRACK_CLASSES: usize = 5;
+
+// Points the values are smoothed over, so that the color does not flicker
+// from point to point along the track.
+const TRACK_SMOOTHING_WINDOW: usize = 30;
+
+// Fewest points of a stretch of one class.  Shorter stretches take the class
+// of the stretch before them, so that a value hovering at a class limit does
+// not break the track into many short segments.
+const TRACK_MIN_SEGMENT: usize = 15;
+
+// Share of the values left out at each end of their range, so that a few
+// outliers, e.g. standing at a crossing, do not squash the other classes.
+const TRACK_OUTLIER_SHARE: f64 = 0.02;
+
+// Metric the track is colored by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrackMetric {
+    Pace,
+    HeartRateZone,
+    Elevation,
+    Grade,
+}
+
+// The class of each point of the track and the lower and upper value of each
+// class, slowest, lowest or most downhill first.
+pub struct TrackColoring {
+    pub classes: Vec<Option<usize>>, // None for points without a value
+    pub bounds: Vec<(f64, f64)>,     // m/s, bpm, m or rise over run
+}
+
+// Return the records with a position, the points of MapCache::run_path.
+fn track_records(data: &Vec<FitDataRecord>) -> Vec<&FitDataRecord> {
+    return data
+        .iter()
+        .filter(|item| {
+            item.kind() == MesgNum::Record
+                && get_field_value(item, "position_lat").is_some()
+                && get_field_value(item, "position_long").is_some()
+        })
+        .collect();
+}
+
+// Return the grade at each record, None where the record has no distance or
+// altitude.
+fn record_grades(records: &[&FitDataRecord]) -> Vec<Option<f64>> {
+    let mut indexes: Vec<usize> = Vec::new();
+    let mut distance: Vec<f64> = Vec::new();
+    let mut altitude: Vec<f64> = Vec::new();
+    for (i, item) in records.iter().enumerate() {
+        if let (Some(d), Some(a)) = (
+            get_field_value(item, "distance"),
+            get_field_value(item, "enhanced_altitude"),
+        ) {
+            indexes.push(i);
+            distance.push(d);
+            altitude.push(a);
+        }
+    }
+    let mut result: Vec<Option<f64>> = vec![None; records.len()];
+    for (i, grade) in indexes.iter().zip(grades(&distance, &altitude)) {
+        result[*i] = Some(grade);
+    }
+    return result;
+}
+
+// Return the values smoothed by a rolling mean over the points that have one.
+fn smooth_values(values: Vec<Option<f64>>) -> Vec<Option<f64>> {
+    let present: Vec<(f32, f32)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|v| (i as f32, v as f32)))
+        .collect();
+    let mut result: Vec<Option<f64>> = vec![None; values.len()];
+    for (i, v) in smooth(
+        &present,
+        SmoothingFilter::RollingMean,
+        TRACK_SMOOTHING_WINDOW,
+    ) {
+        result[i as usize] = Some(v as f64);
+    }
+    return result;
+}
+
+// Return the value of a metric at each point of the track: the speed (m/s),
+// heart rate (bpm), altitude (m) or grade (rise over run).
+pub fn track_values(data: &Vec<FitDataRecord>, metric: TrackMetric) -> Vec<Option<f64>> {
+    let records = track_records(data);
+    match metric {
+        TrackMetric::Pace => {
+            let speeds = records
+                .iter()
+                .map(|item| {
+                    get_field_value(item, "enhanced_speed").or(get_field_value(item, "speed"))
+                })
+                .collect();
+            return smooth_values(speeds);
+        }
+        TrackMetric::HeartRateZone => {
+            let heart_rates = records
+                .iter()
+                .map(|item| get_field_value(item, "heart_rate"))
+                .collect();
+            return smooth_values(heart_rates);
+        }
+        TrackMetric::Elevation => {
+            let altitudes = records
+                .iter()
+                .map(|item| {
+                    get_field_value(item, "enhanced_altitude").or(get_field_value(item, "altitude"))
+                })
+                .collect();
+            return smooth_values(altitudes);
+        }
+        TrackMetric::Grade => return smooth_values(record_grades(&records)),
+    }
+}
+
+// Return TRACK_CLASSES classes of equal width over the range of the values,
+// or a single class when they are all the same.
+fn equal_classes(values: &[Option<f64>]) -> Option<Vec<(f64, f64)>> {
+    let mut sorted: Vec<f64> = values.iter().flatten().copied().collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let skip = (sorted.len() as f64 * TRACK_OUTLIER_SHARE) as usize;
+    let (low, high) = (sorted[skip], sorted[sorted.len() - 1 - skip]);
+    if high <= low {
+        return Some(vec![(low, high)]);
+    }
+    let step = (high - low) / TRACK_CLASSES as f64;
+    return Some(
+        (0..TRACK_CLASSES)
+            .map(|k| (low + k as f64 * step, low + (k + 1) as f64 * step))
+            .collect(),
+    );
+}
+
+// Give the stretches of fewer than TRACK_MIN_SEGMENT points of one class the
+// class of the stretch before them.  Points without a value are passed over.
+fn merge_short_segments(classes: &mut [Option<usize>]) {
+    let points: Vec<usize> = (0..classes.len())
+        .filter(|i| classes[*i].is_some())
+        .collect();
+    let mut kept: Option<usize> = None; // class of the last stretch kept
+    let mut start = 0;
+    while start < points.len() {
+        let class = classes[points[start]];
+        let end = (start..points.len())
+            .find(|k| classes[points[*k]] != class)
+            .unwrap_or(points.len());
+        if end - start < TRACK_MIN_SEGMENT && kept.is_some() {
+            for k in start..end {
+                classes[points[k]] = kept;
+            }
+        } else {
+            kept = class;
+        }
+        start = end;
+    }
+}
+
+// Return the coloring of the track by a metric, or None when the records
+// do not hold it.  Heart rate is classed by the user's zones, the other
+// metrics by equal steps over the activity's range.
+pub fn color_track(
+    data: &Vec<FitDataRecord>,
+    metric: TrackMetric,
+    hr_zones: &HeartRateZones,
+) -> Option<TrackColoring> {
+    let values = track_values(data, metric);
+    let bounds: Vec<(f64, f64)> = match metric {
+        TrackMetric::HeartRateZone => {
+            let (_, zone_limits) = heart_rate_time_in_zone(data, hr_zones)?;
+            let top_heart_rate = heart_rate_zone_top(data, hr_zones);
+            (0..=zone_limits.len())
+                .map(|z| hr_zone_bounds(&zone_limits, z, top_heart_rate))
+                .collect()
+        }
+        _ => equal_classes(&values)?,
+    };
+    // Values beyond the outer classes fall in them.
+    let limits: Vec<f64> = bounds.iter().skip(1).map(|(low, _)| *low).collect();
+    let mut classes: Vec<Option<usize>> = values
+        .iter()
+        .map(|v| v.map(|v| zone_of(&limits, v)))
+        .collect();
+    merge_short_segments(&mut classes);
+    return Some(TrackColoring { classes, bounds });
+}
+
+// Return the index of the track point at the end of each lap, in lap order:
+// the last point recorded at or before the lap's timestamp.
+pub fn lap_end_points(data: &Vec<FitDataRecord>) -> Vec<usize> {
+    let times: Vec<Option<f64>> = track_records(data)
+        .iter()
+        .map(|item| get_field_value(item, "timestamp"))
+        .collect();
+    if times.is_empty() {
+        return Vec::new();
+    }
+    return data
+        .iter()
+        .filter(|item| item.kind() == MesgNum::Lap)
+        .filter_map(|lap| get_field_value(lap, "timestamp"))
+        .map(|end| {
+            times
+                .iter()
+                .rposition(|t| t.is_some_and(|t| t <= end))
+                .unwrap_or(0)
+        })
+        .collect();
+}
+
+// Return the index of the track point at each whole multiple of a distance
+// (m), e.g. every kilometer, with the number of the multiple.
+pub fn distance_mark_points(data: &Vec<FitDataRecord>, mark_length: f64) -> Vec<(usize, u32)> {
+    let mut marks: Vec<(usize, u32)> = Vec::new();
+    let mut next = 1;
+    for (i, item) in track_records(data).iter().enumerate() {
+        if let Some(distance) = get_field_value(item, "distance")
+            && distance >= next as f64 * mark_length
+        {
+            let mark = (distance / mark_length) as u32;
+            marks.push((i, mark));
+            next = mark + 1;
+        }
+    }
+    return marks;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    // Return the number of stretches of one class along the track.
+    fn segments(classes: &[Option<usize>]) -> usize {
+        return 1 + classes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    }
+
+    #[test]
+    fn short_stretches_take_the_class_before_them() {
+        let mut classes: Vec<Option<usize>> = vec![Some(0); 20];
+        classes.extend([Some(1); 3]);
+        classes.extend([Some(0); 20]);
+        merge_short_segments(&mut classes);
+        assert_eq!(classes, vec![Some(0); 43]);
+    }
+
+    #[test]
+    fn long_stretches_and_the_first_stretch_are_kept() {
+        let mut classes: Vec<Option<usize>> = vec![Some(2); 3];
+        classes.extend([Some(0); TRACK_MIN_SEGMENT]);
+        classes.extend([Some(1); TRACK_MIN_SEGMENT]);
+        let expected = classes.clone();
+        merge_short_segments(&mut classes);
+        assert_eq!(classes, expected);
+    }
+
+    #[test]
+    fn points_without_a_value_are_passed_over() {
+        let mut classes: Vec<Option<usize>> = vec![Some(0); 20];
+        classes.extend([None, Some(1), None, Some(1), None]);
+        classes.extend([Some(0); 20]);
+        merge_short_segments(&mut classes);
+        assert_eq!(classes[21], Some(0));
+        assert_eq!(classes[23], Some(0));
+        assert_eq!(classes[20], None);
+        assert_eq!(classes[24], None);
+    }
+
+    #[test]
+    fn values_follow_the_points_with_a_position() {
+        let mut points = run_points(11, |_| (3.0, 150.0, 100.0));
+        points[5].latitude = None;
+        let data = activity(&points);
+        let values = track_values(&data, TrackMetric::Pace);
+        assert_eq!(values.len(), 10);
+        assert!(
+            values
+                .iter()
+                .all(|v| v.is_some_and(|v| (v - 3.0).abs() < 1e-6))
+        );
+    }
+
+    #[test]
+    fn flat_steady_run_is_a_single_class() {
+        let data = steady_run(301, 3.0, 150.0);
+        for metric in [
+            TrackMetric::Pace,
+            TrackMetric::Elevation,
+            TrackMetric::Grade,
+        ] {
+            let coloring = color_track(&data, metric, &HeartRateZones::default()).unwrap();
+            assert_eq!(coloring.bounds.len(), 1);
+            assert!(coloring.classes.iter().all(|c| *c == Some(0)));
+        }
+    }
+
+    #[test]
+    fn noisy_climb_is_colored_in_few_segments() {
+        // A steady climb with altitude noise every other point.
+        let points = run_points(1001, |i| {
+            let noise = if i % 2 == 0 { 2.0 } else { -2.0 };
+            return (3.0, 150.0, 100.0 + 0.1 * i as f64 + noise);
+        });
+        let coloring = color_track(
+            &activity(&points),
+            TrackMetric::Elevation,
+            &HeartRateZones::default(),
+        )
+        .unwrap();
+        assert_eq!(coloring.bounds.len(), TRACK_CLASSES);
+        assert_eq!(coloring.classes.first(), Some(&Some(0)));
+        assert_eq!(coloring.classes.last(), Some(&Some(TRACK_CLASSES - 1)));
+        assert_eq!(segments(&coloring.classes), TRACK_CLASSES);
+    }
+
+    #[test]
+    fn heart_rate_is_colored_by_zone() {
+        let points = run_points(601, |i| {
+            if i <= 300 {
+                (3.0, 120.0, 100.0)
+            } else {
+                (3.0, 175.0, 100.0)
+            }
+        });
+        let zones = HeartRateZones::default();
+        let coloring = color_track(&activity(&points), TrackMetric::HeartRateZone, &zones).unwrap();
+        assert_eq!(coloring.bounds.len(), zones.zone_limits().len() + 1);
+        assert_eq!(coloring.classes[0], Some(2));
+        assert_eq!(coloring.classes[600], Some(5));
+        assert!(segments(&coloring.classes) <= 5);
+        let mut points = points;
+        for point in points.iter_mut() {
+            point.heart_rate = None;
+        }
+        assert!(color_track(&activity(&points), TrackMetric::HeartRateZone, &zones).is_none());
+    }
+
+    #[test]
+    fn markers_fall_on_the_points_of_laps_and_distances() {
+        // 2500 m at 2.5 m/s in a single lap.
+        let data = steady_run(1001, 2.5, 150.0);
+        assert_eq!(lap_end_points(&data), vec![1000]);
+        assert_eq!(
+            distance_mark_points(&data, 1000.0),
+            vec![(400, 1), (800, 2)]
+        );
+        assert!(lap_end_points(&Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+// Module containing the heart rate based training load: the training impulse
+// (TRIMP) and heart rate training stress (hrTSS) of an activity, and the acute
+// and chronic load and form over the activity library.
+
+use crate::get_field_value;
+use crate::library::LibraryEntry;
+use crate::volume::entry_date;
+use crate::zones::{HeartRateZones, MAX_RECORD_GAP};
+use chrono::{Days, Local, NaiveDate};
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+
+// Days the acute (fatigue) and chronic (fitness) loads are averaged over.
+const ACUTE_LOAD_DAYS: f64 = 7.0;
+const CHRONIC_LOAD_DAYS: f64 = 42.0;
+
+// Training load and form on one day.
+#[derive(Clone, Debug)]
+pub struct TrainingLoad {
+    pub date: NaiveDate,
+    pub load: f64,         // hrTSS of the day's activities
+    pub acute_load: f64,   // ATL
+    pub chronic_load: f64, // CTL
+    pub form: f64,         // TSB, the previous day's chronic less acute load
+}
+
+// Return the Banister training impulse of one minute at a heart rate.  The
+// heart rate is taken as a fraction of the heart rate reserve.
+fn impulse_per_minute(heart_rate: f64, hr_zones: &HeartRateZones) -> f64 {
+    let reserve = hr_zones.max_heart_rate - hr_zones.resting_heart_rate;
+    if reserve <= 0.0 {
+        return 0.0;
+    }
+    let fraction = ((heart_rate - hr_zones.resting_heart_rate) / reserve).clamp(0.0, 1.0);
+    return fraction * 0.64 * (1.92 * fraction).exp();
+}
+
+// Return the Banister training impulse (TRIMP) of an activity from its heart
+// rate records, or None for activities without heart rate.  Each heart rate is
+// credited with the time since the previous record, taken from its own record.
+pub fn trimp(data: &Vec<FitDataRecord>, hr_zones: &HeartRateZones) -> Option<f64> {
+    let mut total = 0.0;
+    let mut counted = false;
+    let mut last_time: Option<f64> = None;
+    for item in data {
+        if item.kind() != MesgNum::Record {
+            continue;
+        }
+        let Some(time) = get_field_value(item, "timestamp") else {
+            continue;
+        };
+        let dt = time - last_time.unwrap_or(time);
+        last_time = Some(time);
+        if dt <= 0.0 || dt > MAX_RECORD_GAP {
+            continue;
+        }
+        if let Some(heart_rate) = get_field_value(item, "heart_rate") {
+            total += dt / 60.0 * impulse_per_minute(heart_rate, hr_zones);
+            counted = true;
+        }
+    }
+    if !counted {
+        return None;
+    }
+    return Some(total);
+}
+
+// Return the heart rate training stress score of a TRIMP, 100 being an hour
+// at the lactate threshold heart rate.
+pub fn hr_tss(trimp: f64, hr_zones: &HeartRateZones) -> f64 {
+    let threshold_hour = 60.0 * impulse_per_minute(hr_zones.threshold_heart_rate, hr_zones);
+    if threshold_hour <= 0.0 {
+        return 0.0;
+    }
+    return 100.0 * trimp / threshold_hour;
+}
+
+// Return the daily training load, acute load, chronic load and form from the
+// first activity with heart rate until today, oldest first.  Only activities
+// of the given sport are counted when one is given.
+pub fn training_load(
+    entries: &[LibraryEntry],
+    sport: Option<&str>,
+    hr_zones: &HeartRateZones,
+) -> Vec<TrainingLoad> {
+    let mut dated: Vec<(NaiveDate, f64)> = entries
+        .iter()
+        .filter(|e| sport.is_none_or(|s| e.sport == s))
+        .filter_map(|e| Some((entry_date(e)?, hr_tss(e.trimp?, hr_zones))))
+        .collect();
+    dated.sort_by_key(|d| d.0);
+    let (Some(first), Some(last)) = (dated.first(), dated.last()) else {
+        return Vec::new();
+    };
+    let (first, last) = (first.0, last.0.max(Local::now().date_naive()));
+    let mut days: Vec<TrainingLoad> = Vec::new();
+    let (mut acute_load, mut chronic_load) = (0.0, 0.0);
+    let mut date = first;
+    let mut next = 0; // first activity not yet counted
+    while date <= last {
+        let mut load = 0.0;
+        while next < dated.len() && dated[next].0 == date {
+            load += dated[next].1;
+            next += 1;
+        }
+        let form = chronic_load - acute_load;
+        acute_load += (load - acute_load) / ACUTE_LOAD_DAYS;
+        chronic_load += (load - chronic_load) / CHRONIC_LOAD_DAYS;
+        days.push(TrainingLoad {
+            date,
+            load,
+            acute_load,
+            chronic_load,
+            form,
+        });
+        date = date + Days::new(1);
+    }
+    return days;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, run_points, steady_run};
+
+    // Return a library entry of a running activity a number of days ago.
+    fn entry(days_before: i64, trimp: Option<f64>) -> LibraryEntry {
+        let today = Local::now().timestamp();
+        return LibraryEntry {
+            start_time: today - days_before * 86400,
+            sport: String::from("running"),
+            trimp,
+            ..LibraryEntry::default()
+        };
+    }
+
+    #[test]
+    fn trimp_grows_with_the_heart_rate() {
+        let zones = HeartRateZones::default();
+        // An hour at the resting and at the maximum heart rate.
+        assert_eq!(trimp(&steady_run(3601, 3.0, 60.0), &zones), Some(0.0));
+        let max = trimp(&steady_run(3601, 3.0, 190.0), &zones).unwrap();
+        assert!((max - 60.0 * 0.64 * 1.92f64.exp()).abs() < 1e-6);
+        let easy = trimp(&steady_run(3601, 3.0, 130.0), &zones).unwrap();
+        assert!(easy > 0.0 && easy < max);
+    }
+
+    #[test]
+    fn trimp_needs_heart_rate() {
+        let mut points = run_points(61, |_| (3.0, 150.0, 100.0));
+        for point in points.iter_mut() {
+            point.heart_rate = None;
+        }
+        assert_eq!(trimp(&activity(&points), &HeartRateZones::default()), None);
+    }
+
+    #[test]
+    fn hour_at_threshold_scores_a_hundred() {
+        let zones = HeartRateZones::default();
+        let threshold = trimp(&steady_run(3601, 3.0, zones.threshold_heart_rate), &zones).unwrap();
+        assert!((hr_tss(threshold, &zones) - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_runs_daily_from_the_first_activity_until_today() {
+        let zones = HeartRateZones::default();
+        let days = training_load(&[entry(9, Some(100.0)), entry(2, Some(50.0))], None, &zones);
+        assert_eq!(days.len(), 10);
+        assert_eq!(days[9].date, Local::now().date_naive());
+        assert!(days[0].load > 0.0);
+        assert!(days[1..7].iter().all(|d| d.load == 0.0));
+        // The acute load answers faster and decays faster than the chronic.
+        assert!(days[0].acute_load > days[0].chronic_load);
+        assert!(days[6].acute_load < days[0].acute_load);
+        assert_eq!(days[0].form, 0.0);
+        assert!(days[1].form < 0.0);
+    }
+
+    #[test]
+    fn load_counts_only_the_chosen_sport() {
+        let zones = HeartRateZones::default();
+        let mut cycling = entry(3, Some(100.0));
+        cycling.sport = String::from("cycling");
+        assert!(training_load(&[cycling.clone()], Some("running"), &zones).is_empty());
+        assert_eq!(training_load(&[cycling], Some("cycling"), &zones).len(), 4);
+        assert!(training_load(&[entry(3, None)], None, &zones).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+// Module containing the training volume report, the activity library totals by
+// ISO week, month or year.
+
+use crate::library::LibraryEntry;
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate};
+
+// Length of the periods the totals are grouped by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum VolumePeriod {
+    Week,
+    Month,
+    Year,
+}
+
+// The totals of one period.
+#[derive(Clone, Debug)]
+pub struct VolumeTotals {
+    pub label: String,
+    pub start: NaiveDate,
+    pub count: usize,
+    pub distance: f64, // meters
+    pub duration: f64, // timer time, seconds
+    pub ascent: f64,   // meters
+}
+
+impl VolumeTotals {
+    // Average speed in m/s, or zero for a period without timed activities.
+    pub fn avg_speed(&self) -> f64 {
+        if self.duration > 0.0 {
+            return self.distance / self.duration;
+        }
+        return 0.0;
+    }
+}
+
+// Return the first day of the period containing a date.  Weeks start on
+// Monday, as ISO weeks do.
+fn period_start(date: NaiveDate, period: VolumePeriod) -> NaiveDate {
+    match period {
+        VolumePeriod::Week => {
+            return date - Days::new(date.weekday().num_days_from_monday() as u64);
+        }
+        VolumePeriod::Month => return date.with_day(1).unwrap_or(date),
+        VolumePeriod::Year => return NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date),
+    }
+}
+
+// Return the first day of the following period.
+fn next_period_start(start: NaiveDate, period: VolumePeriod) -> NaiveDate {
+    match period {
+        VolumePeriod::Week => return start + Days::new(7),
+        VolumePeriod::Month => return start + Months::new(1),
+        VolumePeriod::Year => return start + Months::new(12),
+    }
+}
+
+// Return the name of a period, e.g. 2025-W49, 2025-12 or 2025.
+fn period_label(start: NaiveDate, period: VolumePeriod) -> String {
+    match period {
+        VolumePeriod::Week => return start.format("%G-W%V").to_string(),
+        VolumePeriod::Month => return start.format("%Y-%m").to_string(),
+        VolumePeriod::Year => return start.format("%Y").to_string(),
+    }
+}
+
+// Return the local date an activity started on.  Entries without a start time
+// are left out of the report.
+pub(crate) fn entry_date(entry: &LibraryEntry) -> Option<NaiveDate> {
+    if entry.start_time <= 0 {
+        return None;
+    }
+    let date_time = DateTime::from_timestamp(entry.start_time, 0)?;
+    return Some(date_time.with_timezone(&Local).date_naive());
+}
+
+// Total distance, time, ascent and number of activities by period, oldest
+// first.  Only activities of the given sport are counted when one is given.
+// Periods without any activity between the first and the last are included
+// so that gaps in training show up.
+pub fn volume_totals(
+    entries: &[LibraryEntry],
+    period: VolumePeriod,
+    sport: Option<&str>,
+) -> Vec<VolumeTotals> {
+    let dated: Vec<(NaiveDate, &LibraryEntry)> = entries
+        .iter()
+        .filter(|e| sport.is_none_or(|s| e.sport == s))
+        .filter_map(|e| entry_date(e).map(|d| (period_start(d, period), e)))
+        .collect();
+    let (Some(first), Some(last)) = (
+        dated.iter().map(|d| d.0).min(),
+        dated.iter().map(|d| d.0).max(),
+    ) else {
+        return Vec::new();
+    };
+    let mut totals: Vec<VolumeTotals> = Vec::new();
+    let mut start = first;
+    while start <= last {
+        totals.push(VolumeTotals {
+            label: period_label(start, period),
+            start,
+            count: 0,
+            distance: 0.0,
+            duration: 0.0,
+            ascent: 0.0,
+        });
+        start = next_period_start(start, period);
+    }
+    for (start, entry) in dated {
+        if let Ok(i) = totals.binary_search_by_key(&start, |t| t.start) {
+            totals[i].count += 1;
+            totals[i].distance += entry.distance;
+            totals[i].duration += entry.duration;
+            totals[i].ascent += entry.ascent;
+        }
+    }
+    return totals;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Return a library entry starting at noon UTC on a date.
+    fn entry(date: &str, sport: &str, distance: f64) -> LibraryEntry {
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        return LibraryEntry {
+            start_time: day.and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp(),
+            sport: sport.to_string(),
+            distance,
+            duration: distance / 2.5,
+            ascent: 10.0,
+            ..Default::default()
+        };
+    }
+
+    // Return a date given as text.
+    fn date(text: &str) -> NaiveDate {
+        return NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap();
+    }
+
+    #[test]
+    fn periods_start_on_monday_the_first_and_new_year() {
+        let sunday = date("2025-12-07");
+        assert_eq!(period_start(sunday, VolumePeriod::Week), date("2025-12-01"));
+        assert_eq!(
+            period_start(sunday, VolumePeriod::Month),
+            date("2025-12-01")
+        );
+        assert_eq!(period_start(sunday, VolumePeriod::Year), date("2025-01-01"));
+        assert_eq!(
+            next_period_start(date("2025-12-01"), VolumePeriod::Month),
+            date("2026-01-01")
+        );
+    }
+
+    #[test]
+    fn periods_are_labeled_by_iso_week_month_and_year() {
+        let monday = date("2025-12-29");
+        assert_eq!(period_label(monday, VolumePeriod::Week), "2026-W01");
+        assert_eq!(period_label(monday, VolumePeriod::Month), "2025-12");
+        assert_eq!(period_label(monday, VolumePeriod::Year), "2025");
+    }
+
+    #[test]
+    fn totals_include_the_periods_without_activities() {
+        let entries = vec![
+            entry("2025-03-20", "running", 5000.0),
+            entry("2025-01-05", "running", 10000.0),
+            entry("2025-01-25", "running", 2500.0),
+        ];
+        let totals = volume_totals(&entries, VolumePeriod::Month, None);
+        let labels: Vec<&str> = totals.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, vec!["2025-01", "2025-02", "2025-03"]);
+        assert_eq!(totals[0].count, 2);
+        assert_eq!(totals[0].distance, 12500.0);
+        assert_eq!(totals[0].duration, 5000.0);
+        assert_eq!(totals[0].ascent, 20.0);
+        assert_eq!(totals[0].avg_speed(), 2.5);
+        assert_eq!(totals[1].count, 0);
+        assert_eq!(totals[1].avg_speed(), 0.0);
+        assert_eq!(totals[2].distance, 5000.0);
+    }
+
+    #[test]
+    fn totals_are_limited_to_the_sport() {
+        let mut undated = entry("2025-01-01", "running", 1000.0);
+        undated.start_time = 0;
+        let entries = vec![
+            entry("2025-01-06", "running", 5000.0),
+            entry("2025-01-07", "cycling", 20000.0),
+            undated,
+        ];
+        let totals = volume_totals(&entries, VolumePeriod::Week, Some("running"));
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].label, "2025-W02");
+        assert_eq!(totals[0].count, 1);
+        assert!(volume_totals(&entries, VolumePeriod::Year, Some("swimming")).is_empty());
+    }
+}
@@ -0,0 +1,32 @@
+// Module containing helpers shared by the XML (TCX, GPX) readers.
+
+use chrono::{DateTime, Utc};
+use roxmltree::Node;
+
+// Return the first child element with a given (namespace free) name.
+pub(crate) fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    return node
+        .children()
+        .find(|n| n.is_element() && n.tag_name().name() == name);
+}
+
+// Return the first descendant element with a given (namespace free) name.
+pub(crate) fn descendant<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    return node
+        .descendants()
+        .find(|n| n.is_element() && n.tag_name().name() == name);
+}
+
+// Parse the text of an element as a number.
+pub(crate) fn number(node: Option<Node>) -> Option<f64> {
+    return node?.text()?.trim().parse::<f64>().ok();
+}
+
+// Parse an ISO 8601 time.
+pub(crate) fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    let date_time = DateTime::parse_from_rfc3339(text.trim()).ok()?;
+    return Some(date_time.with_timezone(&Utc));
+}
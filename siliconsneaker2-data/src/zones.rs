@@ -0,0 +1,379 @@
+// Module containing the user's training zones and the time spent in each zone
+// computed from the record stream.
+
+use crate::{get_field_value, get_time_in_zone_field};
+use fitparser::{FitDataRecord, profile::field_types::MesgNum};
+use serde::{Deserialize, Serialize};
+
+// Longest gap between two records counted towards time in zone, seconds.
+// Longer gaps are pauses and the device's timer was not running.
+pub(crate) const MAX_RECORD_GAP: f64 = 30.0;
+
+// Heart rate the last of the device's zones ends at, bpm.
+const DEVICE_TOP_HEART_RATE: f64 = 220.0;
+
+// Fractions of the maximum heart rate, or of the heart rate reserve, at which
+// zones 1 to 5 start.  Zone 0 is everything below.
+const PERCENT_MAX_LIMITS: [f64; 5] = [0.5, 0.6, 0.7, 0.8, 0.9];
+
+// Fractions of the lactate threshold heart rate at which zones 1 to 5 start,
+// after Friel's running zones.
+const PERCENT_THRESHOLD_LIMITS: [f64; 5] = [0.85, 0.9, 0.95, 1.0, 1.03];
+
+// Names of the pace zones, slowest first.  Running slower than the easy pace,
+// e.g. walking or standing, falls in the first.
+pub const PACE_ZONE_NAMES: [&str; 6] = [
+    "slower",
+    "easy",
+    "marathon",
+    "threshold",
+    "interval",
+    "repetition",
+];
+
+// How the heart rate zone limits are derived.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeartRateZoneMethod {
+    // The device's time in zone when the file records it, else percent of max.
+    #[default]
+    Device,
+    // Percent of the maximum heart rate.
+    MaxHeartRate,
+    // Percent of the heart rate reserve, the maximum less the resting rate.
+    Reserve,
+    // Percent of the lactate threshold heart rate.
+    Threshold,
+    // The bounds given by the user.
+    Bounds,
+}
+
+// The user's heart rate zone settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartRateZones {
+    pub method: HeartRateZoneMethod,
+    pub max_heart_rate: f64,       // bpm
+    pub resting_heart_rate: f64,   // bpm
+    pub threshold_heart_rate: f64, // lactate threshold, bpm
+    pub bounds: Vec<f64>,          // bpm each zone after zone 0 starts at, ascending
+}
+
+impl Default for HeartRateZones {
+    fn default() -> Self {
+        HeartRateZones {
+            method: HeartRateZoneMethod::Device,
+            max_heart_rate: 190.0,
+            resting_heart_rate: 60.0,
+            threshold_heart_rate: 170.0,
+            bounds: Vec::new(),
+        }
+    }
+}
+
+impl HeartRateZones {
+    // Return the upper heart rate limit of every zone but the last, in the
+    // form of the time_in_zone message's hr_zone_high_boundary.
+    pub fn zone_limits(&self) -> Vec<f64> {
+        match self.method {
+            HeartRateZoneMethod::Device | HeartRateZoneMethod::MaxHeartRate => {
+                return PERCENT_MAX_LIMITS
+                    .iter()
+                    .map(|p| p * self.max_heart_rate)
+                    .collect();
+            }
+            HeartRateZoneMethod::Reserve => {
+                let reserve = self.max_heart_rate - self.resting_heart_rate;
+                return PERCENT_MAX_LIMITS
+                    .iter()
+                    .map(|p| self.resting_heart_rate + p * reserve)
+                    .collect();
+            }
+            HeartRateZoneMethod::Threshold => {
+                return PERCENT_THRESHOLD_LIMITS
+                    .iter()
+                    .map(|p| p * self.threshold_heart_rate)
+                    .collect();
+            }
+            HeartRateZoneMethod::Bounds => {
+                let mut bounds = self.bounds.clone();
+                bounds.sort_by(|a, b| a.total_cmp(b));
+                return bounds;
+            }
+        }
+    }
+}
+
+// Distance the paces of the pace zones are given per.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaceUnit {
+    #[default]
+    Km,
+    Mile,
+}
+
+// The user's pace zone settings.  Each zone starts at its pace, given as
+// minutes:seconds, and runs up to the start of the next faster zone.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaceZones {
+    pub unit: PaceUnit,
+    pub easy: String,
+    pub marathon: String,
+    pub threshold: String,
+    pub interval: String,
+    pub repetition: String,
+}
+
+impl Default for PaceZones {
+    fn default() -> Self {
+        PaceZones {
+            unit: PaceUnit::Km,
+            easy: String::from("6:30"),
+            marathon: String::from("5:20"),
+            threshold: String::from("5:00"),
+            interval: String::from("4:35"),
+            repetition: String::from("4:15"),
+        }
+    }
+}
+
+// Convert a pace given as minutes:seconds, or as decimal minutes, to minutes.
+fn parse_pace(pace: &str) -> Option<f64> {
+    let minutes = match pace.trim().split_once(':') {
+        Some((min, sec)) => {
+            min.trim().parse::<f64>().ok()? + sec.trim().parse::<f64>().ok()? / 60.0
+        }
+        None => pace.trim().parse::<f64>().ok()?,
+    };
+    if minutes > 0.0 {
+        return Some(minutes);
+    }
+    return None;
+}
+
+impl PaceZones {
+    // Return the speed (m/s) at which each zone after the slowest starts, in
+    // ascending order.  A pace that can not be read is taken from the defaults
+    // so that the zones keep their names.
+    pub fn zone_limits(&self) -> Vec<f64> {
+        let meters = match self.unit {
+            PaceUnit::Km => 1000.0,
+            PaceUnit::Mile => 1609.344,
+        };
+        let defaults = PaceZones::default();
+        let mut limits: Vec<f64> = [
+            (&self.easy, &defaults.easy),
+            (&self.marathon, &defaults.marathon),
+            (&self.threshold, &defaults.threshold),
+            (&self.interval, &defaults.interval),
+            (&self.repetition, &defaults.repetition),
+        ]
+        .iter()
+        .filter_map(|(pace, default)| parse_pace(pace).or(parse_pace(default)))
+        .map(|minutes| meters / (minutes * 60.0))
+        .collect();
+        limits.sort_by(|a, b| a.total_cmp(b));
+        return limits;
+    }
+}
+
+// Return the seconds spent in each pace zone, or None for files without
+// speed.  There is one more zone than there are limits.
+pub fn pace_time_in_zone(data: &Vec<FitDataRecord>, zone_limits: &[f64]) -> Option<Vec<f64>> {
+    let zone_times = time_in_zones(data, "enhanced_speed", zone_limits);
+    if zone_times.iter().all(|t| *t <= 0.0) {
+        return None;
+    }
+    return Some(zone_times);
+}
+
+// Return the zone a value falls in given the zone upper limits.
+pub fn zone_of(zone_limits: &[f64], value: f64) -> usize {
+    return zone_limits.iter().take_while(|l| value >= **l).count();
+}
+
+// Return the seconds spent in each zone of a record field, one more zone than
+// there are limits.  Each record is credited with the time since the previous
+// record, except across pauses.  The value and timestamp are taken from the
+// same record so that records missing the field do not shift the times.
+pub fn time_in_zones(data: &Vec<FitDataRecord>, field_name: &str, zone_limits: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; zone_limits.len() + 1];
+    let mut last_time: Option<f64> = None;
+    for item in data {
+        if item.kind() != MesgNum::Record {
+            continue;
+        }
+        let Some(time) = get_field_value(item, "timestamp") else {
+            continue;
+        };
+        let dt = time - last_time.unwrap_or(time);
+        last_time = Some(time);
+        if dt <= 0.0 || dt > MAX_RECORD_GAP {
+            continue;
+        }
+        if let Some(value) = get_field_value(item, field_name) {
+            result[zone_of(zone_limits, value)] += dt;
+        }
+    }
+    return result;
+}
+
+// Return the heart rate the last zone ends at: the user's maximum heart rate,
+// or 220 bpm for the device's own zones as their maximum is not known.
+pub fn heart_rate_zone_top(data: &Vec<FitDataRecord>, zones: &HeartRateZones) -> f64 {
+    if zones.method == HeartRateZoneMethod::Device
+        && let (Some(_), Some(_)) = get_time_in_zone_field(data)
+    {
+        return DEVICE_TOP_HEART_RATE;
+    }
+    return zones.max_heart_rate;
+}
+
+// Return the seconds spent in each heart rate zone and the zone limits.  The
+// device's own time_in_zone message is used when the settings leave the zones
+// to the device and the file records one, otherwise the times are computed
+// from the heart_rate records.  Returns None for files without heart rate.
+pub fn heart_rate_time_in_zone(
+    data: &Vec<FitDataRecord>,
+    zones: &HeartRateZones,
+) -> Option<(Vec<f64>, Vec<f64>)> {
+    if zones.method == HeartRateZoneMethod::Device
+        && let (Some(zone_times), Some(zone_limits)) = get_time_in_zone_field(data)
+    {
+        return Some((zone_times, zone_limits));
+    }
+    let zone_limits = zones.zone_limits();
+    let zone_times = time_in_zones(data, "heart_rate", &zone_limits);
+    if zone_times.iter().all(|t| *t <= 0.0) {
+        return None;
+    }
+    return Some((zone_times, zone_limits));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{activity, recorded_run, run_points, steady_run};
+
+    #[test]
+    fn value_falls_in_the_zone_its_limit_starts() {
+        let limits = [100.0, 120.0, 140.0];
+        assert_eq!(zone_of(&limits, 90.0), 0);
+        assert_eq!(zone_of(&limits, 100.0), 1);
+        assert_eq!(zone_of(&limits, 139.9), 2);
+        assert_eq!(zone_of(&limits, 200.0), 3);
+    }
+
+    #[test]
+    fn heart_rate_zone_limits_follow_the_method() {
+        let mut zones = HeartRateZones::default();
+        assert_eq!(zones.zone_limits(), vec![95.0, 114.0, 133.0, 152.0, 171.0]);
+        zones.method = HeartRateZoneMethod::Reserve;
+        assert_eq!(zones.zone_limits(), vec![125.0, 138.0, 151.0, 164.0, 177.0]);
+        zones.method = HeartRateZoneMethod::Threshold;
+        assert_eq!(zones.zone_limits()[3], 170.0);
+        zones.method = HeartRateZoneMethod::Bounds;
+        zones.bounds = vec![150.0, 120.0, 135.0];
+        assert_eq!(zones.zone_limits(), vec![120.0, 135.0, 150.0]);
+    }
+
+    #[test]
+    fn pace_zone_limits_are_ascending_speeds() {
+        let mut zones = PaceZones::default();
+        let limits = zones.zone_limits();
+        assert_eq!(limits.len(), PACE_ZONE_NAMES.len() - 1);
+        assert!((limits[0] - 1000.0 / 390.0).abs() < 1e-9);
+        assert!(limits.windows(2).all(|pair| pair[0] < pair[1]));
+        // A pace that can not be read falls back to the default.
+        zones.easy = String::from("slow");
+        zones.unit = PaceUnit::Mile;
+        assert!((zones.zone_limits()[0] - 1609.344 / 390.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn time_is_credited_to_the_zone_of_each_record() {
+        let points = run_points(121, |i| {
+            if i <= 60 {
+                (3.0, 150.0, 100.0)
+            } else {
+                (3.0, 170.0, 100.0)
+            }
+        });
+        assert_eq!(
+            time_in_zones(&activity(&points), "heart_rate", &[160.0]),
+            vec![60.0, 60.0]
+        );
+    }
+
+    #[test]
+    fn records_without_the_value_do_not_shift_the_times() {
+        let mut points = run_points(121, |i| {
+            if i <= 60 {
+                (3.0, 150.0, 100.0)
+            } else {
+                (3.0, 170.0, 100.0)
+            }
+        });
+        for point in points.iter_mut().skip(1).step_by(2) {
+            point.heart_rate = None;
+        }
+        assert_eq!(
+            time_in_zones(&activity(&points), "heart_rate", &[160.0]),
+            vec![30.0, 30.0]
+        );
+    }
+
+    #[test]
+    fn pauses_are_not_counted() {
+        let mut points = run_points(61, |_| (3.0, 150.0, 100.0));
+        for point in points.iter_mut().skip(31) {
+            point.time = point.time.map(|t| t + chrono::Duration::seconds(600));
+        }
+        assert_eq!(
+            time_in_zones(&activity(&points), "heart_rate", &[160.0]),
+            vec![59.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn pace_time_in_zone_needs_speed() {
+        let data = steady_run(61, 1000.0 / 300.0, 150.0);
+        let limits = PaceZones::default().zone_limits();
+        let times = pace_time_in_zone(&data, &limits).unwrap();
+        // 5:00 per km is threshold pace.
+        assert_eq!(times[3], 60.0);
+        assert_eq!(times.iter().sum::<f64>(), 60.0);
+        let mut points = run_points(61, |_| (3.0, 150.0, 100.0));
+        for point in points.iter_mut() {
+            point.speed = None;
+        }
+        assert!(pace_time_in_zone(&activity(&points), &limits).is_none());
+    }
+
+    #[test]
+    fn heart_rate_zones_come_from_the_device_or_the_settings() {
+        let recorded = recorded_run("half_marathon.fit");
+        let zones = HeartRateZones::default();
+        assert!(get_time_in_zone_field(&recorded).0.is_some());
+        assert_eq!(
+            heart_rate_zone_top(&recorded, &zones),
+            DEVICE_TOP_HEART_RATE
+        );
+        let (_, limits) = heart_rate_time_in_zone(&recorded, &zones).unwrap();
+        assert_eq!(limits, get_time_in_zone_field(&recorded).1.unwrap());
+        let settings = HeartRateZones {
+            method: HeartRateZoneMethod::MaxHeartRate,
+            ..HeartRateZones::default()
+        };
+        assert_eq!(heart_rate_zone_top(&recorded, &settings), 190.0);
+        let (_, limits) = heart_rate_time_in_zone(&recorded, &settings).unwrap();
+        assert_eq!(limits, settings.zone_limits());
+        // Without a time_in_zone message the settings are used.
+        let synthetic = steady_run(61, 3.0, 150.0);
+        assert_eq!(heart_rate_zone_top(&synthetic, &zones), 190.0);
+        let (times, _) = heart_rate_time_in_zone(&synthetic, &zones).unwrap();
+        assert_eq!(times[3], 60.0);
+    }
+}
@@ -0,0 +1,474 @@
+// Module containing the headless (no GTK) command line subcommands.
+
+use crate::config::{
+    LIBRARYFILE, SETTINGSFILE, config_file_path, load_config, load_library, save_library,
+};
+use crate::i18n::tr;
+use crate::summary::{build_records_report, build_summary_text};
+use fitparser::FitDataRecord;
+use fluent::FluentArgs;
+use siliconsneaker2_data::library::{LibraryIndex, scan_folder};
+use siliconsneaker2_data::volume::{VolumePeriod, VolumeTotals, volume_totals};
+use siliconsneaker2_data::{
+    Units, cvt_altitude, cvt_distance, cvt_elapsed_time, cvt_pace, read_activity,
+};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// Values parsed from the arguments following a subcommand.
+struct CommandLineOptions {
+    files: Vec<String>,
+    units: Units,
+    period: VolumePeriod,
+    sport: Option<String>,
+}
+
+// Run a subcommand if one was requested.  Returns the process exit code,
+// or None when the arguments should be handed to GTK instead.
+pub fn run_command_line(args: &[String]) -> Option<i32> {
+    if args.len() < 2 {
+        return None;
+    }
+    match args[1].as_str() {
+        "summary" => return Some(run_summary(&args[2..])),
+        "volume" => return Some(run_volume(&args[2..])),
+        "records" => return Some(run_records(&args[2..])),
+        _ => return None,
+    }
+}
+
+// Print the command line usage to stderr and return the usage exit code.
+fn usage_error(message: &str) -> i32 {
+    if !message.is_empty() {
+        eprintln!("{}", message);
+    }
+    eprintln!("{}", tr("CLI_USAGE", None));
+    return 2;
+}
+
+// Convert a --units value to a unit system.
+fn parse_units(value: &str) -> Option<Units> {
+    match value.to_lowercase().as_str() {
+        "metric" => return Some(Units::Metric),
+        "us" => return Some(Units::US),
+        _ => return None,
+    }
+}
+
+// Convert a --period value to a volume report period.
+fn parse_period(value: &str) -> Option<VolumePeriod> {
+    match value.to_lowercase().as_str() {
+        "week" => return Some(VolumePeriod::Week),
+        "month" => return Some(VolumePeriod::Month),
+        "year" => return Some(VolumePeriod::Year),
+        _ => return None,
+    }
+}
+
+// Split subcommand arguments into file names and options.  Options are
+// accepted as "--name value" or "--name=value"; those not in `accepted`, the
+// options that apply to the subcommand, are refused.
+fn parse_options(args: &[String], accepted: &[&str]) -> Result<CommandLineOptions, String> {
+    let mut options = CommandLineOptions {
+        files: Vec::new(),
+        units: Units::Metric,
+        period: VolumePeriod::Week,
+        sport: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            options.files.push(arg.to_string());
+            continue;
+        }
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, value),
+            None => (arg.as_str(), iter.next().map(|v| v.as_str()).unwrap_or("")),
+        };
+        if !accepted.contains(&name) {
+            return Err(arg.to_string());
+        }
+        match name {
+            "--units" => match parse_units(value) {
+                Some(units) => options.units = units,
+                None => return Err(format!("--units {}", value)),
+            },
+            "--period" => match parse_period(value) {
+                Some(period) => options.period = period,
+                None => return Err(format!("--period {}", value)),
+            },
+            "--sport" if !value.is_empty() => options.sport = Some(value.to_lowercase()),
+            _ => return Err(arg.to_string()),
+        }
+    }
+    return Ok(options);
+}
+
+// Read and decode an activity file, reporting any problem on stderr.
+fn read_activity_file(file_name: &str) -> Option<Vec<FitDataRecord>> {
+    let mut file = match File::open(file_name) {
+        Ok(file) => file,
+        Err(error) => {
+            match error.kind() {
+                ErrorKind::NotFound => {
+                    eprintln!("{}: {}", file_name, tr("MESSAGE_FILE_NOT_FOUND", None))
+                }
+                _ => eprintln!("{}: {}", file_name, tr("MESSAGE_PERMISSIONS", None)),
+            }
+            return None;
+        }
+    };
+    match read_activity(&mut file) {
+        Ok(data) => return Some(data),
+        Err(error) => {
+            let mut args = FluentArgs::new();
+            args.set("error", error.to_string());
+            eprintln!(
+                "{}: {}",
+                file_name,
+                tr("MESSAGE_NOT_AN_ACTIVITY_FILE", Some(&args))
+            );
+            return None;
+        }
+    }
+}
+
+// Write the session, lap and heart rate zone report for a file to stdout.
+fn run_summary(args: &[String]) -> i32 {
+    let options = match parse_options(args, &["--units"]) {
+        Ok(options) => options,
+        Err(bad_arg) => {
+            return usage_error(&format!(
+                "{}: {}",
+                tr("CLI_INVALID_ARGUMENT", None),
+                bad_arg
+            ));
+        }
+    };
+    if options.files.len() != 1 {
+        return usage_error("");
+    }
+    match read_activity_file(&options.files[0]) {
+        Some(data) => {
+            // The zones are those the user set for the GUI.
+            let config = load_config(Path::new(&config_file_path(SETTINGSFILE)));
+            print!(
+                "{}",
+                build_summary_text(
+                    &data,
+                    &options.units,
+                    &config.heart_rate_zones,
+                    &config.pace_zones
+                )
+            );
+            return 0;
+        }
+        None => return 1,
+    }
+}
+
+// Format one row of the volume table.
+fn volume_row(label: &str, totals: &VolumeTotals, user_unit: &Units) -> String {
+    let (hr, min, sec) = cvt_elapsed_time(totals.duration as f32);
+    let pace = if totals.duration > 0.0 {
+        format!("{:.2}", cvt_pace(totals.avg_speed() as f32, user_unit))
+    } else {
+        String::from("-")
+    };
+    return format!(
+        "{:<12} {:>12} {:>14.2} {:>14} {:>14.1} {:>14}\n",
+        label,
+        totals.count,
+        cvt_distance(totals.distance as f32, user_unit),
+        format!("{}:{:02}:{:02}", hr, min, sec),
+        cvt_altitude(totals.ascent as f32, user_unit),
+        pace
+    );
+}
+
+// Build the volume report as a text table, one row per period plus a total.
+fn build_volume_table(totals: &Vec<VolumeTotals>, user_unit: &Units) -> String {
+    let (distance_unit, ascent_unit, pace_unit) = match user_unit {
+        Units::US => ("UNIT_MILES", "UNIT_FEET", "UNIT_PACE_US"),
+        _ => ("UNIT_KM", "UNIT_METERS", "UNIT_PACE_METRIC"),
+    };
+    let mut table = format!(
+        "{:<12} {:>12} {:>14} {:>14} {:>14} {:>14}\n",
+        tr("VOLUME_COLUMN_PERIOD", None),
+        tr("VOLUME_COLUMN_ACTIVITIES", None),
+        tr("VOLUME_COLUMN_DISTANCE", None),
+        tr("VOLUME_COLUMN_TIME", None),
+        tr("VOLUME_COLUMN_ASCENT", None),
+        tr("VOLUME_COLUMN_PACE", None),
+    );
+    table.push_str(&format!(
+        "{:<12} {:>12} {:>14} {:>14} {:>14} {:>14}\n",
+        "",
+        "",
+        tr(distance_unit, None),
+        "h:mm:ss",
+        tr(ascent_unit, None),
+        tr(pace_unit, None),
+    ));
+    let mut grand_total = VolumeTotals {
+        label: tr("VOLUME_TOTAL", None),
+        start: Default::default(),
+        count: 0,
+        distance: 0.0,
+        duration: 0.0,
+        ascent: 0.0,
+    };
+    for period in totals {
+        table.push_str(&volume_row(&period.label, period, user_unit));
+        grand_total.count += period.count;
+        grand_total.distance += period.distance;
+        grand_total.duration += period.duration;
+        grand_total.ascent += period.ascent;
+    }
+    table.push_str(&volume_row(&grand_total.label, &grand_total, user_unit));
+    return table;
+}
+
+// Index the folder given on the command line, or the library folder chosen in
+// the graphical interface when no folder is given.  Returns the exit code on
+// failure.
+fn index_library_folder(options: &CommandLineOptions) -> Result<LibraryIndex, i32> {
+    if options.files.len() > 1 {
+        return Err(usage_error(""));
+    }
+    let library_file = config_file_path(LIBRARYFILE);
+    let library_path = Path::new(&library_file);
+    let library = load_library(library_path);
+    let folder = match options.files.first() {
+        Some(folder) => folder.clone(),
+        None => library.folder.clone(),
+    };
+    if folder.is_empty() {
+        return Err(usage_error(&tr("LIBRARY_NO_FOLDER", None)));
+    }
+    if !Path::new(&folder).is_dir() {
+        eprintln!("{}: {}", folder, tr("MESSAGE_FILE_NOT_FOUND", None));
+        return Err(1);
+    }
+    // Only the library folder's index is reused and kept up to date.
+    let is_library_folder = Path::new(&folder) == Path::new(&library.folder);
+    let previous = if is_library_folder {
+        library
+    } else {
+        LibraryIndex::default()
+    };
+    // TRIMP is computed with the heart rates the user set for the GUI.
+    let config = load_config(Path::new(&config_file_path(SETTINGSFILE)));
+    let index = scan_folder(
+        Path::new(&folder),
+        &previous,
+        &config.heart_rate_zones,
+        |_, _| {},
+    );
+    if is_library_folder && let Err(e) = save_library(&index, library_path) {
+        eprintln!("{}: {}", library_file, e);
+    }
+    return Ok(index);
+}
+
+// Write the distance, time, ascent and pace totals by period to stdout.  The
+// activities come from the given folder, or from the library folder.
+fn run_volume(args: &[String]) -> i32 {
+    let options = match parse_options(args, &["--units", "--period", "--sport"]) {
+        Ok(options) => options,
+        Err(bad_arg) => {
+            return usage_error(&format!(
+                "{}: {}",
+                tr("CLI_INVALID_ARGUMENT", None),
+                bad_arg
+            ));
+        }
+    };
+    let index = match index_library_folder(&options) {
+        Ok(index) => index,
+        Err(code) => return code,
+    };
+    let totals = volume_totals(&index.entries, options.period, options.sport.as_deref());
+    print!("{}", build_volume_table(&totals, &options.units));
+    return 0;
+}
+
+// Write the fastest time over each best effort distance to stdout.  The
+// activities come from the given folder, or from the library folder.
+fn run_records(args: &[String]) -> i32 {
+    let options = match parse_options(args, &["--units", "--sport"]) {
+        Ok(options) => options,
+        Err(bad_arg) => {
+            return usage_error(&format!(
+                "{}: {}",
+                tr("CLI_INVALID_ARGUMENT", None),
+                bad_arg
+            ));
+        }
+    };
+    let index = match index_library_folder(&options) {
+        Ok(index) => index,
+        Err(code) => return code,
+    };
+    print!(
+        "{}",
+        build_records_report(&index.entries, options.sport.as_deref(), &options.units)
+    );
+    return 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Return the arguments as the owned strings the parser takes.
+    fn args(values: &[&str]) -> Vec<String> {
+        return values.iter().map(|v| v.to_string()).collect();
+    }
+
+    #[test]
+    fn units_are_read_in_any_case() {
+        assert!(matches!(parse_units("US"), Some(Units::US)));
+        assert!(matches!(parse_units("metric"), Some(Units::Metric)));
+        assert!(parse_units("km").is_none());
+    }
+
+    #[test]
+    fn options_are_split_from_the_files() {
+        let options =
+            parse_options(&args(&["a.fit", "--units", "us", "b.fit"]), &["--units"]).unwrap();
+        assert_eq!(options.files, vec!["a.fit", "b.fit"]);
+        assert!(matches!(options.units, Units::US));
+        let options = parse_options(&args(&["--units=metric", "a.fit"]), &["--units"]).unwrap();
+        assert_eq!(options.files, vec!["a.fit"]);
+        assert!(matches!(options.units, Units::Metric));
+    }
+
+    #[test]
+    fn periods_and_sport_are_read() {
+        assert!(matches!(parse_period("Month"), Some(VolumePeriod::Month)));
+        assert!(parse_period("day").is_none());
+        let options = parse_options(
+            &args(&["--period=year", "--sport", "Running"]),
+            &["--period", "--sport"],
+        )
+        .unwrap();
+        assert!(options.period == VolumePeriod::Year);
+        assert_eq!(options.sport.as_deref(), Some("running"));
+        assert!(options.files.is_empty());
+        let options = parse_options(&args(&["runs"]), &["--period", "--sport"]).unwrap();
+        assert!(options.period == VolumePeriod::Week);
+        assert!(options.sport.is_none());
+    }
+
+    #[test]
+    fn unknown_options_and_values_are_refused() {
+        assert_eq!(
+            parse_options(&args(&["--verbose"]), &["--units"])
+                .err()
+                .unwrap(),
+            "--verbose"
+        );
+        assert_eq!(
+            parse_options(&args(&["--units", "km"]), &["--units"])
+                .err()
+                .unwrap(),
+            "--units km"
+        );
+        assert!(parse_options(&args(&["--units"]), &["--units"]).is_err());
+        // Options of another subcommand.
+        assert_eq!(
+            parse_options(&args(&["--period", "year"]), &["--units"])
+                .err()
+                .unwrap(),
+            "--period"
+        );
+    }
+
+    #[test]
+    fn only_subcommands_are_run_headless() {
+        assert_eq!(run_command_line(&args(&["siliconsneaker2"])), None);
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "run.fit"])),
+            None
+        );
+    }
+
+    #[test]
+    fn summary_needs_one_readable_file() {
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "summary"])),
+            Some(2)
+        );
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "summary", "a.fit", "b.fit"])),
+            Some(2)
+        );
+        assert_eq!(
+            run_command_line(&args(&[
+                "siliconsneaker2",
+                "summary",
+                "--period=year",
+                "a.fit"
+            ])),
+            Some(2)
+        );
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "summary", "/no/such/run.fit"])),
+            Some(1)
+        );
+        let not_fit = concat!(env!("CARGO_MANIFEST_DIR"), "/README.md");
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "summary", not_fit])),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn volume_table_has_a_row_per_period_and_a_total() {
+        let period = |label: &str, count: usize, distance: f64| VolumeTotals {
+            label: label.to_string(),
+            start: Default::default(),
+            count,
+            distance,
+            duration: distance / 2.5,
+            ascent: 50.0,
+        };
+        let totals = vec![period("2025-01", 2, 10000.0), period("2025-02", 0, 0.0)];
+        let table = build_volume_table(&totals, &Units::Metric);
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 5);
+        assert!(rows[0].starts_with(&tr("VOLUME_COLUMN_PERIOD", None)));
+        assert!(rows[1].contains("h:mm:ss"));
+        assert!(rows[2].starts_with("2025-01"));
+        assert!(rows[2].contains(" 10.00 "));
+        assert!(rows[2].contains(" 1:06:40 "));
+        assert!(rows[2].ends_with(" 6.67"));
+        assert!(rows[3].ends_with(" -"));
+        assert!(rows[4].starts_with(&tr("VOLUME_TOTAL", None)));
+        assert!(rows[4].contains(" 100.0 "));
+    }
+
+    #[test]
+    fn volume_needs_an_existing_folder() {
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "volume", "a", "b"])),
+            Some(2)
+        );
+        assert_eq!(
+            run_command_line(&args(&[
+                "siliconsneaker2",
+                "volume",
+                "--period",
+                "day",
+                "a"
+            ])),
+            Some(2)
+        );
+        assert_eq!(
+            run_command_line(&args(&["siliconsneaker2", "volume", "/no/such/folder"])),
+            Some(1)
+        );
+    }
+}
@@ -1,8 +1,11 @@
 // Module containing user preferences, static information and helper utilities.
 use crate::Error;
+use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
+use siliconsneaker2_data::library::LibraryIndex;
+use siliconsneaker2_data::zones::{HeartRateZones, PaceZones};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // Meta-program info. Displayed in about dialog.
 pub const ICON_NAME: &str = "siliconsneaker2";
@@ -14,13 +17,20 @@ pub const ARTIST1: &str = "Amos Kofi Commey";
 pub const TESTER1: &str = "Gabriel A. Prevallet";
 pub const TESTER2: &str = "Jacob Thymann Nielsen";
 pub const SETTINGSFILE: &str = "siliconsneaker2_settings.toml";
+pub const LIBRARYFILE: &str = "siliconsneaker2_library.toml";
+pub const TILECACHEDIR: &str = "siliconsneaker2_tiles";
 
-// Unit of measure system.
-pub enum Units {
-    Metric,
-    US,
-    None,
-}
+// Map source used until the user chooses another.
+pub const DEFAULT_MAP_SOURCE: &str = "osm-mapnik";
+
+// Record fields graphed until the user chooses others.
+pub const DEFAULT_GRAPH_FIELDS: [&str; 5] = [
+    "enhanced_speed",
+    "heart_rate",
+    "cadence",
+    "enhanced_altitude",
+    "temperature",
+];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WindowConfig {
@@ -30,6 +40,66 @@ pub struct WindowConfig {
     pub left_frame_split: i32,
     pub right_frame_split: i32,
     pub units_index: u32, // toml won't serialize enums, we'll use the selected DropDown
+    #[serde(default)]
+    pub x_axis_index: u32, // distance, elapsed time or clock time DropDown selection
+    #[serde(default = "default_graph_fields")]
+    pub graph_fields: Vec<String>, // record fields to graph, in display order
+    #[serde(default)]
+    pub smoothing_index: u32, // raw, rolling mean, rolling median or exponential DropDown selection
+    #[serde(default = "default_smoothing_window")]
+    pub smoothing_window: u32, // points
+    #[serde(default)]
+    pub remove_spikes: bool,
+    #[serde(default)]
+    pub offline_map: bool, // serve only the cached map tiles
+    #[serde(default)]
+    pub mbtiles_path: String, // MBTiles file the map is drawn from, empty for the online map
+    #[serde(default = "default_map_source")]
+    pub map_source: String, // id of the online map source
+    #[serde(default)]
+    pub track_color_index: u32, // metric the map track is colored by
+    #[serde(default)]
+    pub distance_markers: bool, // mark every kilometer or mile on the map
+    #[serde(default)]
+    pub heart_rate_zones: HeartRateZones, // edited by hand in the settings file
+    #[serde(default)]
+    pub pace_zones: PaceZones, // edited by hand in the settings file
+    #[serde(default)]
+    pub map_sources: Vec<CustomMapSource>, // added by the user
+}
+
+// A map source added by the user, e.g. a tile server on localhost.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomMapSource {
+    pub id: String,
+    pub name: String,
+    pub url_template: String, // XYZ template with {z}, {x} and {y}
+    #[serde(default)]
+    pub attribution: String,
+    #[serde(default)]
+    pub min_zoom: u32,
+    #[serde(default = "default_max_zoom")]
+    pub max_zoom: u32,
+}
+
+// Return the default list of graphed record fields.
+fn default_graph_fields() -> Vec<String> {
+    return DEFAULT_GRAPH_FIELDS.iter().map(|f| f.to_string()).collect();
+}
+
+// Return the id of the default map source.
+fn default_map_source() -> String {
+    return String::from(DEFAULT_MAP_SOURCE);
+}
+
+// Return the default highest zoom level of a map source.
+fn default_max_zoom() -> u32 {
+    return 19;
+}
+
+// Return the default number of points the graphs are smoothed over.
+fn default_smoothing_window() -> u32 {
+    return 10;
 }
 
 impl Default for WindowConfig {
@@ -41,7 +111,44 @@ impl Default for WindowConfig {
             left_frame_split: 200,
             right_frame_split: 200,
             units_index: 0,
+            x_axis_index: 0,
+            graph_fields: default_graph_fields(),
+            smoothing_index: 0,
+            smoothing_window: default_smoothing_window(),
+            remove_spikes: false,
+            offline_map: false,
+            mbtiles_path: String::new(),
+            map_source: default_map_source(),
+            track_color_index: 0,
+            distance_markers: false,
+            heart_rate_zones: HeartRateZones::default(),
+            pace_zones: PaceZones::default(),
+            map_sources: Vec::new(),
+        }
+    }
+}
+
+/// Returns the path of a file in the user's configuration directory, or just
+/// the file name when the directory can not be determined.
+pub fn config_file_path(file_name: &str) -> String {
+    match BaseDirs::new() {
+        Some(base_dirs) => {
+            return base_dirs
+                .config_dir()
+                .join(file_name)
+                .to_string_lossy()
+                .to_string();
         }
+        None => return String::from(file_name),
+    }
+}
+
+/// Returns the path of a folder in the user's cache directory, or just the
+/// folder name when the directory can not be determined.
+pub fn cache_dir_path(dir_name: &str) -> PathBuf {
+    match BaseDirs::new() {
+        Some(base_dirs) => return base_dirs.cache_dir().join(dir_name),
+        None => return PathBuf::from(dir_name),
     }
 }
 
@@ -84,3 +191,20 @@ pub fn load_config(path: &Path) -> WindowConfig {
         Err(_e) => WindowConfig::default(),
     }
 }
+
+/// Saves the activity library index to a TOML file.
+pub fn save_library(index: &LibraryIndex, path: &Path) -> std::io::Result<()> {
+    let toml_string = toml::to_string(index)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    fs::write(path, toml_string)?;
+    Ok(())
+}
+
+/// Loads the activity library index from a TOML file, or returns an empty
+/// index on failure.
+pub fn load_library(path: &Path) -> LibraryIndex {
+    match fs::read_to_string(path) {
+        Ok(toml_string) => toml::from_str(&toml_string).unwrap_or_default(),
+        Err(_e) => LibraryIndex::default(),
+    }
+}
@@ -1,30 +1,61 @@
 // User interface logic - setup, drawing, formatting.
 
-use crate::config::{ICON_NAME, PROGRAM_NAME, SETTINGSFILE, Units, load_config};
-use crate::data::{
-    GraphAttributes, GraphCache, MapCache, cvt_altitude, cvt_distance, cvt_elapsed_time, cvt_pace,
-    cvt_temperature, get_run_start_date, get_sess_record_field, get_time_in_zone_field,
-    get_timestamps, get_xy, is_american_thanksgiving, is_easter, semi_to_degrees, set_plot_range,
+use crate::config::{
+    CustomMapSource, DEFAULT_MAP_SOURCE, ICON_NAME, LIBRARYFILE, PROGRAM_NAME, SETTINGSFILE,
+    TILECACHEDIR, cache_dir_path, config_file_path, load_config,
 };
 use crate::i18n::tr;
-use directories::BaseDirs;
-use fitparser::{FitDataField, FitDataRecord, profile::field_types::MesgNum};
+use crate::summary::{
+    best_effort_label, build_records_report, build_summary_text, lap_header, track_legend_label,
+};
+use crate::tile_source::{
+    TileSource, cached_map_source, is_url_template, map_source_registry, mbtiles_map_source,
+    new_map_source_id, url_template,
+};
+use chrono::{DateTime, Local};
+use fitparser::FitDataRecord;
+use fluent::FluentArgs;
 use gtk4::cairo::Context;
 use gtk4::ffi::GTK_STYLE_PROVIDER_PRIORITY_APPLICATION;
 use gtk4::glib::clone;
 use gtk4::prelude::*;
 use gtk4::{
-    Adjustment, Application, ApplicationWindow, Button, DrawingArea, DropDown, Frame, Image, Label,
-    Orientation, Scale, ScrolledWindow, StringList, StringObject, TextBuffer, TextView, gdk,
+    Adjustment, Application, ApplicationWindow, Button, CheckButton, ColumnView, ColumnViewColumn,
+    CustomSorter, DrawingArea, DropDown, Frame, Image, Label, ListItem, MenuButton, Orientation,
+    Popover, Scale, ScrolledWindow, SignalListItemFactory, SingleSelection, SortListModel,
+    SortType, SpinButton, StringList, StringObject, TextBuffer, TextView, gdk, gio,
 };
 use libshumate::prelude::*;
 use libshumate::{Coordinate, Marker, MarkerLayer, PathLayer, SimpleMap};
 use plotters::prelude::*;
-use plotters::style::full_palette::BROWN;
-use plotters::style::full_palette::CYAN;
+use plotters::style::full_palette::{
+    BLUE_400, BROWN, CYAN, DEEPORANGE, GREEN_400, GREY_400, GREY_500, GREY_700, INDIGO,
+    LIGHTGREEN_900, ORANGE, ORANGE_400, PINK, PURPLE, PURPLE_400, RED_400, TEAL, YELLOW_600,
+};
 use plotters_cairo::CairoBackend;
+use siliconsneaker2_data::efforts::{BestEffort, best_efforts};
+use siliconsneaker2_data::library::{LibraryEntry, LibraryIndex};
+use siliconsneaker2_data::mbtiles::MbTiles;
+use siliconsneaker2_data::smoothing::{SmoothingFilter, remove_spikes, smooth};
+use siliconsneaker2_data::splits::split_length;
+use siliconsneaker2_data::tiles::{fit_bounds, path_bounds};
+use siliconsneaker2_data::track::{TrackMetric, color_track, distance_mark_points, lap_end_points};
+use siliconsneaker2_data::training_load::{TrainingLoad, training_load};
+use siliconsneaker2_data::volume::{VolumePeriod, VolumeTotals, volume_totals};
+use siliconsneaker2_data::zones::{
+    HeartRateZones, PaceZones, heart_rate_time_in_zone, heart_rate_zone_top,
+};
+use siliconsneaker2_data::{
+    GraphAttributes, GraphCache, MapCache, Units, cvt_altitude, cvt_distance, cvt_elapsed_time,
+    cvt_pace, get_graph_fields, get_record_positions, get_record_x_values, get_run_start_date,
+    get_timestamps, get_xy, hr_zone_bounds, is_american_thanksgiving, is_easter, semi_to_degrees,
+    set_filtered_plot_range, set_plot_range,
+};
+use std::cell::{Cell, RefCell};
+use std::error::Error;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
 
 // #####################################################################
 // ##################### OVERALL UI FUNCTIONS ##########################
@@ -32,11 +63,42 @@ use std::rc::Rc;
 // Widgets used for the graphical user interface.
 pub struct UserInterface {
     pub settings_file: String,
+    pub library_file: String,
     pub win: ApplicationWindow,
     pub outer_box: gtk4::Box,
     pub button_box: gtk4::Box,
     pub main_pane: gtk4::Paned,
     pub btn: Button,
+    pub library_btn: Button,
+    pub compare_btn: Button,
+    pub compare_clear_btn: Button,
+    pub export_menu_btn: MenuButton,
+    pub graphs_menu_btn: MenuButton,
+    pub graphs_box: gtk4::Box,
+    pub smoothing_menu_btn: MenuButton,
+    pub smoothing_box: gtk4::Box,
+    pub smoothing_widget: DropDown,
+    pub smoothing_window_spin: SpinButton,
+    pub remove_spikes_check: CheckButton,
+    pub map_menu_btn: MenuButton,
+    pub map_box: gtk4::Box,
+    pub map_source_list: StringList,
+    pub map_source_widget: DropDown,
+    pub add_map_source_btn: Button,
+    pub remove_map_source_btn: Button,
+    pub offline_check: CheckButton,
+    pub prefetch_btn: Button,
+    pub mbtiles_btn: Button,
+    pub online_map_btn: Button,
+    pub track_color_widget: DropDown,
+    pub track_legend: gtk4::Box,
+    pub map_overlay: gtk4::Overlay,
+    pub no_position_label: Label,
+    pub distance_markers_check: CheckButton,
+    pub export_box: gtk4::Box,
+    pub export_gpx_btn: Button,
+    pub export_csv_btn: Button,
+    pub export_json_btn: Button,
     pub text_view: TextView,
     pub text_buffer: TextBuffer,
     pub frame_left: Frame,
@@ -46,7 +108,11 @@ pub struct UserInterface {
     pub scrolled_window: ScrolledWindow,
     pub map: libshumate::SimpleMap,
     pub path_layer: Option<PathLayer>,
+    pub compare_path_layer: Option<PathLayer>,
+    pub highlight_path_layer: Option<PathLayer>,
+    pub track_layers: RefCell<Vec<PathLayer>>, // segments of the colored track
     pub startstop_layer: Option<MarkerLayer>,
+    pub lap_marker_layer: Option<MarkerLayer>,
     pub marker_layer: Option<MarkerLayer>,
     pub da_window: ScrolledWindow,
     pub curr_pos_adj: Adjustment,
@@ -57,18 +123,34 @@ pub struct UserInterface {
     pub curr_pos_label: Label,
     pub curr_time_label: Label,
     pub y_zoom_label: Label,
+    pub x_axis_widget: DropDown,
+    pub best_effort_list: StringList,
+    pub best_effort_widget: DropDown,
     pub controls_box: gtk4::Box,
     pub uom: StringList,
     pub units_widget: DropDown,
     pub about_label: String,
     pub about_btn: Button,
     pub da: DrawingArea,
+    pub activity: RefCell<Vec<FitDataRecord>>,
+    pub comparison: RefCell<Vec<FitDataRecord>>,
+    pub library_view: RefCell<Option<Rc<LibraryView>>>,
+    pub graph_fields: RefCell<Vec<String>>,
+    pub best_efforts: RefCell<Vec<BestEffort>>,
+    pub heart_rate_zones: RefCell<HeartRateZones>,
+    pub pace_zones: RefCell<PaceZones>,
+    pub tile_source: RefCell<Option<TileSource>>, // None when drawing from an MBTiles file
+    pub mbtiles_path: RefCell<String>,
+    pub map_source_ids: RefCell<Vec<String>>, // in the order of the map source widget
+    pub custom_map_sources: RefCell<Vec<CustomMapSource>>,
+    pub highlight: Rc<Cell<Option<(usize, usize)>>>, // first and last record highlighted
 }
 
 // Instantiate the object holding the widgets (views).
 pub fn instantiate_ui(app: &Application) -> UserInterface {
     let mut ui = UserInterface {
-        settings_file: String::from(SETTINGSFILE),
+        settings_file: config_file_path(SETTINGSFILE),
+        library_file: config_file_path(LIBRARYFILE),
         win: ApplicationWindow::builder()
             .application(app)
             .title(PROGRAM_NAME)
@@ -96,6 +178,129 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
             .height_request(30)
             .width_request(50)
             .build(),
+        library_btn: Button::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .build(),
+        compare_btn: Button::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .sensitive(false)
+            .build(),
+        compare_clear_btn: Button::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .sensitive(false)
+            .build(),
+        export_menu_btn: MenuButton::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .sensitive(false)
+            .build(),
+        export_box: gtk4::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(5)
+            .build(),
+        graphs_menu_btn: MenuButton::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .sensitive(false)
+            .build(),
+        graphs_box: gtk4::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(5)
+            .build(),
+        smoothing_menu_btn: MenuButton::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .sensitive(false)
+            .build(),
+        smoothing_box: gtk4::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(5)
+            .build(),
+        smoothing_widget: DropDown::from_strings(&[
+            &tr("SMOOTHING_RAW", None),
+            &tr("SMOOTHING_ROLLING_MEAN", None),
+            &tr("SMOOTHING_ROLLING_MEDIAN", None),
+            &tr("SMOOTHING_EXPONENTIAL", None),
+        ]),
+        smoothing_window_spin: SpinButton::with_range(2.0, 120.0, 1.0),
+        remove_spikes_check: CheckButton::with_label(&tr("SMOOTHING_REMOVE_SPIKES", None)),
+        map_menu_btn: MenuButton::builder()
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .height_request(30)
+            .width_request(50)
+            .build(),
+        map_box: gtk4::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(5)
+            .build(),
+        map_source_list: StringList::new(&[]),
+        map_source_widget: DropDown::builder().build(),
+        add_map_source_btn: Button::builder().has_frame(false).build(),
+        remove_map_source_btn: Button::builder().has_frame(false).build(),
+        offline_check: CheckButton::with_label(&tr("MAP_OFFLINE_LABEL", None)),
+        prefetch_btn: Button::builder().has_frame(false).sensitive(false).build(),
+        mbtiles_btn: Button::builder().has_frame(false).build(),
+        online_map_btn: Button::builder().has_frame(false).build(),
+        track_color_widget: DropDown::from_strings(&[
+            &tr("TRACK_COLOR_SINGLE", None),
+            &tr("TRACK_COLOR_PACE", None),
+            &tr("TRACK_COLOR_HEART_RATE_ZONE", None),
+            &tr("TRACK_COLOR_ELEVATION", None),
+            &tr("TRACK_COLOR_GRADE", None),
+        ]),
+        track_legend: gtk4::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(2)
+            .halign(gtk4::Align::End)
+            .valign(gtk4::Align::Start)
+            .margin_top(10)
+            .margin_end(10)
+            .visible(false)
+            .can_target(false)
+            .css_classes(["track-legend"])
+            .build(),
+        map_overlay: gtk4::Overlay::new(),
+        no_position_label: Label::builder()
+            .label(tr("MAP_NO_POSITION_DATA", None))
+            .hexpand(true)
+            .vexpand(true)
+            .visible(false)
+            .css_classes(["no-position"])
+            .build(),
+        distance_markers_check: CheckButton::with_label(&tr("MAP_DISTANCE_MARKERS_LABEL", None)),
+        export_gpx_btn: Button::builder().has_frame(false).build(),
+        export_csv_btn: Button::builder().has_frame(false).build(),
+        export_json_btn: Button::builder().has_frame(false).build(),
         text_view: TextView::builder()
             .monospace(true)
             .editable(false)
@@ -124,7 +329,11 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
         scrolled_window: ScrolledWindow::builder().margin_top(5).build(),
         map: SimpleMap::new(),
         path_layer: None,
+        compare_path_layer: None,
+        highlight_path_layer: None,
+        track_layers: RefCell::new(Vec::new()),
         startstop_layer: None,
+        lap_marker_layer: None,
         marker_layer: None,
         da_window: ScrolledWindow::builder()
             .vexpand(true)
@@ -168,6 +377,13 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
         curr_pos_label: Label::new(Some("🏃‍➡️")),
         curr_time_label: Label::new(Some("")),
         y_zoom_label: Label::new(Some("🔍")),
+        x_axis_widget: DropDown::from_strings(&[
+            &tr("X_AXIS_DISTANCE", None),
+            &tr("X_AXIS_ELAPSED_TIME", None),
+            &tr("X_AXIS_CLOCK_TIME", None),
+        ]),
+        best_effort_list: StringList::new(&[&tr("BEST_EFFORT_NONE", None)]),
+        best_effort_widget: DropDown::builder().sensitive(false).build(),
         //        controls_box: gtk4::Box::new(Orientation::Vertical, 10),
         controls_box: gtk4::Box::builder()
             .orientation(Orientation::Vertical)
@@ -193,10 +409,27 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
             .width_request(50)
             .build(),
         da: DrawingArea::builder().width_request(400).build(),
+        activity: RefCell::new(Vec::new()),
+        comparison: RefCell::new(Vec::new()),
+        library_view: RefCell::new(None),
+        graph_fields: RefCell::new(Vec::new()),
+        best_efforts: RefCell::new(Vec::new()),
+        heart_rate_zones: RefCell::new(HeartRateZones::default()),
+        pace_zones: RefCell::new(PaceZones::default()),
+        tile_source: RefCell::new(None),
+        mbtiles_path: RefCell::new(String::new()),
+        map_source_ids: RefCell::new(Vec::new()),
+        custom_map_sources: RefCell::new(Vec::new()),
+        highlight: Rc::new(Cell::new(None)),
     };
     let provider = gtk4::CssProvider::new();
-    let css_data =
-        "textview { font: 14px monospace; font-weight: 500; color: black; background: white; }";
+    let css_data = concat!(
+        "textview { font: 14px monospace; font-weight: 500; color: black; background: white; }",
+        ".track-legend { padding: 5px; border-radius: 5px; color: black; background: rgba(255, 255, 255, 0.85); }",
+        ".lap-marker { padding: 0 3px; border: 1px solid black; border-radius: 3px; font-size: small; font-weight: bold; color: white; background: black; }",
+        ".distance-marker { padding: 0 3px; border: 1px solid black; border-radius: 3px; font-size: small; color: black; background: white; }",
+        ".no-position { font-size: large; color: dimgray; background: whitesmoke; }"
+    );
     provider.load_from_data(css_data);
     gtk4::style_context_add_provider_for_display(
         &gdk::Display::default().expect("Could not get default display."),
@@ -207,6 +440,7 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
     ui.y_zoom_scale.set_adjustment(&ui.y_zoom_adj);
     ui.about_btn.set_label(&ui.about_label);
     ui.units_widget.set_model(Some(&ui.uom));
+    ui.best_effort_widget.set_model(Some(&ui.best_effort_list));
     ui.text_view.set_buffer(Some(&ui.text_buffer));
     ui.text_view
         .set_tooltip_text(Some(&tr("TOOLTIP_TEXT_VIEW", None)));
@@ -225,12 +459,124 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
     ui.btn.set_child(Some(&button_content));
     ui.btn
         .set_tooltip_text(Some(&tr("TOOLTIP_OPEN_BUTTON", None)));
+    ui.library_btn.set_label(&tr("LIBRARY_BUTTON_LABEL", None));
+    ui.library_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_LIBRARY_BUTTON", None)));
+    ui.compare_btn.set_label(&tr("COMPARE_BUTTON_LABEL", None));
+    ui.compare_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_COMPARE_BUTTON", None)));
+    ui.compare_clear_btn
+        .set_label(&tr("COMPARE_CLEAR_BUTTON_LABEL", None));
+    ui.compare_clear_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_COMPARE_CLEAR_BUTTON", None)));
+    // Export menu, one button per file format.
+    ui.export_menu_btn
+        .set_label(&tr("EXPORT_BUTTON_LABEL", None));
+    ui.export_menu_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_EXPORT_BUTTON", None)));
+    ui.export_gpx_btn
+        .set_label(&tr("EXPORT_GPX_BUTTON_LABEL", None));
+    ui.export_gpx_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_EXPORT_GPX_BUTTON", None)));
+    ui.export_csv_btn
+        .set_label(&tr("EXPORT_CSV_BUTTON_LABEL", None));
+    ui.export_csv_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_EXPORT_CSV_BUTTON", None)));
+    ui.export_json_btn
+        .set_label(&tr("EXPORT_JSON_BUTTON_LABEL", None));
+    ui.export_json_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_EXPORT_JSON_BUTTON", None)));
+    ui.export_box.append(&ui.export_gpx_btn);
+    ui.export_box.append(&ui.export_csv_btn);
+    ui.export_box.append(&ui.export_json_btn);
+    let export_popover = Popover::builder().child(&ui.export_box).build();
+    ui.export_menu_btn.set_popover(Some(&export_popover));
+    // Graphs menu, one check button per record field, filled in once a file is read.
+    ui.graphs_menu_btn
+        .set_label(&tr("GRAPHS_BUTTON_LABEL", None));
+    ui.graphs_menu_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_GRAPHS_BUTTON", None)));
+    let graphs_popover = Popover::builder().child(&ui.graphs_box).build();
+    ui.graphs_menu_btn.set_popover(Some(&graphs_popover));
+    // Smoothing menu, the filter, its window and spike removal.
+    ui.smoothing_menu_btn
+        .set_label(&tr("SMOOTHING_BUTTON_LABEL", None));
+    ui.smoothing_menu_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_SMOOTHING_BUTTON", None)));
+    ui.smoothing_window_spin
+        .set_tooltip_text(Some(&tr("TOOLTIP_SMOOTHING_WINDOW", None)));
+    ui.remove_spikes_check
+        .set_tooltip_text(Some(&tr("TOOLTIP_SMOOTHING_REMOVE_SPIKES", None)));
+    let window_row = gtk4::Box::new(Orientation::Horizontal, 5);
+    window_row.append(&Label::new(Some(&tr("SMOOTHING_WINDOW_LABEL", None))));
+    window_row.append(&ui.smoothing_window_spin);
+    ui.smoothing_box.append(&ui.smoothing_widget);
+    ui.smoothing_box.append(&window_row);
+    ui.smoothing_box.append(&ui.remove_spikes_check);
+    let smoothing_popover = Popover::builder().child(&ui.smoothing_box).build();
+    ui.smoothing_menu_btn.set_popover(Some(&smoothing_popover));
+    // Map menu, the tile cache and MBTiles files.
+    ui.map_menu_btn.set_label(&tr("MAP_BUTTON_LABEL", None));
+    ui.map_menu_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_BUTTON", None)));
+    ui.map_source_widget.set_model(Some(&ui.map_source_list));
+    ui.map_source_widget
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_SOURCE", None)));
+    ui.add_map_source_btn
+        .set_label(&tr("MAP_SOURCE_ADD_LABEL", None));
+    ui.add_map_source_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_SOURCE_ADD", None)));
+    ui.remove_map_source_btn
+        .set_label(&tr("MAP_SOURCE_REMOVE_LABEL", None));
+    ui.remove_map_source_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_SOURCE_REMOVE", None)));
+    ui.offline_check
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_OFFLINE", None)));
+    ui.prefetch_btn.set_label(&tr("MAP_PREFETCH_LABEL", None));
+    ui.prefetch_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_PREFETCH", None)));
+    ui.mbtiles_btn
+        .set_label(&tr("MAP_OPEN_MBTILES_LABEL", None));
+    ui.mbtiles_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_OPEN_MBTILES", None)));
+    ui.online_map_btn.set_label(&tr("MAP_ONLINE_LABEL", None));
+    ui.online_map_btn
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_ONLINE", None)));
+    ui.map_box.append(&ui.map_source_widget);
+    ui.map_box.append(&ui.add_map_source_btn);
+    ui.map_box.append(&ui.remove_map_source_btn);
+    ui.map_box.append(&ui.offline_check);
+    ui.map_box.append(&ui.prefetch_btn);
+    ui.map_box.append(&ui.mbtiles_btn);
+    ui.map_box.append(&ui.online_map_btn);
+    ui.track_color_widget
+        .set_tooltip_text(Some(&tr("TOOLTIP_TRACK_COLOR", None)));
+    let track_color_row = gtk4::Box::new(Orientation::Horizontal, 5);
+    track_color_row.append(&Label::new(Some(&tr("TRACK_COLOR_LABEL", None))));
+    track_color_row.append(&ui.track_color_widget);
+    ui.map_box.append(&track_color_row);
+    ui.distance_markers_check
+        .set_tooltip_text(Some(&tr("TOOLTIP_MAP_DISTANCE_MARKERS", None)));
+    ui.map_box.append(&ui.distance_markers_check);
+    // The legend of the colored track floats over the map.
+    ui.map_overlay.set_child(Some(&ui.map));
+    ui.map_overlay.add_overlay(&ui.track_legend);
+    ui.map_overlay.add_overlay(&ui.no_position_label);
+    let map_popover = Popover::builder().child(&ui.map_box).build();
+    ui.map_menu_btn.set_popover(Some(&map_popover));
 
     ui.units_widget
         .set_tooltip_text(Some(&tr("TOOLTIP_UNITS_DROPDOWN", None)));
     ui.win.set_icon_name(Some(ICON_NAME));
     ui.win.set_child(Some(&ui.outer_box));
     ui.button_box.append(&ui.btn);
+    ui.button_box.append(&ui.library_btn);
+    ui.button_box.append(&ui.compare_btn);
+    ui.button_box.append(&ui.compare_clear_btn);
+    ui.button_box.append(&ui.graphs_menu_btn);
+    ui.button_box.append(&ui.smoothing_menu_btn);
+    ui.button_box.append(&ui.map_menu_btn);
+    ui.button_box.append(&ui.export_menu_btn);
     ui.button_box.append(&ui.units_widget);
     ui.button_box.append(&ui.about_btn);
     ui.outer_box.append(&ui.button_box);
@@ -240,9 +586,25 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
     ui.controls_box.append(&ui.curr_pos_label);
     ui.controls_box.append(&ui.curr_pos_scale);
     ui.controls_box.append(&ui.curr_time_label);
-    ui.path_layer = Some(add_path_layer_to_map(&ui.map).unwrap());
+    ui.controls_box.append(&ui.x_axis_widget);
+    ui.controls_box.append(&ui.best_effort_widget);
+    // The comparison path is added first so that it is drawn below the activity.
+    ui.compare_path_layer = Some(add_path_layer_to_map(&ui.map, "magenta").unwrap());
+    ui.path_layer = Some(add_path_layer_to_map(&ui.map, "blue").unwrap());
+    // The highlighted stretch is drawn wider, over the activity.
+    ui.highlight_path_layer = Some(add_path_layer_to_map(&ui.map, "orange").unwrap());
+    ui.highlight_path_layer
+        .as_ref()
+        .unwrap()
+        .set_stroke_width(5.0);
     ui.startstop_layer = Some(add_marker_layer_to_map(&ui.map).unwrap());
+    ui.lap_marker_layer = Some(add_marker_layer_to_map(&ui.map).unwrap());
     ui.marker_layer = Some(add_marker_layer_to_map(&ui.map).unwrap());
+    // Marks the lap whose marker was clicked on the map.
+    ui.text_buffer.create_tag(
+        Some(LAP_HIGHLIGHT_TAG),
+        &[("paragraph-background", &"#ffe082")],
+    );
 
     ui.curr_pos_scale
         .set_tooltip_text(Some(&tr("TOOLTIP_POSITION_SCALE", None)));
@@ -252,20 +614,14 @@ pub fn instantiate_ui(app: &Application) -> UserInterface {
         .set_tooltip_text(Some(&tr("TOOLTIP_ZOOM_SCALE", None)));
     ui.y_zoom_label
         .set_tooltip_text(Some(&tr("TOOLTIP_ZOOM_SCALE", None)));
+    ui.x_axis_widget
+        .set_tooltip_text(Some(&tr("TOOLTIP_X_AXIS_DROPDOWN", None)));
+    ui.best_effort_widget
+        .set_tooltip_text(Some(&tr("TOOLTIP_BEST_EFFORT_DROPDOWN", None)));
     ui.frame_left
         .set_tooltip_text(Some(&tr("TOOLTIP_MAP_FRAME", None)));
     ui.frame_right
         .set_tooltip_text(Some(&tr("TOOLTIP_GRAPH_FRAME", None)));
-    // query paths of user-invisible standard directories.
-    let base_dirs = BaseDirs::new();
-    if base_dirs.is_some() {
-        ui.settings_file = base_dirs
-            .unwrap()
-            .config_dir()
-            .join(SETTINGSFILE)
-            .to_string_lossy()
-            .to_string();
-    }
     set_up_user_defaults(&ui);
     return ui;
 }
@@ -284,7 +640,7 @@ pub fn construct_views_from_data(
     ui.da_window.set_child(Some(&ui.da));
     ui.frame_right.set_child(Some(&ui.da_window));
 
-    ui.frame_left.set_child(Some(&ui.map));
+    ui.frame_left.set_child(Some(&ui.map_overlay));
     // 3. Configure the widget layout.
     ui.left_frame_pane.set_start_child(Some(&ui.frame_left));
     ui.left_frame_pane.set_end_child(Some(&ui.scrolled_window));
@@ -397,14 +753,635 @@ pub fn set_up_user_defaults(ui: &UserInterface) {
     ui.right_frame_pane.set_position(config.right_frame_split);
     ui.left_frame_pane.set_position(config.left_frame_split);
     ui.units_widget.set_selected(config.units_index);
+    ui.x_axis_widget.set_selected(config.x_axis_index);
+    ui.graph_fields.replace(config.graph_fields);
+    ui.smoothing_widget.set_selected(config.smoothing_index);
+    ui.smoothing_window_spin
+        .set_value(config.smoothing_window as f64);
+    ui.remove_spikes_check.set_active(config.remove_spikes);
+    ui.track_color_widget.set_selected(config.track_color_index);
+    ui.distance_markers_check
+        .set_active(config.distance_markers);
+    ui.heart_rate_zones.replace(config.heart_rate_zones);
+    ui.pace_zones.replace(config.pace_zones);
+    ui.offline_check.set_active(config.offline_map);
+    ui.mbtiles_path.replace(config.mbtiles_path);
+    ui.custom_map_sources.replace(config.map_sources);
+    populate_map_source_menu(ui, &config.map_source);
+    // The MBTiles file, if any, is opened once the window is shown.
+    set_up_online_map_source(ui);
+}
+
+// Return the record field to plot the graphs against from the x axis widget.
+pub fn get_x_axis_field(x_axis_widget: &DropDown) -> &'static str {
+    match x_axis_widget.selected() {
+        1 => return "elapsed_time",
+        2 => return "clock_time",
+        _ => return "distance",
+    }
+}
+
+// Return the filter the graphs are smoothed with from the smoothing widget.
+pub fn get_smoothing_filter(smoothing_widget: &DropDown) -> SmoothingFilter {
+    match smoothing_widget.selected() {
+        1 => return SmoothingFilter::RollingMean,
+        2 => return SmoothingFilter::RollingMedian,
+        3 => return SmoothingFilter::Exponential,
+        _ => return SmoothingFilter::Raw,
+    }
+}
+
+// Return the metric the map track is colored by from the track color
+// widget, None for a single color.
+pub fn get_track_metric(track_color_widget: &DropDown) -> Option<TrackMetric> {
+    match track_color_widget.selected() {
+        1 => return Some(TrackMetric::Pace),
+        2 => return Some(TrackMetric::HeartRateZone),
+        3 => return Some(TrackMetric::Elevation),
+        4 => return Some(TrackMetric::Grade),
+        _ => return None,
+    }
+}
+
+// #####################################################################
+// ##################### LIBRARY FUNCTIONS #############################
+// #####################################################################
+// Column titles of the library window, in display order.
+const LIBRARY_COLUMNS: [&str; 5] = [
+    "LIBRARY_COLUMN_DATE",
+    "LIBRARY_COLUMN_SPORT",
+    "LIBRARY_COLUMN_DISTANCE",
+    "LIBRARY_COLUMN_DURATION",
+    "LIBRARY_COLUMN_AVG_HEART_RATE",
+];
+
+// Widgets used for the activity library window.
+pub struct LibraryView {
+    pub window: gtk4::Window,
+    pub folder_label: Label,
+    pub choose_btn: Button,
+    pub rescan_btn: Button,
+    pub volume_btn: Button,
+    pub records_btn: Button,
+    pub load_btn: Button,
+    pub column_view: ColumnView,
+    pub rows: StringList,
+    pub entries: Rc<RefCell<Vec<LibraryEntry>>>,
+    pub units: Rc<Cell<Units>>,
+}
+
+// Return the text shown in a library column.
+fn library_cell_text(entry: &LibraryEntry, column: usize, user_unit: &Units) -> String {
+    match column {
+        0 => {
+            return DateTime::from_timestamp(entry.start_time, 0)
+                .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+        }
+        1 => return entry.sport.clone(),
+        2 => {
+            let unit_label = match user_unit {
+                Units::US => tr("UNIT_MILES", None),
+                _ => tr("UNIT_KM", None),
+            };
+            let distance = cvt_distance(entry.distance as f32, user_unit);
+            return format!("{:.2} {}", distance, unit_label);
+        }
+        3 => {
+            let (hr, min, sec) = cvt_elapsed_time(entry.duration as f32);
+            return format!("{:01}h:{:02}m:{:02}s", hr, min, sec);
+        }
+        _ => match entry.avg_heart_rate {
+            Some(hr) => return format!("{} bpm", hr as i32),
+            None => return String::from("-"),
+        },
+    }
+}
+
+// Compare two library entries on the value of a column.
+fn library_cell_order(a: &LibraryEntry, b: &LibraryEntry, column: usize) -> std::cmp::Ordering {
+    match column {
+        0 => return a.start_time.cmp(&b.start_time),
+        1 => return a.sport.cmp(&b.sport),
+        2 => return a.distance.total_cmp(&b.distance),
+        3 => return a.duration.total_cmp(&b.duration),
+        _ => {
+            return a
+                .avg_heart_rate
+                .unwrap_or(0.0)
+                .total_cmp(&b.avg_heart_rate.unwrap_or(0.0));
+        }
+    }
+}
+
+// Return the position in the entries vector stored in a library row.
+fn library_row_index(row: &glib::Object) -> Option<usize> {
+    let string_obj = row.downcast_ref::<StringObject>()?;
+    return string_obj.string().parse::<usize>().ok();
+}
+
+// Instantiate the library window.  Rows hold the position of their entry so
+// that the columns can sort and format the shared entries vector.
+pub fn instantiate_library_view(ui: &UserInterface) -> LibraryView {
+    let view = LibraryView {
+        window: gtk4::Window::builder()
+            .transient_for(&ui.win)
+            .title(tr("LIBRARY_WINDOW_TITLE", None))
+            .default_width(700)
+            .default_height(500)
+            .hide_on_close(true)
+            .build(),
+        folder_label: Label::builder().xalign(0.0).hexpand(true).build(),
+        choose_btn: Button::builder()
+            .label(tr("LIBRARY_CHOOSE_FOLDER_BUTTON_LABEL", None))
+            .tooltip_text(tr("TOOLTIP_LIBRARY_CHOOSE_FOLDER_BUTTON", None))
+            .build(),
+        rescan_btn: Button::builder()
+            .label(tr("LIBRARY_RESCAN_BUTTON_LABEL", None))
+            .tooltip_text(tr("TOOLTIP_LIBRARY_RESCAN_BUTTON", None))
+            .build(),
+        volume_btn: Button::builder()
+            .label(tr("VOLUME_BUTTON_LABEL", None))
+            .tooltip_text(tr("TOOLTIP_VOLUME_BUTTON", None))
+            .build(),
+        records_btn: Button::builder()
+            .label(tr("RECORDS_BUTTON_LABEL", None))
+            .tooltip_text(tr("TOOLTIP_RECORDS_BUTTON", None))
+            .build(),
+        load_btn: Button::builder()
+            .label(tr("LOAD_BUTTON_LABEL", None))
+            .tooltip_text(tr("TOOLTIP_LOAD_BUTTON", None))
+            .build(),
+        column_view: ColumnView::builder()
+            .show_row_separators(true)
+            .tooltip_text(tr("TOOLTIP_LIBRARY_VIEW", None))
+            .build(),
+        rows: StringList::new(&[]),
+        entries: Rc::new(RefCell::new(Vec::new())),
+        units: Rc::new(Cell::new(Units::Metric)),
+    };
+    let mut date_column: Option<ColumnViewColumn> = None;
+    for (column, title) in LIBRARY_COLUMNS.iter().enumerate() {
+        let factory = SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            if let Some(item) = item.downcast_ref::<ListItem>() {
+                item.set_child(Some(&Label::builder().xalign(0.0).build()));
+            }
+        });
+        factory.connect_bind(clone!(
+            #[strong(rename_to = entries)]
+            view.entries,
+            #[strong(rename_to = units)]
+            view.units,
+            move |_, item| {
+                let Some(item) = item.downcast_ref::<ListItem>() else {
+                    return;
+                };
+                let entries = entries.borrow();
+                let entry = item
+                    .item()
+                    .and_then(|row| library_row_index(&row))
+                    .and_then(|i| entries.get(i));
+                if let (Some(label), Some(entry)) = (item.child().and_downcast::<Label>(), entry) {
+                    label.set_text(&library_cell_text(entry, column, &units.get()));
+                }
+            }
+        ));
+        let sorter = CustomSorter::new(clone!(
+            #[strong(rename_to = entries)]
+            view.entries,
+            move |a, b| {
+                let entries = entries.borrow();
+                let entry_a = library_row_index(a).and_then(|i| entries.get(i));
+                let entry_b = library_row_index(b).and_then(|i| entries.get(i));
+                match (entry_a, entry_b) {
+                    (Some(a), Some(b)) => return library_cell_order(a, b, column).into(),
+                    _ => return gtk4::Ordering::Equal,
+                }
+            }
+        ));
+        let view_column = ColumnViewColumn::new(Some(&tr(title, None)), Some(factory));
+        view_column.set_sorter(Some(&sorter));
+        view_column.set_resizable(true);
+        view_column.set_expand(true);
+        view.column_view.append_column(&view_column);
+        if column == 0 {
+            date_column = Some(view_column);
+        }
+    }
+    let sorted = SortListModel::new(Some(view.rows.clone()), view.column_view.sorter());
+    view.column_view
+        .set_model(Some(&SingleSelection::new(Some(sorted))));
+    // Most recent activity first.
+    view.column_view
+        .sort_by_column(date_column.as_ref(), SortType::Descending);
+
+    let button_row = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(10)
+        .margin_top(5)
+        .margin_bottom(5)
+        .margin_start(5)
+        .margin_end(5)
+        .build();
+    button_row.append(&view.choose_btn);
+    button_row.append(&view.rescan_btn);
+    button_row.append(&view.volume_btn);
+    button_row.append(&view.records_btn);
+    button_row.append(&view.load_btn);
+    button_row.append(&view.folder_label);
+    let list_window = ScrolledWindow::builder()
+        .vexpand(true)
+        .hexpand(true)
+        .child(&view.column_view)
+        .build();
+    let content = gtk4::Box::new(Orientation::Vertical, 5);
+    content.append(&button_row);
+    content.append(&list_window);
+    view.window.set_child(Some(&content));
+    return view;
+}
+
+// Fill the library window from an index, showing values in the given units.
+pub fn populate_library_view(view: &LibraryView, index: &LibraryIndex, user_unit: Units) {
+    view.units.set(user_unit);
+    view.entries.replace(index.entries.clone());
+    let positions: Vec<String> = (0..index.entries.len()).map(|i| i.to_string()).collect();
+    let position_strs: Vec<&str> = positions.iter().map(|s| s.as_str()).collect();
+    view.rows.splice(0, view.rows.n_items(), &position_strs);
+    if index.folder.is_empty() {
+        view.folder_label.set_text(&tr("LIBRARY_NO_FOLDER", None));
+    } else {
+        view.folder_label.set_text(&index.folder);
+    }
+    view.rescan_btn.set_sensitive(!index.folder.is_empty());
+}
+
+// Return the library entry displayed at a position of the (sorted) list.
+pub fn library_entry_at(view: &LibraryView, position: u32) -> Option<LibraryEntry> {
+    let row = view.column_view.model()?.item(position)?;
+    let i = library_row_index(&row)?;
+    return view.entries.borrow().get(i).cloned();
+}
+
+// Values the volume chart can show, in the order of its dropdown.
+const VOLUME_METRICS: [&str; 5] = [
+    "VOLUME_COLUMN_DISTANCE",
+    "VOLUME_COLUMN_TIME",
+    "VOLUME_COLUMN_ASCENT",
+    "VOLUME_COLUMN_ACTIVITIES",
+    "VOLUME_COLUMN_PACE",
+];
+
+// Return the selected string of a dropdown built on a StringList.
+fn selected_string(widget: &DropDown) -> Option<String> {
+    let item = widget.selected_item()?;
+    let string_obj = item.downcast::<StringObject>().ok()?;
+    return Some(String::from(string_obj.string()));
+}
+
+// Use plotters.rs to draw the volume totals as a bar chart.
+fn draw_volume_chart(
+    totals: &Vec<VolumeTotals>,
+    metric: usize,
+    user_unit: &Units,
+    cr: &Context,
+    width: f64,
+    height: f64,
+) {
+    let root = plotters_cairo::CairoBackend::new(&cr, (width as u32, height as u32))
+        .unwrap()
+        .into_drawing_area();
+    let _ = root.fill(&WHITE);
+    if totals.is_empty() {
+        let _ = root.present();
+        return;
+    }
+    let (distance_unit, ascent_unit, pace_unit) = match user_unit {
+        Units::US => ("UNIT_MILES", "UNIT_FEET", "UNIT_PACE_US"),
+        _ => ("UNIT_KM", "UNIT_METERS", "UNIT_PACE_METRIC"),
+    };
+    let (values, ylabel): (Vec<f32>, String) = match metric {
+        0 => (
+            totals
+                .iter()
+                .map(|t| cvt_distance(t.distance as f32, user_unit))
+                .collect(),
+            tr(distance_unit, None),
+        ),
+        1 => (
+            totals.iter().map(|t| t.duration as f32 / 3600.0).collect(),
+            String::from("h"),
+        ),
+        2 => (
+            totals
+                .iter()
+                .map(|t| cvt_altitude(t.ascent as f32, user_unit))
+                .collect(),
+            tr(ascent_unit, None),
+        ),
+        3 => (
+            totals.iter().map(|t| t.count as f32).collect(),
+            tr("VOLUME_COLUMN_ACTIVITIES", None),
+        ),
+        _ => (
+            totals
+                .iter()
+                .map(|t| {
+                    if t.duration > 0.0 {
+                        cvt_pace(t.avg_speed() as f32, user_unit)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect(),
+            tr(pace_unit, None),
+        ),
+    };
+    let y_max = values.iter().cloned().fold(0.0, f32::max).max(1.0) * 1.1;
+    let labels: Vec<String> = totals.iter().map(|t| t.label.clone()).collect();
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            tr(VOLUME_METRICS[metric], None),
+            ("sans-serif", 16).into_font(),
+        )
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .margin(10)
+        .build_cartesian_2d((0..totals.len()).into_segmented(), 0f32..y_max)
+        .unwrap();
+    let _ = chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(labels.len().min(12))
+        .x_label_formatter(&|x| match x {
+            SegmentValue::CenterOf(i) => labels.get(*i).cloned().unwrap_or_default(),
+            _ => String::new(),
+        })
+        .y_desc(ylabel)
+        .draw();
+    let _ = chart.draw_series(
+        Histogram::vertical(&chart)
+            .style(GREEN.filled())
+            .margin(3)
+            .data(values.iter().enumerate().map(|(i, v)| (i, *v))),
+    );
+    let _ = root.present();
+}
+
+// Create and present the training volume window for the library entries.
+// The chart is redrawn whenever the period, value or sport selection changes.
+pub fn show_volume_window(parent: &gtk4::Window, entries: Vec<LibraryEntry>, user_unit: Units) {
+    let window = gtk4::Window::builder()
+        .transient_for(parent)
+        .title(tr("VOLUME_WINDOW_TITLE", None))
+        .default_width(800)
+        .default_height(500)
+        .build();
+    let period_widget = DropDown::from_strings(&[
+        &tr("VOLUME_PERIOD_WEEK", None),
+        &tr("VOLUME_PERIOD_MONTH", None),
+        &tr("VOLUME_PERIOD_YEAR", None),
+    ]);
+    let metric_names: Vec<String> = VOLUME_METRICS.iter().map(|m| tr(m, None)).collect();
+    let metric_strs: Vec<&str> = metric_names.iter().map(|s| s.as_str()).collect();
+    let metric_widget = DropDown::from_strings(&metric_strs);
+    let mut sports: Vec<String> = entries.iter().map(|e| e.sport.clone()).collect();
+    sports.sort();
+    sports.dedup();
+    let all_sports = tr("VOLUME_ALL_SPORTS", None);
+    let mut sport_strs: Vec<&str> = vec![all_sports.as_str()];
+    sport_strs.extend(sports.iter().map(|s| s.as_str()));
+    let sport_widget = DropDown::from_strings(&sport_strs);
+    let da = DrawingArea::builder().vexpand(true).hexpand(true).build();
+    da.set_draw_func(clone!(
+        #[strong]
+        period_widget,
+        #[strong]
+        metric_widget,
+        #[strong]
+        sport_widget,
+        move |_drawing_area, cr, width, height| {
+            let period = match period_widget.selected() {
+                1 => VolumePeriod::Month,
+                2 => VolumePeriod::Year,
+                _ => VolumePeriod::Week,
+            };
+            // The first sport entry stands for all sports.
+            let sport = match sport_widget.selected() {
+                0 => None,
+                _ => selected_string(&sport_widget),
+            };
+            let totals = volume_totals(&entries, period, sport.as_deref());
+            let metric = (metric_widget.selected() as usize).min(VOLUME_METRICS.len() - 1);
+            draw_volume_chart(&totals, metric, &user_unit, cr, width as f64, height as f64);
+        }
+    ));
+    for widget in [&period_widget, &metric_widget, &sport_widget] {
+        widget.connect_selected_notify(clone!(
+            #[weak]
+            da,
+            move |_| {
+                da.queue_draw();
+            }
+        ));
+    }
+    let controls = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(10)
+        .margin_top(5)
+        .margin_bottom(5)
+        .margin_start(5)
+        .margin_end(5)
+        .build();
+    controls.append(&period_widget);
+    controls.append(&metric_widget);
+    controls.append(&sport_widget);
+    let content = gtk4::Box::new(Orientation::Vertical, 5);
+    content.append(&controls);
+    content.append(&da);
+    window.set_child(Some(&content));
+    window.present();
+}
+
+// Create and present the personal records window, the fastest library
+// activity of each sport over each best effort distance.
+pub fn show_records_window(parent: &gtk4::Window, entries: Vec<LibraryEntry>, user_unit: Units) {
+    let text = build_records_report(&entries, None, &user_unit);
+    let text_view = TextView::builder()
+        .monospace(true)
+        .editable(false)
+        .left_margin(25)
+        .right_margin(25)
+        .build();
+    text_view.buffer().set_text(&text);
+    let window = gtk4::Window::builder()
+        .transient_for(parent)
+        .title(tr("RECORDS_WINDOW_TITLE", None))
+        .default_width(900)
+        .default_height(300)
+        .child(&ScrolledWindow::builder().child(&text_view).build())
+        .build();
+    window.present();
+}
+
+// Use plotters.rs to draw the acute load, chronic load and form by day.
+fn draw_training_load_chart(days: &Vec<TrainingLoad>, cr: &Context, width: f64, height: f64) {
+    let root = plotters_cairo::CairoBackend::new(&cr, (width as u32, height as u32))
+        .unwrap()
+        .into_drawing_area();
+    let _ = root.fill(&WHITE);
+    if days.is_empty() {
+        let _ = root.present();
+        return;
+    }
+    let y_max = days
+        .iter()
+        .map(|d| d.acute_load.max(d.chronic_load) as f32)
+        .fold(0.0, f32::max)
+        .max(1.0)
+        * 1.1;
+    let y_min = days.iter().map(|d| d.form as f32).fold(0.0, f32::min) * 1.1;
+    let dates: Vec<String> = days
+        .iter()
+        .map(|d| d.date.format("%Y-%m-%d").to_string())
+        .collect();
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            tr("LOAD_WINDOW_TITLE", None),
+            ("sans-serif", 16).into_font(),
+        )
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .margin(10)
+        .build_cartesian_2d(0f32..(days.len() - 1).max(1) as f32, y_min..y_max)
+        .unwrap();
+    let _ = chart
+        .configure_mesh()
+        .x_labels(6)
+        .x_label_formatter(&|x| dates.get(*x as usize).cloned().unwrap_or_default())
+        .y_desc(tr("LOAD_AXIS_LABEL", None))
+        .draw();
+    let series: [(&str, RGBColor, fn(&TrainingLoad) -> f64); 3] = [
+        ("LOAD_CHRONIC", BLUE, |d| d.chronic_load),
+        ("LOAD_ACUTE", RED, |d| d.acute_load),
+        ("LOAD_FORM", GREEN, |d| d.form),
+    ];
+    for (name, color, value) in series {
+        let points = days
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (i as f32, value(d) as f32));
+        if let Ok(drawn) = chart.draw_series(LineSeries::new(points, &color)) {
+            drawn
+                .label(tr(name, None))
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        }
+    }
+    let _ = chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw();
+    let _ = root.present();
+}
+
+// Create and present the training load window for the library entries.  The
+// chart is redrawn whenever the sport selection changes.
+pub fn show_training_load_window(
+    parent: &gtk4::Window,
+    entries: Vec<LibraryEntry>,
+    hr_zones: HeartRateZones,
+) {
+    let window = gtk4::Window::builder()
+        .transient_for(parent)
+        .title(tr("LOAD_WINDOW_TITLE", None))
+        .default_width(800)
+        .default_height(500)
+        .build();
+    let mut sports: Vec<String> = entries.iter().map(|e| e.sport.clone()).collect();
+    sports.sort();
+    sports.dedup();
+    let all_sports = tr("VOLUME_ALL_SPORTS", None);
+    let mut sport_strs: Vec<&str> = vec![all_sports.as_str()];
+    sport_strs.extend(sports.iter().map(|s| s.as_str()));
+    let sport_widget = DropDown::from_strings(&sport_strs);
+    let da = DrawingArea::builder().vexpand(true).hexpand(true).build();
+    da.set_draw_func(clone!(
+        #[strong]
+        sport_widget,
+        move |_drawing_area, cr, width, height| {
+            // The first sport entry stands for all sports.
+            let sport = match sport_widget.selected() {
+                0 => None,
+                _ => selected_string(&sport_widget),
+            };
+            let days = training_load(&entries, sport.as_deref(), &hr_zones);
+            draw_training_load_chart(&days, cr, width as f64, height as f64);
+        }
+    ));
+    sport_widget.connect_selected_notify(clone!(
+        #[weak]
+        da,
+        move |_| {
+            da.queue_draw();
+        }
+    ));
+    let controls = gtk4::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(10)
+        .margin_top(5)
+        .margin_bottom(5)
+        .margin_start(5)
+        .margin_end(5)
+        .build();
+    controls.append(&sport_widget);
+    let content = gtk4::Box::new(Orientation::Vertical, 5);
+    content.append(&controls);
+    content.append(&da);
+    window.set_child(Some(&content));
+    window.present();
 }
 
 // #####################################################################
 // ##################### GRAPH FUNCTIONS ###############################
 // #####################################################################
+// Colour of the comparison activity on the graphs and map.
+const COMPARE_COLOR: RGBColor = MAGENTA;
+// Colour of the highlighted stretch of an activity on the graphs and map.
+const HIGHLIGHT_COLOR: RGBColor = ORANGE;
+// Colour of a derived series drawn over a graph.
+const OVERLAY_COLOR: RGBColor = GREY_500;
+// Colours of the training zones, easiest first.
+const ZONE_COLORS: [RGBColor; 7] = [
+    GREY_400, BLUE_400, GREEN_400, YELLOW_600, ORANGE_400, RED_400, PURPLE_400,
+];
+
+// Return the colour a record field is graphed in.
+fn graph_color(field: &str) -> RGBColor {
+    match field {
+        "enhanced_speed" | "speed" => return GREEN,
+        "heart_rate" => return BLUE,
+        "cadence" => return CYAN,
+        "enhanced_altitude" | "altitude" => return RED,
+        "temperature" => return BROWN,
+        "power" => return ORANGE,
+        "vertical_oscillation" => return PURPLE,
+        "stance_time" => return TEAL,
+        "step_length" => return INDIGO,
+        "respiration_rate" | "enhanced_respiration_rate" => return PINK,
+        "grade" => return DEEPORANGE,
+        "efficiency_factor" => return LIGHTGREEN_900,
+        _ => return GREY_700,
+    }
+}
+
 // Use plotters.rs to draw a graph on the drawing area.
 fn draw_graphs(
     gc_rc: &Rc<GraphCache>,
+    compare_gc: Option<&GraphCache>,
+    highlight: Option<(usize, usize)>,
     curr_adj: &Adjustment,
     cr: &Context,
     width: f64,
@@ -412,97 +1389,36 @@ fn draw_graphs(
 ) {
     // --- 🎨 Custom Drawing Logic Starts Here ---
     let gc = &**gc_rc;
+    // The highlighted records are placed on the graphs by their x values.
+    let highlight = highlight.and_then(|(start, end)| {
+        let stretch = gc.record_x.get(start..=end)?;
+        let first = stretch.iter().flatten().next()?;
+        let last = stretch.iter().flatten().last()?;
+        return Some((*first, *last));
+    });
     let root = plotters_cairo::CairoBackend::new(&cr, (width as u32, height as u32))
         .unwrap()
         .into_drawing_area();
     let _ = root.fill(&WHITE);
-    let areas = root.split_evenly((2, 3));
-    // Declare and initialize.
-    for (a, idx) in areas.iter().zip(1..) {
+    // Lay the graphs holding data out in rows of up to three.
+    let graphs: Vec<&GraphAttributes> = gc.graphs.iter().filter(|g| g.plotvals.len() > 0).collect();
+    if graphs.len() == 0 {
+        let _ = root.present();
+        return;
+    }
+    let cols = graphs.len().min(3);
+    let rows = graphs.len().div_ceil(cols);
+    let areas = root.split_evenly((rows, cols));
+    for (a, graph) in areas.iter().zip(graphs.iter()) {
         // After this point, we should be able to construct a chart context
-        if idx == 1 {
-            if gc.distance_pace.plotvals.len() == 0 {
-                continue;
-            };
-            build_individual_graph(
-                &gc.distance_pace.plotvals,
-                gc.distance_pace.caption.as_str(),
-                gc.distance_pace.xlabel.as_str(),
-                gc.distance_pace.ylabel.as_str(),
-                &gc.distance_pace.plot_range,
-                &gc.distance_pace.y_formatter,
-                &GREEN,
-                curr_adj,
-                a,
-            );
-        }
-        if idx == 2 {
-            if gc.distance_heart_rate.plotvals.len() == 0 {
-                continue;
-            };
-            build_individual_graph(
-                &gc.distance_heart_rate.plotvals,
-                gc.distance_heart_rate.caption.as_str(),
-                gc.distance_heart_rate.xlabel.as_str(),
-                gc.distance_heart_rate.ylabel.as_str(),
-                &gc.distance_heart_rate.plot_range,
-                &gc.distance_heart_rate.y_formatter,
-                &BLUE,
-                curr_adj,
-                a,
-            )
-        }
-        if idx == 3 {
-            if gc.distance_cadence.plotvals.len() == 0 {
-                continue;
-            };
-            build_individual_graph(
-                &gc.distance_cadence.plotvals,
-                gc.distance_cadence.caption.as_str(),
-                gc.distance_cadence.xlabel.as_str(),
-                gc.distance_cadence.ylabel.as_str(),
-                &gc.distance_cadence.plot_range,
-                &gc.distance_cadence.y_formatter,
-                &CYAN,
-                curr_adj,
-                a,
-            )
-        }
-        if idx == 4 {
-            if gc.distance_elevation.plotvals.len() == 0 {
-                continue;
-            };
-            build_individual_graph(
-                &gc.distance_elevation.plotvals,
-                gc.distance_elevation.caption.as_str(),
-                gc.distance_elevation.xlabel.as_str(),
-                gc.distance_elevation.ylabel.as_str(),
-                &gc.distance_elevation.plot_range,
-                &gc.distance_elevation.y_formatter,
-                &RED,
-                curr_adj,
-                a,
-            )
-        }
-        if idx == 5 {
-            if gc.distance_temperature.plotvals.len() == 0 {
-                continue;
-            };
-            build_individual_graph(
-                &gc.distance_temperature.plotvals,
-                gc.distance_temperature.caption.as_str(),
-                gc.distance_temperature.xlabel.as_str(),
-                gc.distance_temperature.ylabel.as_str(),
-                &gc.distance_temperature.plot_range,
-                &gc.distance_temperature.y_formatter,
-                &BROWN,
-                curr_adj,
-                a,
-            )
-        }
-        if idx == 6 {
-            break;
-        }
+        build_individual_graph(
+            graph,
+            &graph_color(&graph.field),
+            compare_gc.and_then(|c| c.graphs.iter().find(|g| g.field == graph.field)),
+            highlight,
+            curr_adj,
+            a,
+        );
     }
 
     let _ = root.present();
@@ -511,19 +1427,28 @@ fn draw_graphs(
 
 // Use plotters to actually draw a graph.
 fn build_individual_graph(
-    plotvals: &Vec<(f32, f32)>,
-    caption: &str,
-    xlabel: &str,
-    ylabel: &str,
-    plot_range: &(std::ops::Range<f32>, std::ops::Range<f32>),
-    y_formatter: &Box<dyn Fn(&f32) -> String>,
+    graph: &GraphAttributes,
     color: &RGBColor,
+    compare: Option<&GraphAttributes>,
+    highlight: Option<(f32, f32)>,
     curr_adj: &Adjustment,
     a: &plotters::drawing::DrawingArea<CairoBackend<'_>, plotters::coord::Shift>,
 ) {
+    let plotvals = &graph.plotvals;
+    let (caption, xlabel, ylabel) = (&graph.caption, &graph.xlabel, &graph.ylabel);
+    let (y_formatter, x_formatter) = (&graph.y_formatter, &graph.x_formatter);
+    // Widen the range so that a comparison activity fits on the same axes.
+    let mut plot_range = graph.plot_range.clone();
+    if let Some(cmp) = compare.filter(|c| c.plotvals.len() > 0) {
+        plot_range.0.start = plot_range.0.start.min(cmp.plot_range.0.start);
+        plot_range.0.end = plot_range.0.end.max(cmp.plot_range.0.end);
+        plot_range.1.start = plot_range.1.start.min(cmp.plot_range.1.start);
+        plot_range.1.end = plot_range.1.end.max(cmp.plot_range.1.end);
+    }
+    let plot_range = &plot_range;
     let mut chart = ChartBuilder::on(&a)
         // Set the caption of the chart
-        .caption(caption, ("sans-serif", 16).into_font())
+        .caption(caption.as_str(), ("sans-serif", 16).into_font())
         // Set the size of the label region
         .x_label_area_size(40)
         .y_label_area_size(60)
@@ -531,31 +1456,71 @@ fn build_individual_graph(
         // Finally attach a coordinate on the drawing area and make a chart context
         .build_cartesian_2d(plot_range.clone().0, plot_range.clone().1)
         .unwrap();
-    let _ = chart
-        .configure_mesh()
-        // We can customize the maximum number of labels allowed for each axis
-        .x_labels(5)
+    let mut mesh = chart.configure_mesh();
+    // We can customize the maximum number of labels allowed for each axis
+    mesh.x_labels(5)
         .y_labels(5)
         .x_desc(xlabel)
         .y_desc(ylabel)
-        .y_label_formatter(&y_formatter)
-        .draw();
+        .y_label_formatter(&y_formatter);
+    if let Some(x_formatter) = x_formatter {
+        mesh.x_label_formatter(x_formatter);
+    }
+    let _ = mesh.draw();
+    // Shade the zones, clipped to the y range shown.
+    for (zone, band) in graph.bands.iter().enumerate() {
+        let lower = band.0.max(plot_range.1.start);
+        let upper = band.1.min(plot_range.1.end);
+        if lower >= upper {
+            continue;
+        }
+        let zone_color = ZONE_COLORS[zone.min(ZONE_COLORS.len() - 1)];
+        let _ = chart.draw_series(std::iter::once(Rectangle::new(
+            [(plot_range.0.start, lower), (plot_range.0.end, upper)],
+            zone_color.mix(0.15).filled(),
+        )));
+    }
+    // Shade the highlighted stretch, e.g. a best effort, behind the series.
+    if let Some((first, last)) = highlight {
+        let _ = chart.draw_series(std::iter::once(Rectangle::new(
+            [(first, plot_range.1.start), (last, plot_range.1.end)],
+            HIGHLIGHT_COLOR.mix(0.2).filled(),
+        )));
+    }
     // // And we can draw something in the drawing area
     // We need to clone plotvals each time we make a call to LineSeries and PointSeries
+    if let Some(cmp) = compare {
+        let _ = chart.draw_series(LineSeries::new(cmp.plotvals.clone(), &COMPARE_COLOR));
+    }
+    // A derived series, e.g. grade adjusted pace, is drawn faintly below the recorded one.
+    if graph.overlay.len() > 0 {
+        let _ = chart.draw_series(LineSeries::new(graph.overlay.clone(), &OVERLAY_COLOR));
+    }
     let _ = chart.draw_series(LineSeries::new(plotvals.clone(), color));
     // Calculate the hairline.
     let idx = (curr_adj.value() * (plotvals.len() as f64 - 1.0)).trunc() as usize;
     if idx > 0 && idx < plotvals.len() - 1 {
         let hair_x = plotvals[idx].0;
         let hair_y = plotvals[idx].1;
-        let mylabel = format!(
-            "{:<1}: {:<5.2}{:<1}: {:>1}",
+        let hair_x_text = match x_formatter {
+            Some(x_formatter) => x_formatter(&hair_x),
+            None => format!("{:.2}", hair_x),
+        };
+        let mut mylabel = format!(
+            "{:<1}: {:<1} {:<1}: {:>1}",
             xlabel,
-            hair_x,
+            hair_x_text,
             ylabel,
             &y_formatter(&hair_y)
         )
         .to_string();
+        if let Some(overlay) = graph.overlay.get(idx) {
+            mylabel.push_str(&format!(
+                " {}: {}",
+                graph.overlay_label,
+                &y_formatter(&overlay.1)
+            ));
+        }
         let hair_y_min = plot_range.clone().0.start;
         let hair_y_max = plot_range.clone().1.end;
         let mut hairlinevals: Vec<(f32, f32)> = Vec::new();
@@ -590,15 +1555,120 @@ fn build_individual_graph(
 fn build_graphs(_data: &Vec<FitDataRecord>, ui: &UserInterface, gc_rc: &Rc<GraphCache>) {
     // Need to clone to use inside the closure.
     let curr_pos = ui.curr_pos_adj.clone();
+    // The comparison activity is converted with the same units and zoom.
+    let highlight = Rc::clone(&ui.highlight);
+    let comparison = ui.comparison.borrow();
+    let compare_gc = if comparison.is_empty() {
+        None
+    } else {
+        Some(instantiate_graph_cache(&comparison, &ui))
+    };
     ui.da.set_draw_func(clone!(
         #[strong]
         gc_rc,
         move |_drawing_area, cr, width, height| {
-            draw_graphs(&gc_rc, &curr_pos, cr, width as f64, height as f64);
+            draw_graphs(
+                &gc_rc,
+                compare_gc.as_ref(),
+                highlight.get(),
+                &curr_pos,
+                cr,
+                width as f64,
+                height as f64,
+            );
         }
     ));
 }
 
+// Redraw the graphs and the comparison path after a comparison activity has
+// been loaded or cleared.
+pub fn update_comparison_views(ui: &UserInterface) {
+    update_graph_views(&ui);
+    build_comparison_path(&ui);
+    ui.map.queue_draw();
+}
+
+// Rebuild the graphs of the current activity, e.g. after the graph set changed.
+pub fn update_graph_views(ui: &UserInterface) {
+    let data = ui.activity.borrow();
+    let gc_rc = Rc::new(instantiate_graph_cache(&data, &ui));
+    build_graphs(&data, &ui, &gc_rc);
+    ui.da.queue_draw();
+}
+
+// Fill the best effort menu from the current activity.  Nothing is highlighted
+// until the user picks an effort.
+pub fn populate_best_effort_menu(ui: &UserInterface) {
+    let efforts = best_efforts(&ui.activity.borrow());
+    let mut labels: Vec<String> = vec![tr("BEST_EFFORT_NONE", None)];
+    for effort in efforts.iter() {
+        labels.push(best_effort_label(&effort.name));
+    }
+    let label_strs: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+    ui.best_effort_widget.set_sensitive(efforts.len() > 0);
+    ui.best_efforts.replace(efforts);
+    ui.best_effort_list
+        .splice(0, ui.best_effort_list.n_items(), &label_strs);
+    ui.best_effort_widget.set_selected(0);
+    select_best_effort(&ui);
+}
+
+// Highlight the best effort picked in the best effort menu on the graphs and map.
+pub fn select_best_effort(ui: &UserInterface) {
+    let selected = ui.best_effort_widget.selected() as usize;
+    let highlight = match selected {
+        0 => None,
+        _ => ui
+            .best_efforts
+            .borrow()
+            .get(selected - 1)
+            .map(|e| (e.start_index, e.end_index)),
+    };
+    ui.highlight.set(highlight);
+    build_highlight_path(&ui);
+    ui.da.queue_draw();
+    ui.map.queue_draw();
+}
+
+// Fill the graphs menu with a check button for each numeric record field of
+// the current activity.  The chosen fields come first, in the order drawn.
+pub fn populate_graph_menu(ui: &Rc<UserInterface>) {
+    while let Some(child) = ui.graphs_box.first_child() {
+        ui.graphs_box.remove(&child);
+    }
+    let available = get_graph_fields(&ui.activity.borrow());
+    let chosen = ui.graph_fields.borrow().clone();
+    let mut fields: Vec<String> = chosen
+        .iter()
+        .filter(|f| available.iter().any(|a| a.0 == **f))
+        .cloned()
+        .collect();
+    for (name, _) in available.iter() {
+        if !chosen.contains(name) {
+            fields.push(name.clone());
+        }
+    }
+    for field in fields {
+        let check = CheckButton::builder()
+            .label(graph_caption(&field))
+            .active(chosen.contains(&field))
+            .build();
+        check.connect_toggled(clone!(
+            #[strong]
+            ui,
+            move |check| {
+                // Newly chosen graphs are drawn last.
+                ui.graph_fields.borrow_mut().retain(|f| *f != field);
+                if check.is_active() {
+                    ui.graph_fields.borrow_mut().push(field.clone());
+                }
+                update_graph_views(&ui);
+            }
+        ));
+        ui.graphs_box.append(&check);
+    }
+}
+
 // Update the views when supplied with data.
 fn update_map_graph_and_summary_widgets(
     ui: &UserInterface,
@@ -615,6 +1685,10 @@ fn update_map_graph_and_summary_widgets(
 // #####################################################################
 // ##################### MAP FUNCTIONS #################################
 // #####################################################################
+// Highest zoom level a track is fitted to the map at, street level.  Closer
+// up, a short track fills the map with little around it.
+const FIT_MAX_ZOOM: f64 = 17.0;
+
 // Add a marker layer to the map.
 fn add_marker_layer_to_map(map: &SimpleMap) -> Option<MarkerLayer> {
     if map.viewport().is_some() {
@@ -627,15 +1701,14 @@ fn add_marker_layer_to_map(map: &SimpleMap) -> Option<MarkerLayer> {
 }
 
 // Adds a PathLayer with a path of given coordinates to the map.
-fn add_path_layer_to_map(map: &SimpleMap) -> Option<PathLayer> {
+fn add_path_layer_to_map(map: &SimpleMap, color: &str) -> Option<PathLayer> {
     if map.viewport().is_some() {
         let viewport = map.viewport().unwrap();
         let path_layer = PathLayer::new(&viewport);
-        let result = gdk::RGBA::parse("blue");
+        let result = gdk::RGBA::parse(color);
         match result {
-            Ok(_) => {
-                let blue = gdk::RGBA::parse("blue").unwrap();
-                path_layer.set_stroke_color(Some(&blue));
+            Ok(rgba) => {
+                path_layer.set_stroke_color(Some(&rgba));
             }
             Err(_) => {}
         }
@@ -698,7 +1771,21 @@ fn update_marker_layer(
 ) {
     ui.marker_layer.as_ref().unwrap().remove_all();
     let run_path = &mc.run_path;
-    let idx = (curr_pos.value() * (run_path.len() as f64 - 1.0)).trunc() as usize;
+    if run_path.is_empty() {
+        return;
+    }
+    // The current position counts all the records; the marker shows the last
+    // track point at or before it.
+    let record = (curr_pos.value() * (mc.record_points.len() as f64 - 1.0)).trunc() as usize;
+    let idx = mc
+        .record_points
+        .iter()
+        .take(record + 1)
+        .flatten()
+        .last()
+        .copied()
+        .unwrap_or(0)
+        .min(run_path.len() - 1);
     let curr_lat = run_path[idx].0;
     let curr_lon = run_path[idx].1;
     let lat_deg = semi_to_degrees(curr_lat);
@@ -720,17 +1807,409 @@ fn update_marker_layer(
     ui.marker_layer.as_ref().unwrap().add_marker(&marker);
 }
 
+// Draw the path of the comparison activity, if any, on the map.
+fn build_comparison_path(ui: &UserInterface) {
+    let compare_path_layer = ui.compare_path_layer.as_ref().unwrap();
+    compare_path_layer.remove_all();
+    let mc = instantiate_map_cache(&ui.comparison.borrow());
+    for (lat, lon) in mc.run_path {
+        let coord = Coordinate::new_full(semi_to_degrees(lat), semi_to_degrees(lon));
+        compare_path_layer.add_node(&coord);
+    }
+}
+
+// Draw the highlighted stretch of the activity on the map.
+fn build_highlight_path(ui: &UserInterface) {
+    let highlight_path_layer = ui.highlight_path_layer.as_ref().unwrap();
+    highlight_path_layer.remove_all();
+    if let Some((start, end)) = ui.highlight.get() {
+        let mc = instantiate_map_cache(&ui.activity.borrow());
+        let points = mc.record_points.iter().take(end + 1).skip(start).flatten();
+        for (lat, lon) in points.filter_map(|point| mc.run_path.get(*point)) {
+            let coord = Coordinate::new_full(semi_to_degrees(*lat), semi_to_degrees(*lon));
+            highlight_path_layer.add_node(&coord);
+        }
+    }
+}
+
+// Return the color of a class of the colored track, from blue for the
+// first class to red for the last.
+fn track_class_color(class: usize, count: usize) -> String {
+    let hue = match count {
+        0 | 1 => 1.0 / 3.0,
+        _ => 2.0 / 3.0 * (1.0 - class as f64 / (count - 1) as f64),
+    };
+    let (r, g, b) = HSLColor(hue, 0.85, 0.45).rgb();
+    return format!("#{:02x}{:02x}{:02x}", r, g, b);
+}
+
+// Draw the track in segments colored by the metric the user chose, with a
+// legend over the map.  The single colored path is shown otherwise, and for
+// activities without the metric.
+pub fn build_track_coloring(data: &Vec<FitDataRecord>, ui: &UserInterface, mc: &MapCache) {
+    let Some(map) = ui.map.map() else {
+        return;
+    };
+    for layer in ui.track_layers.borrow_mut().drain(..) {
+        map.remove_layer(&layer);
+    }
+    while let Some(child) = ui.track_legend.first_child() {
+        ui.track_legend.remove(&child);
+    }
+    ui.track_legend.set_visible(false);
+    ui.path_layer.as_ref().unwrap().set_visible(true);
+    let (Some(viewport), Some(metric)) =
+        (ui.map.viewport(), get_track_metric(&ui.track_color_widget))
+    else {
+        return;
+    };
+    let Some(coloring) = color_track(data, metric, &ui.heart_rate_zones.borrow()) else {
+        return;
+    };
+    let count = coloring.bounds.len();
+    // A point without a value keeps the class of the point before it.
+    let mut class = coloring
+        .classes
+        .iter()
+        .flatten()
+        .next()
+        .copied()
+        .unwrap_or(0);
+    let mut segment: Option<(usize, PathLayer)> = None;
+    let mut previous: Option<Coordinate> = None;
+    for (i, (lat, lon)) in mc.run_path.iter().enumerate() {
+        if let Some(Some(k)) = coloring.classes.get(i) {
+            class = *k;
+        }
+        let coord = Coordinate::new_full(semi_to_degrees(*lat), semi_to_degrees(*lon));
+        if segment.as_ref().is_none_or(|(k, _)| *k != class) {
+            let layer = PathLayer::new(&viewport);
+            if let Ok(rgba) = gdk::RGBA::parse(track_class_color(class, count)) {
+                layer.set_stroke_color(Some(&rgba));
+            }
+            layer.set_stroke_width(3.0);
+            // Segments meet at the point where the class changes.
+            if let Some(previous) = &previous {
+                layer.add_node(previous);
+            }
+            // Below the highlighted stretch and the markers.
+            map.insert_layer_behind(&layer, ui.highlight_path_layer.as_ref());
+            ui.track_layers.borrow_mut().push(layer.clone());
+            segment = Some((class, layer));
+        }
+        segment.as_ref().unwrap().1.add_node(&coord);
+        previous = Some(coord);
+    }
+    if ui.track_layers.borrow().is_empty() {
+        return;
+    }
+    ui.path_layer.as_ref().unwrap().set_visible(false);
+    // The legend lists the last class, the fastest, highest or steepest, first.
+    let user_unit = get_unit_system(&ui.units_widget);
+    let title = Label::builder()
+        .label(selected_string(&ui.track_color_widget).unwrap_or_default())
+        .halign(gtk4::Align::Start)
+        .build();
+    ui.track_legend.append(&title);
+    for k in (0..count).rev() {
+        let line = Label::builder().halign(gtk4::Align::Start).build();
+        line.set_markup(&format!(
+            "<span foreground=\"{}\">━━</span> {}",
+            track_class_color(k, count),
+            glib::markup_escape_text(&track_legend_label(metric, &coloring.bounds, k, &user_unit))
+        ));
+        ui.track_legend.append(&line);
+    }
+    ui.track_legend.set_visible(true);
+}
+
+// Return the lap, counting from 1, a track point falls in given the point
+// at the end of each lap, or 0 for an activity without laps.
+fn lap_of_point(lap_ends: &[usize], point: usize) -> usize {
+    return lap_ends
+        .iter()
+        .position(|end| point <= *end)
+        .map(|lap| lap + 1)
+        .unwrap_or(lap_ends.len());
+}
+
+// Place a marker at the end of every lap and, when the user chose, at every
+// kilometer or mile.  Clicking a marker moves the current position there and
+// highlights the lap it falls in in the summary.
+pub fn build_lap_markers(data: &Vec<FitDataRecord>, ui: &UserInterface, mc: &MapCache) {
+    let layer = ui.lap_marker_layer.as_ref().unwrap();
+    layer.remove_all();
+    let run_path = &mc.run_path;
+    if run_path.is_empty() {
+        return;
+    }
+    let lap_ends = lap_end_points(data);
+    let mut markers: Vec<(usize, String, &str)> = Vec::new();
+    for (lap, point) in lap_ends.iter().enumerate() {
+        let mut args = FluentArgs::new();
+        args.set("lap", lap + 1);
+        markers.push((*point, tr("MAP_LAP_MARKER", Some(&args)), "lap-marker"));
+    }
+    if ui.distance_markers_check.is_active() {
+        let user_unit = get_unit_system(&ui.units_widget);
+        let unit = match user_unit {
+            Units::US => "mi",
+            _ => "km",
+        };
+        for (point, mark) in distance_mark_points(data, split_length(&user_unit)) {
+            markers.push((point, format!("{} {}", mark, unit), "distance-marker"));
+        }
+    }
+    for (point, text, css_class) in markers {
+        let point = point.min(run_path.len() - 1);
+        let (lat, lon) = run_path[point];
+        let label = Label::builder()
+            .label(text)
+            .css_classes([css_class])
+            .build();
+        label.set_cursor_from_name(Some("pointer"));
+        // The current position counts all the records, so the marker is
+        // placed by the record its track point came from.
+        let record = mc
+            .record_points
+            .iter()
+            .position(|p| *p == Some(point))
+            .unwrap_or(0);
+        let position = record as f64 / mc.record_points.len().saturating_sub(1).max(1) as f64;
+        let lap = lap_of_point(&lap_ends, point);
+        let curr_pos_adj = ui.curr_pos_adj.clone();
+        let text_view = ui.text_view.clone();
+        let click = gtk4::GestureClick::new();
+        click.connect_released(move |_, _, _, _| {
+            curr_pos_adj.set_value(position);
+            highlight_lap_summary(&text_view, lap);
+        });
+        label.add_controller(click);
+        let marker = Marker::builder()
+            .latitude(semi_to_degrees(lat))
+            .longitude(semi_to_degrees(lon))
+            .child(&label)
+            .build();
+        layer.add_marker(&marker);
+    }
+}
+
+// Set the source of the map tiles to the chosen online map, through the tile
+// cache.
+pub fn set_up_online_map_source(ui: &UserInterface) {
+    // A source that has gone, e.g. from an older libshumate, falls back to
+    // the default.
+    let registry = map_source_registry(&ui.custom_map_sources.borrow());
+    if let Some(online) = registry
+        .by_id(&selected_map_source_id(ui))
+        .or_else(|| registry.by_id(DEFAULT_MAP_SOURCE))
+    {
+        match url_template(&online) {
+            Some(url) => {
+                let id = online.id().unwrap_or_default();
+                let source = TileSource::from_url(&id, &url, cache_dir_path(TILECACHEDIR));
+                source.set_offline(ui.offline_check.is_active());
+                ui.map
+                    .set_map_source(Some(&cached_map_source(&online, &source)));
+                ui.tile_source.replace(Some(source));
+            }
+            None => {
+                ui.tile_source.replace(None);
+                ui.map.set_map_source(Some(&online));
+            }
+        }
+    }
+    update_map_menu(ui);
+}
+
+// Set the source of the map tiles: the MBTiles file if one is chosen, else
+// the online map.  The file is opened off the main thread.  An MBTiles file
+// that can not be read is dropped for the online map and the error returned.
+pub async fn set_up_map_source(ui: &UserInterface) -> Result<(), Box<dyn Error>> {
+    let mbtiles_path = ui.mbtiles_path.borrow().clone();
+    if mbtiles_path.is_empty() {
+        set_up_online_map_source(ui);
+        return Ok(());
+    }
+    let path = mbtiles_path.clone();
+    let opened = gio::spawn_blocking(move || {
+        return MbTiles::open(Path::new(&path)).map_err(|e| e.to_string());
+    })
+    .await
+    .unwrap_or_else(|_| Err(String::from("The MBTiles file could not be opened.")));
+    // Another map source may have been chosen in the meantime.
+    if *ui.mbtiles_path.borrow() != mbtiles_path {
+        return Ok(());
+    }
+    match opened {
+        Ok(mbtiles) => {
+            ui.tile_source.replace(None);
+            ui.map
+                .set_map_source(Some(&mbtiles_map_source(Arc::new(mbtiles))));
+            update_map_menu(ui);
+            return Ok(());
+        }
+        Err(e) => {
+            ui.mbtiles_path.replace(String::new());
+            set_up_online_map_source(ui);
+            return Err(e.into());
+        }
+    }
+}
+
+// Return whether the online map is one of the sources added by the user.
+fn is_custom_map_source(ui: &UserInterface) -> bool {
+    let id = selected_map_source_id(ui);
+    return ui
+        .custom_map_sources
+        .borrow()
+        .iter()
+        .any(|source| source.id == id);
+}
+
+// Return whether the tiles of the map source may be downloaded ahead of time.
+// The OpenStreetMap tile usage policy forbids bulk downloads, as do those of
+// the other servers built into libshumate, so only the sources added by the
+// user, e.g. a tile server on localhost, are downloaded ahead of time.
+pub fn prefetch_allowed(ui: &UserInterface) -> bool {
+    return ui.tile_source.borrow().is_some() && is_custom_map_source(ui);
+}
+
+// Enable the map menu items that apply to the map source and loaded activity.
+pub fn update_map_menu(ui: &UserInterface) {
+    let cached = ui.tile_source.borrow().is_some();
+    ui.offline_check.set_sensitive(cached);
+    // There is nothing to download for an activity without positions.
+    let has_path = !ui.activity.borrow().is_empty() && !ui.no_position_label.is_visible();
+    ui.prefetch_btn
+        .set_sensitive(prefetch_allowed(ui) && !ui.offline_check.is_active() && has_path);
+    ui.online_map_btn
+        .set_sensitive(!ui.mbtiles_path.borrow().is_empty());
+    ui.remove_map_source_btn
+        .set_sensitive(is_custom_map_source(ui));
+}
+
+// Fill the map source widget with the sources libshumate knows about and
+// those added by the user, and select one of them by id.
+pub fn populate_map_source_menu(ui: &UserInterface, selected_id: &str) {
+    let registry = map_source_registry(&ui.custom_map_sources.borrow());
+    let mut ids: Vec<String> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for i in 0..registry.n_items() {
+        if let Some(source) = registry.item(i).and_downcast::<libshumate::MapSource>() {
+            let id = source.id().unwrap_or_default().to_string();
+            let name = source.name().map(|n| n.to_string()).unwrap_or(id.clone());
+            ids.push(id);
+            names.push(name);
+        }
+    }
+    let selected = ids
+        .iter()
+        .position(|id| id == selected_id)
+        .or_else(|| ids.iter().position(|id| id == DEFAULT_MAP_SOURCE))
+        .unwrap_or(0);
+    ui.map_source_ids.replace(ids);
+    let name_strs: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+    ui.map_source_list
+        .splice(0, ui.map_source_list.n_items(), &name_strs);
+    ui.map_source_widget.set_selected(selected as u32);
+}
+
+// Return the id of the map source selected in the map source widget.
+pub fn selected_map_source_id(ui: &UserInterface) -> String {
+    return ui
+        .map_source_ids
+        .borrow()
+        .get(ui.map_source_widget.selected() as usize)
+        .cloned()
+        .unwrap_or(String::from(DEFAULT_MAP_SOURCE));
+}
+
+// Create and present the window adding a map source given by an XYZ URL
+// template, such as a tile server on localhost.
+pub fn show_map_source_window(ui: &Rc<UserInterface>) {
+    let window = gtk4::Window::builder()
+        .transient_for(&ui.win)
+        .modal(true)
+        .title(tr("MAP_SOURCE_WINDOW_TITLE", None))
+        .build();
+    let name_entry = gtk4::Entry::new();
+    let url_entry = gtk4::Entry::builder()
+        .placeholder_text("http://localhost:8080/{z}/{x}/{y}.png")
+        .width_chars(40)
+        .tooltip_text(tr("TOOLTIP_MAP_SOURCE_URL", None))
+        .build();
+    let attribution_entry = gtk4::Entry::new();
+    let min_zoom_spin = SpinButton::with_range(0.0, 22.0, 1.0);
+    let max_zoom_spin = SpinButton::with_range(0.0, 22.0, 1.0);
+    max_zoom_spin.set_value(19.0);
+    let message_label = Label::builder().wrap(true).build();
+    let add_btn = Button::with_label(&tr("MAP_SOURCE_ADD_BUTTON", None));
+    let grid = gtk4::Grid::builder()
+        .row_spacing(5)
+        .column_spacing(10)
+        .margin_top(10)
+        .margin_bottom(10)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    let rows: [(&str, &gtk4::Widget); 5] = [
+        ("MAP_SOURCE_NAME", name_entry.upcast_ref()),
+        ("MAP_SOURCE_URL", url_entry.upcast_ref()),
+        ("MAP_SOURCE_ATTRIBUTION", attribution_entry.upcast_ref()),
+        ("MAP_SOURCE_MIN_ZOOM", min_zoom_spin.upcast_ref()),
+        ("MAP_SOURCE_MAX_ZOOM", max_zoom_spin.upcast_ref()),
+    ];
+    for (row, (key, widget)) in rows.iter().enumerate() {
+        let label = Label::builder()
+            .label(tr(key, None))
+            .halign(gtk4::Align::Start)
+            .build();
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    grid.attach(&message_label, 0, 5, 2, 1);
+    grid.attach(&add_btn, 1, 6, 1, 1);
+    add_btn.connect_clicked(clone!(
+        #[strong]
+        ui,
+        #[weak]
+        window,
+        move |_| {
+            let name = name_entry.text().trim().to_string();
+            let url_template = url_entry.text().trim().to_string();
+            if name.is_empty() || !is_url_template(&url_template) {
+                message_label.set_text(&tr("MESSAGE_BAD_MAP_SOURCE", None));
+                return;
+            }
+            let min_zoom = min_zoom_spin.value_as_int() as u32;
+            let max_zoom = max_zoom_spin.value_as_int() as u32;
+            let source = CustomMapSource {
+                id: new_map_source_id(&name, &url_template, &ui.map_source_ids.borrow()),
+                name,
+                url_template,
+                attribution: attribution_entry.text().trim().to_string(),
+                min_zoom: min_zoom.min(max_zoom),
+                max_zoom: max_zoom.max(min_zoom),
+            };
+            let id = source.id.clone();
+            ui.custom_map_sources.borrow_mut().push(source);
+            ui.mbtiles_path.replace(String::new());
+            // Selecting the source sets up the map.
+            populate_map_source_menu(&ui, &id);
+            window.close();
+        }
+    ));
+    window.set_child(Some(&grid));
+    window.present();
+}
+
 // Build the map.
 fn build_map(data: &Vec<FitDataRecord>, ui: &UserInterface, mc_rc: &Rc<MapCache>) {
-    if libshumate::MapSourceRegistry::with_defaults()
-        .by_id("osm-mapnik")
-        .is_some()
-    {
+    // An activity without positions, e.g. on a treadmill, has no map.
+    ui.no_position_label.set_visible(mc_rc.run_path.is_empty());
+    if ui.map.map_source().is_some() {
         let mc = &**mc_rc;
-        let source = libshumate::MapSourceRegistry::with_defaults()
-            .by_id("osm-mapnik")
-            .unwrap();
-        ui.map.set_map_source(Some(&source));
         // Get values from fit file.
         let run_path = &mc.run_path;
         ui.path_layer.as_ref().unwrap().remove_all();
@@ -738,6 +2217,7 @@ fn build_map(data: &Vec<FitDataRecord>, ui: &UserInterface, mc_rc: &Rc<MapCache>
             let coord = Coordinate::new_full(semi_to_degrees(lat), semi_to_degrees(lon));
             ui.path_layer.as_ref().unwrap().add_node(&coord);
         }
+        build_comparison_path(&ui);
         ui.map.add_overlay_layer(ui.path_layer.as_ref().unwrap());
         // add pins for the starting and stopping points of the run
         ui.startstop_layer.as_ref().unwrap().remove_all();
@@ -776,332 +2256,149 @@ fn build_map(data: &Vec<FitDataRecord>, ui: &UserInterface, mc_rc: &Rc<MapCache>
                 .unwrap()
                 .add_marker(&stop_marker);
         }
+        build_track_coloring(data, ui, mc);
+        build_lap_markers(data, ui, mc);
         ui.map
             .add_overlay_layer(ui.startstop_layer.as_ref().unwrap());
         // Add a layer for indication of current position (aka the runner).
         ui.marker_layer.as_ref().unwrap().remove_all();
         ui.map.add_overlay_layer(ui.marker_layer.as_ref().unwrap());
-        // You may want to set an initial center and zoom level.
-        if ui.map.viewport().is_some() {
-            let viewport = ui.map.viewport().unwrap();
-            let nec_lat = get_sess_record_field(&data, "nec_lat");
-            let nec_long = get_sess_record_field(&data, "nec_long");
-            let swc_lat = get_sess_record_field(&data, "swc_lat");
-            let swc_long = get_sess_record_field(&data, "swc_long");
-            if !nec_lat.is_nan() & !nec_long.is_nan() & !swc_lat.is_nan() & !swc_long.is_nan() {
-                let center_lat =
-                    (semi_to_degrees(nec_lat as f32) + semi_to_degrees(swc_lat as f32)) / 2.0;
-                let center_long =
-                    (semi_to_degrees(nec_long as f32) + semi_to_degrees(swc_long as f32)) / 2.0;
-                viewport.set_location(center_lat, center_long);
-            } else {
-                viewport.set_location(29.7601, -95.3701); // e.g. Houston, USA
-            }
-            viewport.set_zoom_level(14.0);
-        }
+        fit_map_to_path(ui, run_path);
     }
 }
-// Return a language specific string for the field name identifier.
-fn pretty_field(fld: &FitDataField) -> String {
-    match fld.name() {
-        "start_position_lat" => return tr("PRETTY_START_POSITION_LAT", None),
-        "start_position_long" => return tr("PRETTY_START_POSITION_LONG", None),
-        "end_position_lat" => return tr("PRETTY_END_POSITION_LAT", None),
-        "end_position_long" => return tr("PRETTY_END_POSITION_LONG", None),
-        "total_strides" => return tr("PRETTY_TOTAL_STRIDES", None),
-        "total_calories" => return tr("PRETTY_TOTAL_CALORIES", None),
-        "avg_heart_rate" => return tr("PRETTY_AVG_HEART_RATE", None),
-        "max_heart_rate" => return tr("PRETTY_MAX_HEART_RATE", None),
-        "avg_running_cadence" => return tr("PRETTY_AVG_RUNNING_CADENCE", None),
-        "max_running_cadence" => return tr("PRETTY_MAX_RUNNING_CADENCE", None),
-        "total_training_effect" => return tr("PRETTY_TOTAL_TRAINING_EFFECT", None),
-        "first_lap_index" => return tr("PRETTY_FIRST_LAP_INDEX", None),
-        "num_laps" => return tr("PRETTY_NUM_LAPS", None),
-        "avg_fractional_cadence" => return tr("PRETTY_AVG_FRACTIONAL_CADENCE", None),
-        "max_fractional_cadence" => return tr("PRETTY_MAX_FRACTIONAL_CADENCE", None),
-        "total_anaerobic_training_effect" => {
-            return tr("PRETTY_TOTAL_ANAEROBIC_TRAINING_EFFECT", None);
-        }
-        "sport" => return tr("PRETTY_SPORT", None),
-        "sub_sport" => return tr("PRETTY_SUB_SPORT", None),
-        "timestamp" => return tr("PRETTY_TIMESTAMP", None),
-        "start_time" => return tr("PRETTY_START_TIME", None),
-        "total_ascent" => return tr("PRETTY_TOTAL_ASCENT", None),
-        "total_descent" => return tr("PRETTY_TOTAL_DESCENT", None),
-        "total_distance" => return tr("PRETTY_TOTAL_DISTANCE", None),
-        "total_elapsed_time" => return tr("PRETTY_TOTAL_ELAPSED_TIME", None),
-        "total_timer_time" => return tr("PRETTY_TOTAL_TIMER_TIME", None),
-        "enhanced_avg_speed" => return tr("PRETTY_ENHANCED_AVG_SPEED", None),
-        "enhanced_max_speed" => return tr("PRETTY_ENHANCED_MAX_SPEED", None),
-        "min_temperature" => return tr("PRETTY_MIN_TEMPERATURE", None),
-        "max_temperature" => return tr("PRETTY_MAX_TEMPERATURE", None),
-        "avg_temperature" => return tr("PRETTY_AVG_TEMPERATURE", None),
-        _ => return "".to_string(),
+
+// Center the map on a path and zoom in as far as the whole path fits.
+fn fit_map_to_path(ui: &UserInterface, run_path: &Vec<(f64, f64)>) {
+    let (Some(viewport), Some(source), Some(bounds)) = (
+        ui.map.viewport(),
+        ui.map.map_source(),
+        path_bounds(run_path),
+    ) else {
+        return;
+    };
+    // The map is not laid out yet when the first activity is opened, the
+    // pane positions give the size it will have.
+    let (mut width, mut height) = (ui.map.width(), ui.map.height());
+    if width <= 0 || height <= 0 {
+        width = ui.main_pane.position();
+        height = ui.left_frame_pane.position();
     }
+    let max_zoom = (source.max_zoom_level() as f64).min(FIT_MAX_ZOOM);
+    let (latitude, longitude, zoom) = fit_bounds(
+        &bounds,
+        width as f64,
+        height as f64,
+        source.tile_size() as f64,
+        max_zoom,
+    );
+    viewport.set_location(latitude, longitude);
+    viewport.set_zoom_level(zoom.max(source.min_zoom_level() as f64));
 }
 // #####################################################################
 // ##################### SUMMARY FUNCTIONS #############################
 // #####################################################################
-// Convert a value to user-defined units and return a formatted string when supplied a field and units.
-fn format_string_for_field(fld: &FitDataField, user_unit: &Units) -> Option<String> {
-    match fld.name() {
-        "start_position_lat" | "start_position_long" | "end_position_lat" | "end_position_long" => {
-            let result: Result<i64, _> = fld.value().try_into();
-            match result {
-                Ok(semi) => {
-                    let degrees = semi_to_degrees(semi as f32);
-                    return Some(format!("{:<30}: {degrees:<6.3}°\n", pretty_field(fld)));
-                }
-                Err(_) => return None,
-            }
-        }
+// Name of the summary text tag marking the lap chosen on the map.
+const LAP_HIGHLIGHT_TAG: &str = "lap-highlight";
 
-        "total_strides"
-        | "total_calories"
-        | "avg_heart_rate"
-        | "max_heart_rate"
-        | "avg_running_cadence"
-        | "max_running_cadence"
-        | "total_training_effect"
-        | "first_lap_index"
-        | "num_laps"
-        | "avg_fractional_cadence"
-        | "max_fractional_cadence"
-        | "total_anaerobic_training_effect"
-        | "sport"
-        | "sub_sport"
-        | "timestamp"
-        | "start_time" => {
-            return Some(format!(
-                "{:<30}: {:<#} {:<}\n",
-                pretty_field(fld),
-                fld.value(),
-                fld.units()
-            ));
-        }
-        "total_ascent" | "total_descent" => {
-            let result: Result<f64, _> = fld.value().clone().try_into();
-            match result {
-                Ok(val) => {
-                    let val_cvt = cvt_altitude(val as f32, &user_unit);
-                    match user_unit {
-                        Units::US => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                tr("UNIT_FEET", None),
-                            ));
-                        }
-                        Units::Metric => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                tr("UNIT_METERS", None),
-                            ));
-                        }
-                        Units::None => {
-                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
-                        }
-                    }
-                }
-                Err(_) => return None,
-            }
-        }
-        "total_distance" => {
-            let result: Result<f64, _> = fld.value().clone().try_into();
-            match result {
-                Ok(val) => {
-                    let val_cvt = cvt_distance(val as f32, &user_unit);
-                    match user_unit {
-                        Units::US => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                tr("UNIT_MILES", None),
-                            ));
-                        }
-                        Units::Metric => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                tr("UNIT_KM", None),
-                            ));
-                        }
-                        Units::None => {
-                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
-                        }
-                    }
-                }
-                Err(_) => return None,
-            }
-        }
-        "total_elapsed_time" | "total_timer_time" => {
-            let result: Result<f64, _> = fld.value().clone().try_into();
-            match result {
-                Ok(val) => {
-                    let val_cvt = cvt_elapsed_time(val as f32);
-                    return Some(format!(
-                        "{:<30}: {:01}h:{:02}m:{:02}s\n",
-                        pretty_field(fld),
-                        val_cvt.0,
-                        val_cvt.1,
-                        val_cvt.2
-                    ));
-                }
-                Err(_) => return None,
-            }
-        }
-        "min_temperature" | "max_temperature" | "avg_temperature" => {
-            let result: Result<i64, _> = fld.value().clone().try_into();
-            match result {
-                Ok(val) => {
-                    let val_cvt = cvt_temperature(val as f32, &user_unit);
-                    match user_unit {
-                        Units::US => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                "°F"
-                            ));
-                        }
-                        Units::Metric => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                "°C"
-                            ));
-                        }
-                        Units::None => {
-                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
-                        }
-                    }
-                }
-                Err(_) => return None,
-            }
-        }
-        "enhanced_avg_speed" | "enhanced_max_speed" => {
-            let result: Result<f64, _> = fld.value().clone().try_into();
-            match result {
-                Ok(val) => {
-                    let val_cvt = cvt_pace(val as f32, &user_unit);
-                    match user_unit {
-                        Units::US => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                tr("UNIT_PACE_US", None),
-                            ));
-                        }
-                        Units::Metric => {
-                            return Some(format!(
-                                "{:<30}: {:<.2} {:<}\n",
-                                pretty_field(fld),
-                                val_cvt,
-                                tr("UNIT_PACE_METRIC", None),
-                            ));
-                        }
-                        Units::None => {
-                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
-                        }
-                    }
-                }
-                Err(_) => return None,
-            }
-        }
-        _ => return None, // matches other patterns
-    }
+// Highlight the block of a lap, counting from 1, in the summary and scroll
+// it into view.  The block runs from its header to the blank line after its
+// fields.
+fn highlight_lap_summary(text_view: &TextView, lap_index: usize) {
+    let buffer = text_view.buffer();
+    let (start, end) = buffer.bounds();
+    buffer.remove_tag_by_name(LAP_HIGHLIGHT_TAG, &start, &end);
+    let Some((mut block_start, header_end)) = start.forward_search(
+        &lap_header(lap_index),
+        gtk4::TextSearchFlags::TEXT_ONLY,
+        None,
+    ) else {
+        return;
+    };
+    let block_end = match header_end.forward_search("\n\n", gtk4::TextSearchFlags::TEXT_ONLY, None)
+    {
+        Some((blank_line, _)) => blank_line,
+        None => end,
+    };
+    buffer.apply_tag_by_name(LAP_HIGHLIGHT_TAG, &block_start, &block_end);
+    text_view.scroll_to_iter(&mut block_start, 0.0, true, 0.0, 0.0);
 }
 
 // Build a summary.
 fn build_summary(data: &Vec<FitDataRecord>, ui: &UserInterface) {
     // Get the enumerated value for the unit system the user selected.
     let user_unit = get_unit_system(&ui.units_widget);
-    ui.text_buffer.set_text(&tr("SUMMARY_FILE_LOADED", None));
-    // Clear out anything in the buffer.
-    let mut start = ui.text_buffer.start_iter();
-    let mut end = ui.text_buffer.end_iter();
-    ui.text_buffer.delete(&mut start, &mut end);
-    let mut lap_index: u8 = 0;
-    let mut lap_str: String;
-    for item in data {
-        match item.kind() {
-            MesgNum::Session | MesgNum::Lap => {
-                // print all the data records in FIT file
-                if item.kind() == MesgNum::Session {
-                    ui.text_buffer.insert(&mut end, "\n");
-                    ui.text_buffer
-                        .insert(&mut end, &tr("SUMMARY_SESSION_HEADER", None));
-                    ui.text_buffer.insert(&mut end, "\n");
-                }
-                if item.kind() == MesgNum::Lap {
-                    lap_index = lap_index + 1;
-                    let lap_name = &tr("SUMMARY_LAP_HEADER", None);
-                    lap_str = format!(
-                        "------------------------------ {} {}-----------------------------------\n",
-                        lap_name, lap_index
-                    );
-                    ui.text_buffer.insert(&mut end, "\n");
-                    ui.text_buffer.insert(&mut end, &lap_str);
-                    ui.text_buffer.insert(&mut end, "\n");
-                }
-                // Retrieve the FitDataField struct.
-                for fld in item.fields().iter() {
-                    let value_str = format_string_for_field(fld, &user_unit);
-                    if value_str.is_some() {
-                        ui.text_buffer.insert(&mut end, &value_str.unwrap());
-                    }
-                }
-            }
-            _ => print!("{}", ""), // matches other patterns
+    ui.text_buffer.set_text(&build_summary_text(
+        data,
+        &user_unit,
+        &ui.heart_rate_zones.borrow(),
+        &ui.pace_zones.borrow(),
+    ));
+}
+
+// #####################################################################
+// ##################### CACHE FUNCTIONS ###############################
+// #####################################################################
+// Return the lower and upper heart rate of every heart rate zone of an
+// activity, or nothing for an activity without heart rate.
+fn heart_rate_bands(d: &Vec<FitDataRecord>, hr_zones: &HeartRateZones) -> Vec<(f32, f32)> {
+    let Some((zone_times, zone_limits)) = heart_rate_time_in_zone(d, hr_zones) else {
+        return Vec::new();
+    };
+    let top_heart_rate = heart_rate_zone_top(d, hr_zones);
+    return (0..zone_times.len())
+        .map(|z| hr_zone_bounds(&zone_limits, z, top_heart_rate))
+        .map(|(ll, ul)| (ll as f32, ul as f32))
+        .collect();
+}
+
+// Return the caption of a graph of a record field.  Fields without a
+// translation are captioned with their FIT name.
+pub fn graph_caption(field: &str) -> String {
+    match field {
+        "enhanced_speed" => return tr("GRAPH_CAPTION_PACE", None),
+        "heart_rate" => return tr("GRAPH_CAPTION_HR", None),
+        "cadence" => return tr("GRAPH_CAPTION_CADENCE", None),
+        "enhanced_altitude" => return tr("GRAPH_CAPTION_ELEVATION", None),
+        "temperature" => return tr("GRAPH_CAPTION_TEMP", None),
+        "power" => return tr("GRAPH_CAPTION_POWER", None),
+        "vertical_oscillation" => return tr("GRAPH_CAPTION_VERTICAL_OSCILLATION", None),
+        "stance_time" => return tr("GRAPH_CAPTION_STANCE_TIME", None),
+        "step_length" => return tr("GRAPH_CAPTION_STEP_LENGTH", None),
+        "respiration_rate" | "enhanced_respiration_rate" => {
+            return tr("GRAPH_CAPTION_RESPIRATION_RATE", None);
         }
+        "grade" => return tr("GRAPH_CAPTION_GRADE", None),
+        "efficiency_factor" => return tr("GRAPH_CAPTION_EFFICIENCY_FACTOR", None),
+        _ => return field.replace('_', " "),
     }
-    if let (Some(zone_times), Some(zone_limits)) = get_time_in_zone_field(data) {
-        // There are 7 zones but only 6 upper limits.
-        ui.text_buffer.insert(&mut end, "\n");
-        ui.text_buffer
-            .insert(&mut end, &tr("SUMMARY_HR_ZONE_HEADER", None));
-        ui.text_buffer.insert(&mut end, "\n");
-        for (z, val) in zone_times.iter().enumerate() {
-            let val_cvt = cvt_elapsed_time(*val as f32);
-            let ll: f64;
-            let ul: f64;
-            if z == 0 {
-                ll = 0.0;
-                ul = zone_limits[z];
-            } else if z < zone_limits.len() && z > 0 {
-                ll = zone_limits[z - 1];
-                ul = zone_limits[z];
-            } else {
-                ll = zone_limits[z - 1];
-                ul = 220.0;
+}
+
+// Return the y axis label of a graph of a record field.  Values get_xy does
+// not convert are labelled with the units recorded in the file.
+fn graph_ylabel(field: &str, file_units: &str, user_unit: &Units) -> String {
+    if let Units::None = user_unit {
+        return "".to_string();
+    }
+    match (field, user_unit) {
+        ("enhanced_speed", Units::US) => return tr("LABEL_PACE_US", None),
+        ("enhanced_speed", _) => return tr("LABEL_PACE_METRIC", None),
+        ("heart_rate", _) => return tr("LABEL_HR_BPM", None),
+        ("cadence", _) => return tr("LABEL_CADENCE", None),
+        ("enhanced_altitude", Units::US) => return tr("LABEL_ELEVATION_FT", None),
+        ("enhanced_altitude", _) => return tr("LABEL_ELEVATION_M", None),
+        ("temperature", Units::US) => return tr("LABEL_TEMP_F", None),
+        ("temperature", _) => return tr("LABEL_TEMP_C", None),
+        _ => {
+            if file_units.is_empty() {
+                return graph_caption(field);
             }
-            let value_str = format!(
-                "{:<5}{:<} ({:>3}-{:>3} bpm): {:01}h:{:02}m:{:02}s\n",
-                tr("SUMMARY_HR_ZONE_LABEL", None),
-                z,
-                ll as i32,
-                ul as i32,
-                val_cvt.0,
-                val_cvt.1,
-                val_cvt.2
-            );
-            ui.text_buffer.insert(&mut end, &value_str);
+            return format!("{} ({})", graph_caption(field), file_units);
         }
-        ui.text_buffer.insert(&mut end, "\n");
-    };
+    }
 }
 
-// #####################################################################
-// ##################### CACHE FUNCTIONS ###############################
-// #####################################################################
 // Calculate a cache of the graph attributes (see GraphAtributes) a *SINGLE* time for display.
+// One graph is built for each field the user chose that the records contain.
 pub fn instantiate_graph_cache(d: &Vec<FitDataRecord>, ui: &UserInterface) -> GraphCache {
     let user_unit = get_unit_system(&ui.units_widget);
 
@@ -1113,156 +2410,131 @@ pub fn instantiate_graph_cache(d: &Vec<FitDataRecord>, ui: &UserInterface) -> Gr
         let secs = x.fract() * 60.0;
         format!("{:02.0}:{:02.0}", mins, secs)
     };
-    // let mut xlabel: &str;
-    let mut xlabel: String;
-    let mut ylabel: String;
-    // distance_pace
-    let xy = get_xy(&d, &ui.units_widget, "distance", "enhanced_speed");
-    let range = set_plot_range(&xy, zoom_x, zoom_y);
-    match user_unit {
-        Units::US => {
-            ylabel = tr("LABEL_PACE_US", None);
-            xlabel = tr("LABEL_DISTANCE_MILES", None);
-        }
-        Units::Metric => {
-            ylabel = tr("LABEL_PACE_METRIC", None);
-            xlabel = tr("LABEL_DISTANCE_KM", None);
-        }
-        Units::None => {
-            ylabel = "".to_string();
-            xlabel = "".to_string();
-        }
-    }
-    let distance_pace = GraphAttributes {
-        plotvals: (xy),
-        caption: tr("GRAPH_CAPTION_PACE", None),
-        xlabel: (String::from(xlabel)),
-        ylabel: (String::from(ylabel)),
-        plot_range: (range),
-        y_formatter: (Box::new(pace_formatter)),
-        // color: (&RED),
+    // Format seconds as h:mm:ss; clock time wraps at midnight.
+    let elapsed_formatter = |x: &f32| {
+        let (hr, min, sec) = cvt_elapsed_time(x.max(0.0));
+        format!("{}:{:02}:{:02}", hr, min, sec)
     };
-    // distance_heart_rate
-    let xy = get_xy(&d, &ui.units_widget, "distance", "heart_rate");
-    let range = set_plot_range(&xy.clone(), zoom_x, zoom_y);
-    match user_unit {
-        Units::US => {
-            ylabel = tr("LABEL_HR_BPM", None);
-            xlabel = tr("LABEL_DISTANCE_MILES", None);
-        }
-        Units::Metric => {
-            ylabel = tr("LABEL_HR_BPM", None);
-            xlabel = tr("LABEL_DISTANCE_KM", None);
-        }
-        Units::None => {
-            ylabel = "".to_string();
-            xlabel = "".to_string();
-        }
-    }
-    let distance_heart_rate = GraphAttributes {
-        plotvals: (xy),
-        caption: tr("GRAPH_CAPTION_HR", None),
-        xlabel: (String::from(xlabel)),
-        ylabel: (String::from(ylabel)),
-        plot_range: (range),
-        y_formatter: (Box::new(num_formatter)),
-        // color: (&BLUE),
+    let clock_formatter = |x: &f32| {
+        let (hr, min, sec) = cvt_elapsed_time(x.max(0.0) % 86400.0);
+        format!("{}:{:02}:{:02}", hr, min, sec)
     };
-    // distance-cadence
-    let xy = get_xy(&d, &ui.units_widget, "distance", "cadence");
-    let range = set_plot_range(&xy.clone(), zoom_x, zoom_y);
-    match user_unit {
-        Units::US => {
-            ylabel = tr("LABEL_CADENCE", None);
-            xlabel = tr("LABEL_DISTANCE_MILES", None);
-        }
-        Units::Metric => {
-            ylabel = tr("LABEL_CADENCE", None);
-            xlabel = tr("LABEL_DISTANCE_KM", None);
-        }
-        Units::None => {
-            ylabel = "".to_string();
-            xlabel = "".to_string();
-        }
-    }
-    let distance_cadence = GraphAttributes {
-        plotvals: (xy),
-        caption: tr("GRAPH_CAPTION_CADENCE", None),
-        xlabel: (String::from(xlabel)),
-        ylabel: (String::from(ylabel)),
-        plot_range: (range),
-        y_formatter: (Box::new(num_formatter)),
-        // color: (&CYAN),
+    // The x axis is the same for every graph.
+    let x_field = get_x_axis_field(&ui.x_axis_widget);
+    let xlabel: String = match (x_field, user_unit) {
+        ("elapsed_time", _) => tr("LABEL_ELAPSED_TIME", None),
+        ("clock_time", _) => tr("LABEL_CLOCK_TIME", None),
+        (_, Units::US) => tr("LABEL_DISTANCE_MILES", None),
+        (_, Units::Metric) => tr("LABEL_DISTANCE_KM", None),
+        (_, Units::None) => "".to_string(),
     };
-    //distance-elevation
-    let xy = get_xy(&d, &ui.units_widget, "distance", "enhanced_altitude");
-    let range = set_plot_range(&xy.clone(), zoom_x, zoom_y);
-    match user_unit {
-        Units::US => {
-            ylabel = tr("LABEL_ELEVATION_FT", None);
-            xlabel = tr("LABEL_DISTANCE_MILES", None);
-        }
-        Units::Metric => {
-            ylabel = tr("LABEL_ELEVATION_M", None);
-            xlabel = tr("LABEL_DISTANCE_KM", None);
-        }
-        Units::None => {
-            ylabel = "".to_string();
-            xlabel = "".to_string();
+    let x_formatter = || -> Option<Box<dyn Fn(&f32) -> String>> {
+        match x_field {
+            "elapsed_time" => return Some(Box::new(elapsed_formatter)),
+            "clock_time" => return Some(Box::new(clock_formatter)),
+            _ => return None,
         }
-    }
-    let distance_elevation = GraphAttributes {
-        plotvals: (xy),
-        caption: tr("GRAPH_CAPTION_ELEVATION", None),
-        xlabel: (String::from(xlabel)),
-        ylabel: (String::from(ylabel)),
-        plot_range: (range),
-        y_formatter: (Box::new(num_formatter)),
-        // color: (&RED),
     };
-    // distance-temperature
-    let xy = get_xy(&d, &ui.units_widget, "distance", "temperature");
-    let range = set_plot_range(&xy.clone(), zoom_x, zoom_y);
-    match user_unit {
-        Units::US => {
-            ylabel = tr("LABEL_TEMP_F", None);
-            xlabel = tr("LABEL_DISTANCE_MILES", None);
-        }
-        Units::Metric => {
-            ylabel = tr("LABEL_TEMP_C", None);
-            xlabel = tr("LABEL_DISTANCE_KM", None);
-        }
-        Units::None => {
-            ylabel = "".to_string();
-            xlabel = "".to_string();
-        }
+    let available = get_graph_fields(&d);
+    let mut graphs: Vec<GraphAttributes> = Vec::new();
+    for field in ui.graph_fields.borrow().iter() {
+        let Some((_, file_units)) = available.iter().find(|f| f.0 == *field) else {
+            continue;
+        };
+        let xy = filter_series(get_xy(&d, &user_unit, x_field, field), ui);
+        let range = if is_filtered(ui) {
+            set_filtered_plot_range(&xy, zoom_x, zoom_y)
+        } else {
+            set_plot_range(&xy, zoom_x, zoom_y)
+        };
+        let y_formatter: Box<dyn Fn(&f32) -> String> = match field.as_str() {
+            "enhanced_speed" => Box::new(pace_formatter),
+            _ => Box::new(num_formatter),
+        };
+        // Grade adjusted pace is drawn over the pace graph.
+        let (overlay, overlay_label) = match field.as_str() {
+            "enhanced_speed" => (
+                filter_series(get_xy(&d, &user_unit, x_field, "grade_adjusted_speed"), ui),
+                tr("GRAPH_OVERLAY_GAP", None),
+            ),
+            _ => (Vec::new(), String::new()),
+        };
+        // The training zones are shaded behind the heart rate and pace graphs.
+        let bands: Vec<(f32, f32)> = match field.as_str() {
+            "heart_rate" => heart_rate_bands(&d, &ui.heart_rate_zones.borrow()),
+            "enhanced_speed" => pace_bands(&ui.pace_zones.borrow(), &user_unit),
+            _ => Vec::new(),
+        };
+        graphs.push(GraphAttributes {
+            field: field.clone(),
+            plotvals: (xy),
+            caption: graph_caption(field),
+            xlabel: (xlabel.clone()),
+            ylabel: graph_ylabel(field, file_units, &user_unit),
+            plot_range: (range),
+            y_formatter: (y_formatter),
+            x_formatter: (x_formatter()),
+            overlay: (overlay),
+            overlay_label: (overlay_label),
+            bands: (bands),
+        });
     }
-    let distance_temperature = GraphAttributes {
-        plotvals: (xy),
-        caption: tr("GRAPH_CAPTION_TEMP", None),
-        xlabel: (String::from(xlabel)),
-        ylabel: (String::from(ylabel)),
-        plot_range: (range),
-        y_formatter: (Box::new(num_formatter)),
-        // color: (&BROWN),
-    };
 
     let time_stamps = get_timestamps(&d);
     let gc: GraphCache = GraphCache {
-        distance_pace: distance_pace,
-        distance_heart_rate: distance_heart_rate,
-        distance_cadence: distance_cadence,
-        distance_elevation: distance_elevation,
-        distance_temperature: distance_temperature,
+        graphs: graphs,
         time_stamps: time_stamps,
+        record_x: get_record_x_values(&d, &user_unit, x_field),
     };
     return gc;
 }
 
+// Return true when the user chose to smooth or despike the graphs.
+fn is_filtered(ui: &UserInterface) -> bool {
+    return get_smoothing_filter(&ui.smoothing_widget) != SmoothingFilter::Raw
+        || ui.remove_spikes_check.is_active();
+}
+
+// Apply the spike removal and smoothing the user chose to a graphed series.
+// Spikes are removed first so that they do not spread into their neighbours.
+fn filter_series(xy: Vec<(f32, f32)>, ui: &UserInterface) -> Vec<(f32, f32)> {
+    let window = ui.smoothing_window_spin.value_as_int().max(2) as usize;
+    let mut series = xy;
+    if ui.remove_spikes_check.is_active() {
+        series = remove_spikes(&series, window);
+    }
+    return smooth(&series, get_smoothing_filter(&ui.smoothing_widget), window);
+}
+
+// Return the fastest and slowest pace of every pace zone in the units the
+// pace graph is drawn in.
+fn pace_bands(pace_zones: &PaceZones, user_unit: &Units) -> Vec<(f32, f32)> {
+    let zone_limits = pace_zones.zone_limits();
+    // The slowest zone starts at standstill, the fastest has no end.
+    let mut speeds: Vec<f32> = vec![0.0];
+    speeds.extend(zone_limits.iter().map(|l| *l as f32));
+    speeds.push(f32::INFINITY);
+    return speeds
+        .windows(2)
+        .map(|w| (cvt_pace(w[1], user_unit), cvt_pace(w[0], user_unit)))
+        .collect();
+}
+
 // Calculate a means to capture the data in run_path a *SINGLE* time.
 pub fn instantiate_map_cache(d: &Vec<FitDataRecord>) -> MapCache {
-    let units_widget = DropDown::builder().build(); // bogus value - no units required for position
-    let run_path = get_xy(&d, &units_widget, "position_lat", "position_long");
-    let mc: MapCache = MapCache { run_path: run_path };
+    // The path is made of the records with a position, so a record's point
+    // is its index among them.
+    let mut run_path: Vec<(f64, f64)> = Vec::new();
+    let record_points = get_record_positions(&d)
+        .iter()
+        .map(|position| {
+            run_path.push((*position)?);
+            return Some(run_path.len() - 1);
+        })
+        .collect();
+    let mc: MapCache = MapCache {
+        run_path: run_path,
+        record_points: record_points,
+    };
     return mc;
 }
@@ -23,37 +23,62 @@
  * - Craig S. Prevallet, December, 2025
  */
 
+mod cli;
 mod config;
-mod data;
 mod gui;
 mod i18n;
+mod summary;
+mod tile_source;
 
+use crate::cli::run_command_line;
 use crate::config::{
-    APP_ID, ARTIST1, AUTHOR, COPYRIGHT, ICON_NAME, PROGRAM_NAME, TESTER1, TESTER2, WindowConfig,
-    save_config,
+    APP_ID, ARTIST1, AUTHOR, COPYRIGHT, DEFAULT_MAP_SOURCE, ICON_NAME, PROGRAM_NAME, TESTER1,
+    TESTER2, WindowConfig, load_library, save_config, save_library,
 };
 use crate::gui::{
-    UserInterface, connect_interactive_widgets, construct_views_from_data, instantiate_graph_cache,
-    instantiate_map_cache, instantiate_ui,
+    LibraryView, UserInterface, build_lap_markers, build_track_coloring,
+    connect_interactive_widgets, construct_views_from_data, get_unit_system,
+    instantiate_graph_cache, instantiate_library_view, instantiate_map_cache, instantiate_ui,
+    library_entry_at, populate_best_effort_menu, populate_graph_menu, populate_library_view,
+    populate_map_source_menu, prefetch_allowed, select_best_effort, selected_map_source_id,
+    set_up_map_source, set_up_online_map_source, show_map_source_window, show_records_window,
+    show_training_load_window, show_volume_window, update_comparison_views, update_graph_views,
+    update_map_menu,
 };
 use crate::i18n::tr;
+use fitparser::FitDataRecord;
+use fluent::FluentArgs;
 use gtk4::glib::clone;
 use gtk4::prelude::*;
 use gtk4::{
-    Application, ButtonsType, FileChooserAction, FileChooserNative, License, MessageDialog,
-    MessageType, ResponseType,
+    Application, ButtonsType, FileChooserAction, FileChooserNative, FileFilter, License,
+    MessageDialog, MessageType, ResponseType, gio,
 };
 use semver::{BuildMetadata, Prerelease};
+use siliconsneaker2_data::csv::fit_to_csv;
+use siliconsneaker2_data::gpx::fit_to_gpx;
+use siliconsneaker2_data::json::fit_to_json;
+use siliconsneaker2_data::library::{LibraryEntry, LibraryIndex, scan_folder};
+use siliconsneaker2_data::read_activity;
+use siliconsneaker2_data::tiles::path_bounds;
 use std::error::Error;
 use std::fs::File;
 use std::io::ErrorKind;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 // Only God and I knew what this was doing when I wrote it.
 // Now only God knows.
 // Program entry point.
 fn main() {
+    // Headless subcommands (e.g. summary) are handled before GTK is touched.
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(exit_code) = run_command_line(&args) {
+        std::process::exit(exit_code);
+    }
     let app = Application::builder()
         .application_id(APP_ID)
         .flags(gtk4::gio::ApplicationFlags::HANDLES_OPEN)
@@ -175,7 +200,7 @@ fn get_file_handle_from_dialog(dialog: &FileChooserNative, ui: &UserInterface) -
 
 // Get the data, create the caches, construct the views, and connect the interactive widgets.
 fn tie_it_all_together(file: &mut File, ui: &Rc<UserInterface>) {
-    if let Ok(data) = fitparser::from_reader(file) {
+    if let Ok(data) = read_activity(file) {
         // Create a map cache.
         let map_cache = instantiate_map_cache(&data);
         // Wrap the MapCache in an Rc for shared ownership.
@@ -186,7 +211,255 @@ fn tie_it_all_together(file: &mut File, ui: &Rc<UserInterface>) {
         let gc_rc = Rc::new(graph_cache);
         construct_views_from_data(&ui, &data, &mc_rc, &gc_rc);
         connect_interactive_widgets(&ui, &data, &mc_rc, &gc_rc);
+        // Keep the records for the export buttons.
+        ui.activity.replace(data);
+        update_map_menu(&ui);
+        ui.export_menu_btn.set_sensitive(true);
+        ui.compare_btn.set_sensitive(true);
+        populate_graph_menu(&ui);
+        populate_best_effort_menu(&ui);
+        ui.graphs_menu_btn.set_sensitive(true);
+        ui.smoothing_menu_btn.set_sensitive(true);
+    }
+}
+
+// Ask the user for a destination and write the loaded activity in an export format.
+fn export_with_dialog<F>(ui: &Rc<UserInterface>, title: &str, default_name: &str, export: F)
+where
+    F: Fn(&Vec<FitDataRecord>) -> String + 'static,
+{
+    let native = FileChooserNative::new(
+        Some(title),
+        Some(&ui.win),
+        FileChooserAction::Save,
+        Some("Save"),
+        Some("Cancel"),
+    );
+    native.set_current_name(default_name);
+    native.connect_response(clone!(
+        #[strong]
+        ui,
+        move |dialog, response| {
+            if response == ResponseType::Accept
+                && let Some(path) = dialog.file().and_then(|file| file.path())
+            {
+                let contents = export(&ui.activity.borrow());
+                if let Err(e) = std::fs::write(&path, contents) {
+                    show_error_dialog(&ui.win, e.to_string());
+                }
+            }
+        }
+    ));
+    native.show();
+}
+
+// Download the map tiles covering the loaded activity so that its map can be
+// shown offline.  The progress is shown on the map menu button.
+fn prefetch_map_tiles(ui: &Rc<UserInterface>) {
+    if !prefetch_allowed(ui) {
+        return;
+    }
+    let Some(source) = ui.tile_source.borrow().clone() else {
+        return;
+    };
+    let map_cache = instantiate_map_cache(&ui.activity.borrow());
+    let Some(bounds) = path_bounds(&map_cache.run_path) else {
+        return;
+    };
+    ui.map_menu_btn.popdown();
+    ui.prefetch_btn.set_sensitive(false);
+    glib::MainContext::default().spawn_local(clone!(
+        #[strong]
+        ui,
+        async move {
+            let (failed, total) = source
+                .prefetch(&bounds, |done, total| {
+                    let mut args = FluentArgs::new();
+                    args.set("done", done);
+                    args.set("total", total);
+                    ui.map_menu_btn
+                        .set_label(&tr("MAP_PREFETCH_PROGRESS", Some(&args)));
+                })
+                .await;
+            ui.map_menu_btn.set_label(&tr("MAP_BUTTON_LABEL", None));
+            update_map_menu(&ui);
+            if failed > 0 {
+                let mut args = FluentArgs::new();
+                args.set("failed", failed);
+                args.set("total", total);
+                show_error_dialog(&ui.win, tr("MESSAGE_PREFETCH_FAILED", Some(&args)));
+            }
+        }
+    ));
+}
+
+// Open an activity chosen in the library window.
+fn open_library_entry(entry: &LibraryEntry, ui: &Rc<UserInterface>) {
+    match File::open(&entry.path) {
+        Ok(mut file) => {
+            update_window_title(&ui, &entry.path);
+            tie_it_all_together(&mut file, &ui);
+        }
+        Err(error) => match error.kind() {
+            ErrorKind::NotFound => show_error_dialog(&ui.win, tr("MESSAGE_FILE_NOT_FOUND", None)),
+            _ => show_error_dialog(&ui.win, tr("MESSAGE_PERMISSIONS", None)),
+        },
+    }
+}
+
+// Index the activity files of a folder, save the index and show it.  The
+// files are read off the main thread and the progress shown in place of the
+// folder name.
+fn rescan_library(ui: &Rc<UserInterface>, view: &Rc<LibraryView>, folder: &Path) {
+    let mut previous = load_library(Path::new(&ui.library_file));
+    // Entries of a different folder can not be reused.
+    if Path::new(&previous.folder) != folder {
+        previous = LibraryIndex::default();
+    }
+    view.choose_btn.set_sensitive(false);
+    view.rescan_btn.set_sensitive(false);
+    // Files done and total, updated by the scan.
+    let progress = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+    let timer = glib::timeout_add_local(
+        Duration::from_millis(200),
+        clone!(
+            #[strong]
+            view,
+            #[strong]
+            progress,
+            move || {
+                let mut args = FluentArgs::new();
+                args.set("done", progress.0.load(Ordering::Relaxed));
+                args.set("total", progress.1.load(Ordering::Relaxed));
+                view.folder_label
+                    .set_text(&tr("LIBRARY_SCAN_PROGRESS", Some(&args)));
+                return glib::ControlFlow::Continue;
+            }
+        ),
+    );
+    let folder = folder.to_path_buf();
+    let hr_zones = ui.heart_rate_zones.borrow().clone();
+    glib::MainContext::default().spawn_local(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        async move {
+            let scanned = gio::spawn_blocking(move || {
+                return scan_folder(&folder, &previous, &hr_zones, |done, total| {
+                    progress.0.store(done, Ordering::Relaxed);
+                    progress.1.store(total, Ordering::Relaxed);
+                });
+            })
+            .await;
+            timer.remove();
+            view.choose_btn.set_sensitive(true);
+            let library_path = Path::new(&ui.library_file);
+            let index = match scanned {
+                Ok(index) => {
+                    if let Err(e) = save_library(&index, library_path) {
+                        show_error_dialog(&view.window, e.to_string());
+                    }
+                    index
+                }
+                // A scan that failed leaves the index as it was.
+                Err(_) => load_library(library_path),
+            };
+            populate_library_view(&view, &index, get_unit_system(&ui.units_widget));
+        }
+    ));
+}
+
+// Return the library window, creating it and connecting its handlers the
+// first time it is needed.  Closing the window only hides it.
+fn library_window(ui: &Rc<UserInterface>) -> Rc<LibraryView> {
+    if let Some(view) = ui.library_view.borrow().as_ref() {
+        return Rc::clone(view);
     }
+    let view = Rc::new(instantiate_library_view(&ui));
+    view.choose_btn.connect_clicked(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        move |_| {
+            let native = FileChooserNative::new(
+                Some(&tr("LIBRARY_CHOOSE_FOLDER_BUTTON_LABEL", None)),
+                Some(&view.window),
+                FileChooserAction::SelectFolder,
+                Some("Select"),
+                Some("Cancel"),
+            );
+            native.connect_response(clone!(
+                #[strong]
+                ui,
+                #[strong]
+                view,
+                move |dialog, response| {
+                    if response == ResponseType::Accept
+                        && let Some(folder) = dialog.file().and_then(|file| file.path())
+                    {
+                        rescan_library(&ui, &view, &folder);
+                    }
+                }
+            ));
+            native.show();
+        }
+    )); // library-choose-btn-clicked
+    view.rescan_btn.connect_clicked(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        move |_| {
+            let folder = load_library(Path::new(&ui.library_file)).folder;
+            rescan_library(&ui, &view, Path::new(&folder));
+        }
+    )); // library-rescan-btn-clicked
+    view.volume_btn.connect_clicked(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        move |_| {
+            let entries = view.entries.borrow().clone();
+            show_volume_window(&view.window, entries, get_unit_system(&ui.units_widget));
+        }
+    )); // library-volume-btn-clicked
+    view.records_btn.connect_clicked(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        move |_| {
+            let entries = view.entries.borrow().clone();
+            show_records_window(&view.window, entries, get_unit_system(&ui.units_widget));
+        }
+    )); // library-records-btn-clicked
+    view.load_btn.connect_clicked(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        move |_| {
+            let entries = view.entries.borrow().clone();
+            let hr_zones = ui.heart_rate_zones.borrow().clone();
+            show_training_load_window(&view.window, entries, hr_zones);
+        }
+    )); // library-load-btn-clicked
+    view.column_view.connect_activate(clone!(
+        #[strong]
+        ui,
+        #[strong]
+        view,
+        move |_, position| {
+            if let Some(entry) = library_entry_at(&view, position) {
+                open_library_entry(&entry, &ui);
+            }
+        }
+    )); // library-row-activated
+    ui.library_view.replace(Some(Rc::clone(&view)));
+    return view;
 }
 
 // Wrapper for build_gui to handle no files from command line.
@@ -207,6 +480,16 @@ fn build_gui(app: &Application, files: &[gtk4::gio::File], _: &str) {
     let ui_rc = Rc::new(ui_original);
     let ui1 = Rc::clone(&ui_rc);
     ui_rc.win.present();
+    // An MBTiles file that has gone is dropped for the online map.
+    if !ui1.mbtiles_path.borrow().is_empty() {
+        glib::MainContext::default().spawn_local(clone!(
+            #[strong]
+            ui1,
+            async move {
+                let _ = set_up_map_source(&ui1).await;
+            }
+        ));
+    }
 
     // If the user has provided a file name on the command line - use the first file.
     if files.len() > 0 {
@@ -251,6 +534,280 @@ fn build_gui(app: &Application, files: &[gtk4::gio::File], _: &str) {
         },
     )); //button-connect-clicked
 
+    ui1.library_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            let view = library_window(&ui1);
+            let index = load_library(Path::new(&ui1.library_file));
+            populate_library_view(&view, &index, get_unit_system(&ui1.units_widget));
+            view.window.present();
+        }
+    )); // library-btn-clicked
+
+    ui1.compare_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            let native = FileChooserNative::new(
+                Some(&tr("COMPARE_BUTTON_LABEL", None)),
+                Some(&ui1.win),
+                FileChooserAction::Open,
+                Some("Open"),
+                Some("Cancel"),
+            );
+            native.connect_response(clone!(
+                #[strong]
+                ui1,
+                move |dialog, response| {
+                    if response == ResponseType::Accept
+                        && let Some(path) = dialog.file().and_then(|file| file.path())
+                    {
+                        match File::open(&path).map(|mut file| read_activity(&mut file)) {
+                            Ok(Ok(data)) => {
+                                ui1.comparison.replace(data);
+                                let mut args = FluentArgs::new();
+                                args.set("file", path.to_string_lossy().to_string());
+                                ui1.compare_clear_btn.set_tooltip_text(Some(&tr(
+                                    "TOOLTIP_COMPARE_LOADED",
+                                    Some(&args),
+                                )));
+                                ui1.compare_clear_btn.set_sensitive(true);
+                                update_comparison_views(&ui1);
+                            }
+                            Ok(Err(_)) => {
+                                show_error_dialog(&ui1.win, tr("MESSAGE_NOT_A_FIT_FILE", None))
+                            }
+                            Err(_) => show_error_dialog(&ui1.win, tr("MESSAGE_PERMISSIONS", None)),
+                        }
+                    }
+                }
+            ));
+            native.show();
+        }
+    )); // compare-btn-clicked
+
+    ui1.compare_clear_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.comparison.replace(Vec::new());
+            ui1.compare_clear_btn
+                .set_tooltip_text(Some(&tr("TOOLTIP_COMPARE_CLEAR_BUTTON", None)));
+            ui1.compare_clear_btn.set_sensitive(false);
+            update_comparison_views(&ui1);
+        }
+    )); // compare-clear-btn-clicked
+
+    ui1.best_effort_widget.connect_selected_notify(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            select_best_effort(&ui1);
+        }
+    )); // best-effort-selected
+
+    // Redraw the graphs whenever the smoothing changes.
+    ui1.smoothing_widget.connect_selected_notify(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            update_graph_views(&ui1);
+        }
+    )); // smoothing-selected
+    ui1.smoothing_window_spin.connect_value_changed(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            update_graph_views(&ui1);
+        }
+    )); // smoothing-window-changed
+    ui1.remove_spikes_check.connect_toggled(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            update_graph_views(&ui1);
+        }
+    )); // remove-spikes-toggled
+
+    // Redraw the graphs against distance, elapsed time or clock time.
+    ui1.x_axis_widget.connect_selected_notify(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            update_graph_views(&ui1);
+        }
+    )); // x-axis-selected
+
+    ui1.map_source_widget.connect_selected_notify(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            // Choosing a map source leaves the MBTiles file.
+            ui1.mbtiles_path.replace(String::new());
+            set_up_online_map_source(&ui1);
+        }
+    )); // map-source-selected
+
+    ui1.add_map_source_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.map_menu_btn.popdown();
+            show_map_source_window(&ui1);
+        }
+    )); // add-map-source-btn-clicked
+
+    ui1.remove_map_source_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            let id = selected_map_source_id(&ui1);
+            ui1.custom_map_sources
+                .borrow_mut()
+                .retain(|source| source.id != id);
+            populate_map_source_menu(&ui1, DEFAULT_MAP_SOURCE);
+            set_up_online_map_source(&ui1);
+        }
+    )); // remove-map-source-btn-clicked
+
+    ui1.track_color_widget.connect_selected_notify(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            let data = ui1.activity.borrow();
+            build_track_coloring(&data, &ui1, &instantiate_map_cache(&data));
+        }
+    )); // track-color-selected
+
+    ui1.distance_markers_check.connect_toggled(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            let data = ui1.activity.borrow();
+            build_lap_markers(&data, &ui1, &instantiate_map_cache(&data));
+        }
+    )); // distance-markers-toggled
+
+    ui1.offline_check.connect_toggled(clone!(
+        #[strong]
+        ui1,
+        move |check| {
+            if let Some(source) = ui1.tile_source.borrow().as_ref() {
+                source.set_offline(check.is_active());
+            }
+            update_map_menu(&ui1);
+        }
+    )); // offline-toggled
+
+    ui1.prefetch_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            prefetch_map_tiles(&ui1);
+        }
+    )); // prefetch-btn-clicked
+
+    ui1.mbtiles_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.map_menu_btn.popdown();
+            let native = FileChooserNative::new(
+                Some(&tr("MAP_OPEN_MBTILES_LABEL", None)),
+                Some(&ui1.win),
+                FileChooserAction::Open,
+                Some("Open"),
+                Some("Cancel"),
+            );
+            let filter = FileFilter::new();
+            filter.add_pattern("*.mbtiles");
+            native.add_filter(&filter);
+            native.connect_response(clone!(
+                #[strong]
+                ui1,
+                move |dialog, response| {
+                    if response == ResponseType::Accept
+                        && let Some(path) = dialog.file().and_then(|file| file.path())
+                    {
+                        ui1.mbtiles_path.replace(path.to_string_lossy().to_string());
+                        glib::MainContext::default().spawn_local(clone!(
+                            #[strong]
+                            ui1,
+                            async move {
+                                if let Err(e) = set_up_map_source(&ui1).await {
+                                    let mut args = FluentArgs::new();
+                                    args.set("error", e.to_string());
+                                    show_error_dialog(
+                                        &ui1.win,
+                                        tr("MESSAGE_NOT_AN_MBTILES_FILE", Some(&args)),
+                                    );
+                                }
+                            }
+                        ));
+                    }
+                }
+            ));
+            native.show();
+        }
+    )); // mbtiles-btn-clicked
+
+    ui1.online_map_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.map_menu_btn.popdown();
+            ui1.mbtiles_path.replace(String::new());
+            set_up_online_map_source(&ui1);
+        }
+    )); // online-map-btn-clicked
+
+    ui1.export_gpx_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.export_menu_btn.popdown();
+            export_with_dialog(
+                &ui1,
+                &tr("EXPORT_GPX_BUTTON_LABEL", None),
+                "activity.gpx",
+                fit_to_gpx,
+            );
+        }
+    )); // export-gpx-btn-clicked
+
+    ui1.export_csv_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.export_menu_btn.popdown();
+            // Convert with the unit system selected at the time of export.
+            let user_unit = get_unit_system(&ui1.units_widget);
+            export_with_dialog(
+                &ui1,
+                &tr("EXPORT_CSV_BUTTON_LABEL", None),
+                "activity.csv",
+                move |data| fit_to_csv(data, &user_unit),
+            );
+        }
+    )); // export-csv-btn-clicked
+
+    ui1.export_json_btn.connect_clicked(clone!(
+        #[strong]
+        ui1,
+        move |_| {
+            ui1.export_menu_btn.popdown();
+            let user_unit = get_unit_system(&ui1.units_widget);
+            let hr_zones = ui1.heart_rate_zones.borrow().clone();
+            export_with_dialog(
+                &ui1,
+                &tr("EXPORT_JSON_BUTTON_LABEL", None),
+                "activity.json",
+                move |data| fit_to_json(data, &user_unit, &hr_zones),
+            );
+        }
+    )); // export-json-btn-clicked
+
     ui1.about_btn.connect_clicked(clone!(
         #[strong]
         ui1,
@@ -319,6 +876,19 @@ fn build_gui(app: &Application, files: &[gtk4::gio::File], _: &str) {
                 right_frame_split: ui1.right_frame_pane.position(),
                 left_frame_split: ui1.left_frame_pane.position(),
                 units_index: ui1.units_widget.selected(),
+                x_axis_index: ui1.x_axis_widget.selected(),
+                graph_fields: ui1.graph_fields.borrow().clone(),
+                smoothing_index: ui1.smoothing_widget.selected(),
+                smoothing_window: ui1.smoothing_window_spin.value_as_int() as u32,
+                remove_spikes: ui1.remove_spikes_check.is_active(),
+                offline_map: ui1.offline_check.is_active(),
+                mbtiles_path: ui1.mbtiles_path.borrow().clone(),
+                map_source: selected_map_source_id(&ui1),
+                track_color_index: ui1.track_color_widget.selected(),
+                distance_markers: ui1.distance_markers_check.is_active(),
+                heart_rate_zones: ui1.heart_rate_zones.borrow().clone(),
+                pace_zones: ui1.pace_zones.borrow().clone(),
+                map_sources: ui1.custom_map_sources.borrow().clone(),
             };
             match save_config(&current_config, config_path) {
                 Ok(_) => glib::signal::Propagation::Proceed,
@@ -0,0 +1,793 @@
+// Module containing the text report of session, lap and heart rate zone values.
+// Nothing in here depends on GTK so the report may be produced headless.
+
+use crate::i18n::tr;
+use chrono::{DateTime, Local};
+use fitparser::{FitDataField, FitDataRecord, profile::field_types::MesgNum};
+use siliconsneaker2_data::decoupling::{EFFICIENCY_FACTOR_UNITS, decoupling};
+use siliconsneaker2_data::efforts::{BestEffort, best_efforts};
+use siliconsneaker2_data::gap::message_grade_adjusted_speed;
+use siliconsneaker2_data::library::{LibraryEntry, PersonalRecord, personal_records};
+use siliconsneaker2_data::splits::{Split, split_length, splits};
+use siliconsneaker2_data::track::TrackMetric;
+use siliconsneaker2_data::training_load::{hr_tss, trimp};
+use siliconsneaker2_data::zones::{
+    HeartRateZones, PACE_ZONE_NAMES, PaceZones, heart_rate_time_in_zone, heart_rate_zone_top,
+    pace_time_in_zone,
+};
+use siliconsneaker2_data::{
+    Units, cvt_altitude, cvt_distance, cvt_elapsed_time, cvt_pace, cvt_temperature, hr_zone_bounds,
+    semi_to_degrees,
+};
+
+// Return a language specific string for the field name identifier.
+fn pretty_field(fld: &FitDataField) -> String {
+    match fld.name() {
+        "start_position_lat" => return tr("PRETTY_START_POSITION_LAT", None),
+        "start_position_long" => return tr("PRETTY_START_POSITION_LONG", None),
+        "end_position_lat" => return tr("PRETTY_END_POSITION_LAT", None),
+        "end_position_long" => return tr("PRETTY_END_POSITION_LONG", None),
+        "total_strides" => return tr("PRETTY_TOTAL_STRIDES", None),
+        "total_calories" => return tr("PRETTY_TOTAL_CALORIES", None),
+        "avg_heart_rate" => return tr("PRETTY_AVG_HEART_RATE", None),
+        "max_heart_rate" => return tr("PRETTY_MAX_HEART_RATE", None),
+        "avg_running_cadence" => return tr("PRETTY_AVG_RUNNING_CADENCE", None),
+        "max_running_cadence" => return tr("PRETTY_MAX_RUNNING_CADENCE", None),
+        "total_training_effect" => return tr("PRETTY_TOTAL_TRAINING_EFFECT", None),
+        "first_lap_index" => return tr("PRETTY_FIRST_LAP_INDEX", None),
+        "num_laps" => return tr("PRETTY_NUM_LAPS", None),
+        "avg_fractional_cadence" => return tr("PRETTY_AVG_FRACTIONAL_CADENCE", None),
+        "max_fractional_cadence" => return tr("PRETTY_MAX_FRACTIONAL_CADENCE", None),
+        "total_anaerobic_training_effect" => {
+            return tr("PRETTY_TOTAL_ANAEROBIC_TRAINING_EFFECT", None);
+        }
+        "sport" => return tr("PRETTY_SPORT", None),
+        "sub_sport" => return tr("PRETTY_SUB_SPORT", None),
+        "timestamp" => return tr("PRETTY_TIMESTAMP", None),
+        "start_time" => return tr("PRETTY_START_TIME", None),
+        "total_ascent" => return tr("PRETTY_TOTAL_ASCENT", None),
+        "total_descent" => return tr("PRETTY_TOTAL_DESCENT", None),
+        "total_distance" => return tr("PRETTY_TOTAL_DISTANCE", None),
+        "total_elapsed_time" => return tr("PRETTY_TOTAL_ELAPSED_TIME", None),
+        "total_timer_time" => return tr("PRETTY_TOTAL_TIMER_TIME", None),
+        "enhanced_avg_speed" => return tr("PRETTY_ENHANCED_AVG_SPEED", None),
+        "enhanced_max_speed" => return tr("PRETTY_ENHANCED_MAX_SPEED", None),
+        "min_temperature" => return tr("PRETTY_MIN_TEMPERATURE", None),
+        "max_temperature" => return tr("PRETTY_MAX_TEMPERATURE", None),
+        "avg_temperature" => return tr("PRETTY_AVG_TEMPERATURE", None),
+        _ => return "".to_string(),
+    }
+}
+// Convert a value to user-defined units and return a formatted string when supplied a field and units.
+pub fn format_string_for_field(fld: &FitDataField, user_unit: &Units) -> Option<String> {
+    match fld.name() {
+        "start_position_lat" | "start_position_long" | "end_position_lat" | "end_position_long" => {
+            let result: Result<i64, _> = fld.value().try_into();
+            match result {
+                Ok(semi) => {
+                    let degrees = semi_to_degrees(semi as f64);
+                    return Some(format!("{:<30}: {degrees:<6.3}°\n", pretty_field(fld)));
+                }
+                Err(_) => return None,
+            }
+        }
+
+        "total_strides"
+        | "total_calories"
+        | "avg_heart_rate"
+        | "max_heart_rate"
+        | "avg_running_cadence"
+        | "max_running_cadence"
+        | "total_training_effect"
+        | "first_lap_index"
+        | "num_laps"
+        | "avg_fractional_cadence"
+        | "max_fractional_cadence"
+        | "total_anaerobic_training_effect"
+        | "sport"
+        | "sub_sport"
+        | "timestamp"
+        | "start_time" => {
+            return Some(format!(
+                "{:<30}: {:<#} {:<}\n",
+                pretty_field(fld),
+                fld.value(),
+                fld.units()
+            ));
+        }
+        "total_ascent" | "total_descent" => {
+            let result: Result<f64, _> = fld.value().clone().try_into();
+            match result {
+                Ok(val) => {
+                    let val_cvt = cvt_altitude(val as f32, &user_unit);
+                    match user_unit {
+                        Units::US => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                tr("UNIT_FEET", None),
+                            ));
+                        }
+                        Units::Metric => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                tr("UNIT_METERS", None),
+                            ));
+                        }
+                        Units::None => {
+                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
+                        }
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+        "total_distance" => {
+            let result: Result<f64, _> = fld.value().clone().try_into();
+            match result {
+                Ok(val) => {
+                    let val_cvt = cvt_distance(val as f32, &user_unit);
+                    match user_unit {
+                        Units::US => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                tr("UNIT_MILES", None),
+                            ));
+                        }
+                        Units::Metric => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                tr("UNIT_KM", None),
+                            ));
+                        }
+                        Units::None => {
+                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
+                        }
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+        "total_elapsed_time" | "total_timer_time" => {
+            let result: Result<f64, _> = fld.value().clone().try_into();
+            match result {
+                Ok(val) => {
+                    let val_cvt = cvt_elapsed_time(val as f32);
+                    return Some(format!(
+                        "{:<30}: {:01}h:{:02}m:{:02}s\n",
+                        pretty_field(fld),
+                        val_cvt.0,
+                        val_cvt.1,
+                        val_cvt.2
+                    ));
+                }
+                Err(_) => return None,
+            }
+        }
+        "min_temperature" | "max_temperature" | "avg_temperature" => {
+            let result: Result<i64, _> = fld.value().clone().try_into();
+            match result {
+                Ok(val) => {
+                    let val_cvt = cvt_temperature(val as f32, &user_unit);
+                    match user_unit {
+                        Units::US => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                "°F"
+                            ));
+                        }
+                        Units::Metric => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                "°C"
+                            ));
+                        }
+                        Units::None => {
+                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
+                        }
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+        "enhanced_avg_speed" | "enhanced_max_speed" => {
+            let result: Result<f64, _> = fld.value().clone().try_into();
+            match result {
+                Ok(val) => {
+                    let val_cvt = cvt_pace(val as f32, &user_unit);
+                    match user_unit {
+                        Units::US => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                tr("UNIT_PACE_US", None),
+                            ));
+                        }
+                        Units::Metric => {
+                            return Some(format!(
+                                "{:<30}: {:<.2} {:<}\n",
+                                pretty_field(fld),
+                                val_cvt,
+                                tr("UNIT_PACE_METRIC", None),
+                            ));
+                        }
+                        Units::None => {
+                            return Some(format!("{:<30}: {:<.2} {:<}\n", fld.name(), val_cvt, ""));
+                        }
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+        _ => return None, // matches other patterns
+    }
+}
+
+// Return the report line of a grade adjusted average speed, shown as a pace.
+fn format_grade_adjusted_pace(speed: f64, user_unit: &Units) -> String {
+    let label = tr("PRETTY_AVG_GRADE_ADJUSTED_PACE", None);
+    let val_cvt = cvt_pace(speed as f32, &user_unit);
+    match user_unit {
+        Units::US => {
+            return format!(
+                "{:<30}: {:<.2} {:<}\n",
+                label,
+                val_cvt,
+                tr("UNIT_PACE_US", None),
+            );
+        }
+        Units::Metric => {
+            return format!(
+                "{:<30}: {:<.2} {:<}\n",
+                label,
+                val_cvt,
+                tr("UNIT_PACE_METRIC", None),
+            );
+        }
+        Units::None => {
+            return format!(
+                "{:<30}: {:<.2} {:<}\n",
+                "avg_grade_adjusted_speed", val_cvt, ""
+            );
+        }
+    }
+}
+
+// Return a language specific name for a best effort distance.
+pub fn best_effort_label(name: &str) -> String {
+    match name {
+        "400m" => return tr("BEST_EFFORT_400M", None),
+        "1k" => return tr("BEST_EFFORT_1K", None),
+        "mile" => return tr("BEST_EFFORT_MILE", None),
+        "5k" => return tr("BEST_EFFORT_5K", None),
+        "10k" => return tr("BEST_EFFORT_10K", None),
+        "half_marathon" => return tr("BEST_EFFORT_HALF_MARATHON", None),
+        "marathon" => return tr("BEST_EFFORT_MARATHON", None),
+        _ => return name.to_string(),
+    }
+}
+
+// Return the time and pace of a best effort, e.g. "0h:21m:35s  4.32 min/km".
+pub fn format_best_effort(effort: &BestEffort, user_unit: &Units) -> String {
+    let (hr, min, sec) = cvt_elapsed_time(effort.time as f32);
+    let pace = cvt_pace(effort.avg_speed() as f32, &user_unit);
+    let pace_unit = match user_unit {
+        Units::US => tr("UNIT_PACE_US", None),
+        Units::Metric => tr("UNIT_PACE_METRIC", None),
+        Units::None => "".to_string(),
+    };
+    return format!(
+        "{:01}h:{:02}m:{:02}s  {:<.2} {:<}",
+        hr, min, sec, pace, pace_unit
+    );
+}
+
+// Build the personal records table, the fastest library activity over each
+// best effort distance.
+pub fn build_records_table(records: &Vec<PersonalRecord>, user_unit: &Units) -> String {
+    let mut table = String::new();
+    for record in records {
+        let date = DateTime::from_timestamp(record.entry.start_time, 0)
+            .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        table.push_str(&format!(
+            "{:<20}: {:<32} {:<12} {}\n",
+            best_effort_label(&record.effort.name),
+            format_best_effort(record.effort, user_unit),
+            date,
+            record.entry.path
+        ));
+    }
+    return table;
+}
+
+// Build the personal records report of the library entries.  Records of
+// different sports are not comparable, so without a sport a table is built
+// for each sport in the library.
+pub fn build_records_report(
+    entries: &[LibraryEntry],
+    sport: Option<&str>,
+    user_unit: &Units,
+) -> String {
+    let mut sports: Vec<&str> = match sport {
+        Some(sport) => vec![sport],
+        None => entries.iter().map(|e| e.sport.as_str()).collect(),
+    };
+    sports.sort();
+    sports.dedup();
+    let mut report = String::new();
+    for sport in sports {
+        let records = personal_records(entries, Some(sport));
+        if records.len() == 0 {
+            continue;
+        }
+        report.push_str(&format!("{}\n", sport));
+        report.push_str(&build_records_table(&records, user_unit));
+        report.push_str("\n");
+    }
+    if report.is_empty() {
+        report = format!("{}\n", tr("RECORDS_NONE", None));
+    }
+    return report;
+}
+
+// Format an optional average as a whole number, or "-" when not recorded.
+fn format_optional(value: Option<f64>) -> String {
+    match value {
+        Some(v) => return format!("{:.0}", v),
+        None => return String::from("-"),
+    }
+}
+
+// Build the splits table, one row per kilometre or mile.
+pub fn build_splits_table(splits: &Vec<Split>, user_unit: &Units) -> String {
+    let (distance_unit, altitude_unit, pace_unit) = match user_unit {
+        Units::US => ("UNIT_MILES", "UNIT_FEET", "UNIT_PACE_US"),
+        _ => ("UNIT_KM", "UNIT_METERS", "UNIT_PACE_METRIC"),
+    };
+    let mut table = format!(
+        "{:<6} {:>9} {:>9} {:>11} {:>9} {:>9} {:>9} {:>9}\n",
+        tr("SPLITS_COLUMN_SPLIT", None),
+        tr("SPLITS_COLUMN_DISTANCE", None),
+        tr("SPLITS_COLUMN_TIME", None),
+        tr("SPLITS_COLUMN_PACE", None),
+        tr("SPLITS_COLUMN_HEART_RATE", None),
+        tr("SPLITS_COLUMN_CADENCE", None),
+        tr("SPLITS_COLUMN_ASCENT", None),
+        tr("SPLITS_COLUMN_DESCENT", None),
+    );
+    table.push_str(&format!(
+        "{:<6} {:>9} {:>9} {:>11} {:>9} {:>9} {:>9} {:>9}\n",
+        "",
+        tr(distance_unit, None),
+        "h:mm:ss",
+        tr(pace_unit, None),
+        "bpm",
+        "rpm",
+        tr(altitude_unit, None),
+        tr(altitude_unit, None),
+    ));
+    for (i, split) in splits.iter().enumerate() {
+        let (hr, min, sec) = cvt_elapsed_time(split.time as f32);
+        table.push_str(&format!(
+            "{:<6} {:>9.2} {:>9} {:>11.2} {:>9} {:>9} {:>9.0} {:>9.0}\n",
+            i + 1,
+            cvt_distance(split.distance as f32, &user_unit),
+            format!("{}:{:02}:{:02}", hr, min, sec),
+            cvt_pace(split.avg_speed() as f32, &user_unit),
+            format_optional(split.avg_heart_rate),
+            format_optional(split.avg_cadence),
+            cvt_altitude(split.ascent as f32, &user_unit),
+            cvt_altitude(split.descent as f32, &user_unit),
+        ));
+    }
+    return table;
+}
+
+// Return a language specific name for a pace zone, see PACE_ZONE_NAMES.
+fn pace_zone_label(name: &str) -> String {
+    match name {
+        "slower" => return tr("PACE_ZONE_SLOWER", None),
+        "easy" => return tr("PACE_ZONE_EASY", None),
+        "marathon" => return tr("PACE_ZONE_MARATHON", None),
+        "threshold" => return tr("PACE_ZONE_THRESHOLD", None),
+        "interval" => return tr("PACE_ZONE_INTERVAL", None),
+        "repetition" => return tr("PACE_ZONE_REPETITION", None),
+        _ => return name.to_string(),
+    }
+}
+
+// Format a speed (m/s) as a pace in minutes:seconds.
+fn format_pace_minutes(speed: f64, user_unit: &Units) -> String {
+    let pace = cvt_pace(speed as f32, &user_unit);
+    let (min, sec) = (pace.trunc(), (pace.fract() * 60.0).round());
+    if sec >= 60.0 {
+        return format!("{}:00", min + 1.0);
+    }
+    return format!("{}:{:02}", min, sec);
+}
+
+// Build the time in pace zone table, slowest zone first.
+pub fn build_pace_zone_table(
+    zone_times: &Vec<f64>,
+    zone_limits: &Vec<f64>,
+    user_unit: &Units,
+) -> String {
+    let pace_unit = match user_unit {
+        Units::US => tr("UNIT_PACE_US", None),
+        Units::Metric => tr("UNIT_PACE_METRIC", None),
+        Units::None => "".to_string(),
+    };
+    let mut table = String::new();
+    for (z, val) in zone_times.iter().enumerate() {
+        let (hr, min, sec) = cvt_elapsed_time(*val as f32);
+        // The slowest zone has no lower speed and the fastest no upper speed.
+        let slowest = match z {
+            0 => String::new(),
+            _ => format_pace_minutes(zone_limits[z - 1], user_unit),
+        };
+        let fastest = match zone_limits.get(z) {
+            Some(limit) => format_pace_minutes(*limit, user_unit),
+            None => String::new(),
+        };
+        table.push_str(&format!(
+            "{:<12} ({:>5}-{:<5} {}): {:01}h:{:02}m:{:02}s\n",
+            pace_zone_label(PACE_ZONE_NAMES.get(z).unwrap_or(&"")),
+            slowest,
+            fastest,
+            pace_unit,
+            hr,
+            min,
+            sec
+        ));
+    }
+    return table;
+}
+
+// Return the legend line of a class of the colored map track.  Values
+// beyond the outer classes fall in them, so their ranges are open.
+pub fn track_legend_label(
+    metric: TrackMetric,
+    bounds: &Vec<(f64, f64)>,
+    class: usize,
+    user_unit: &Units,
+) -> String {
+    let (low, high) = bounds[class];
+    let last = bounds.len() - 1;
+    if metric == TrackMetric::HeartRateZone {
+        return format!(
+            "{} {} ({}-{} bpm)",
+            tr("SUMMARY_HR_ZONE_LABEL", None),
+            class,
+            low as i32,
+            high as i32
+        );
+    }
+    let (from, to, unit) = match metric {
+        // Pace runs the other way, the fastest class has the lowest pace.
+        TrackMetric::Pace => {
+            let unit = match user_unit {
+                Units::US => tr("UNIT_PACE_US", None),
+                _ => tr("UNIT_PACE_METRIC", None),
+            };
+            let fast = format_pace_minutes(high, user_unit);
+            let slow = format_pace_minutes(low, user_unit);
+            if bounds.len() > 1 && class == 0 {
+                return format!("> {} {}", fast, unit);
+            }
+            if bounds.len() > 1 && class == last {
+                return format!("< {} {}", slow, unit);
+            }
+            (fast, slow, unit)
+        }
+        TrackMetric::Elevation => {
+            let unit = match user_unit {
+                Units::US => tr("UNIT_FEET", None),
+                _ => tr("UNIT_METERS", None),
+            };
+            (
+                format!("{:.0}", cvt_altitude(low as f32, user_unit)),
+                format!("{:.0}", cvt_altitude(high as f32, user_unit)),
+                unit,
+            )
+        }
+        _ => (
+            format!("{:.1}", low * 100.0),
+            format!("{:.1}", high * 100.0),
+            "%".to_string(),
+        ),
+    };
+    if bounds.len() == 1 {
+        return format!("{} {}", from, unit);
+    }
+    if class == 0 {
+        return format!("< {} {}", to, unit);
+    }
+    if class == last {
+        return format!("> {} {}", from, unit);
+    }
+    return format!("{}-{} {}", from, to, unit);
+}
+
+// Return the line heading the block of a lap in the report, laps counting
+// from 1.
+pub fn lap_header(lap_index: usize) -> String {
+    return format!(
+        "------------------------------ {} {}-----------------------------------\n",
+        tr("SUMMARY_LAP_HEADER", None),
+        lap_index
+    );
+}
+
+// Build the session, lap and heart rate zone report as text.
+pub fn build_summary_text(
+    data: &Vec<FitDataRecord>,
+    user_unit: &Units,
+    hr_zones: &HeartRateZones,
+    pace_zones: &PaceZones,
+) -> String {
+    let mut summary = String::new();
+    let mut lap_index: usize = 0;
+    for item in data {
+        match item.kind() {
+            MesgNum::Session | MesgNum::Lap => {
+                // print all the data records in FIT file
+                if item.kind() == MesgNum::Session {
+                    summary.push_str("\n");
+                    summary.push_str(&tr("SUMMARY_SESSION_HEADER", None));
+                    summary.push_str("\n");
+                }
+                if item.kind() == MesgNum::Lap {
+                    lap_index = lap_index + 1;
+                    summary.push_str("\n");
+                    summary.push_str(&lap_header(lap_index));
+                    summary.push_str("\n");
+                }
+                // Retrieve the FitDataField struct.
+                for fld in item.fields().iter() {
+                    if let Some(value_str) = format_string_for_field(fld, &user_unit) {
+                        summary.push_str(&value_str);
+                    }
+                }
+                if let Some(speed) = message_grade_adjusted_speed(data, item) {
+                    summary.push_str(&format_grade_adjusted_pace(speed, &user_unit));
+                }
+                if item.kind() == MesgNum::Session
+                    && let Some(impulse) = trimp(data, hr_zones)
+                {
+                    summary.push_str(&format!(
+                        "{:<30}: {:.0}\n",
+                        tr("PRETTY_TRIMP", None),
+                        impulse
+                    ));
+                    summary.push_str(&format!(
+                        "{:<30}: {:.0}\n",
+                        tr("PRETTY_HR_TSS", None),
+                        hr_tss(impulse, hr_zones)
+                    ));
+                }
+            }
+            _ => (), // matches other patterns
+        }
+    }
+    let activity_splits = splits(data, split_length(&user_unit));
+    if activity_splits.len() > 0 {
+        summary.push_str("\n");
+        summary.push_str(&tr("SUMMARY_SPLITS_HEADER", None));
+        summary.push_str("\n");
+        summary.push_str(&build_splits_table(&activity_splits, &user_unit));
+    }
+    let efforts = best_efforts(data);
+    if efforts.len() > 0 {
+        summary.push_str("\n");
+        summary.push_str(&tr("SUMMARY_BEST_EFFORTS_HEADER", None));
+        summary.push_str("\n");
+        for effort in efforts.iter() {
+            summary.push_str(&format!(
+                "{:<30}: {}\n",
+                best_effort_label(&effort.name),
+                format_best_effort(effort, &user_unit)
+            ));
+        }
+    }
+    if let Some(drift) = decoupling(data) {
+        summary.push_str("\n");
+        summary.push_str(&tr("SUMMARY_DECOUPLING_HEADER", None));
+        summary.push_str("\n");
+        summary.push_str(&format!(
+            "{:<30}: {:.2} {}\n",
+            tr("DECOUPLING_FIRST_HALF", None),
+            drift.first_half,
+            EFFICIENCY_FACTOR_UNITS
+        ));
+        summary.push_str(&format!(
+            "{:<30}: {:.2} {}\n",
+            tr("DECOUPLING_SECOND_HALF", None),
+            drift.second_half,
+            EFFICIENCY_FACTOR_UNITS
+        ));
+        summary.push_str(&format!(
+            "{:<30}: {:.1} %\n",
+            tr("DECOUPLING_PERCENT", None),
+            drift.percent()
+        ));
+    }
+    if let Some((zone_times, zone_limits)) = heart_rate_time_in_zone(data, hr_zones) {
+        // There is one more zone than there are upper limits.
+        let top_heart_rate = heart_rate_zone_top(data, hr_zones);
+        summary.push_str("\n");
+        summary.push_str(&tr("SUMMARY_HR_ZONE_HEADER", None));
+        summary.push_str("\n");
+        for (z, val) in zone_times.iter().enumerate() {
+            let val_cvt = cvt_elapsed_time(*val as f32);
+            let (ll, ul) = hr_zone_bounds(&zone_limits, z, top_heart_rate);
+            let value_str = format!(
+                "{:<5}{:<} ({:>3}-{:>3} bpm): {:01}h:{:02}m:{:02}s\n",
+                tr("SUMMARY_HR_ZONE_LABEL", None),
+                z,
+                ll as i32,
+                ul as i32,
+                val_cvt.0,
+                val_cvt.1,
+                val_cvt.2
+            );
+            summary.push_str(&value_str);
+        }
+        summary.push_str("\n");
+    };
+    let pace_limits = pace_zones.zone_limits();
+    if let Some(zone_times) = pace_time_in_zone(data, &pace_limits) {
+        // The heart rate zone table already ends with a blank line.
+        if !summary.ends_with("\n\n") {
+            summary.push_str("\n");
+        }
+        summary.push_str(&tr("SUMMARY_PACE_ZONE_HEADER", None));
+        summary.push_str("\n");
+        summary.push_str(&build_pace_zone_table(
+            &zone_times,
+            &pace_limits,
+            &user_unit,
+        ));
+    }
+    return summary;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fitparser::Value;
+
+    // Return a session field as fitparser decodes it.
+    fn field(name: &str, value: Value, units: &str) -> FitDataField {
+        return FitDataField::new(name.to_string(), 0, None, value, units.to_string());
+    }
+
+    #[test]
+    fn distance_is_given_in_the_unit_system() {
+        let distance = field("total_distance", Value::Float64(5000.0), "m");
+        let metric = format_string_for_field(&distance, &Units::Metric).unwrap();
+        assert!(metric.contains("5.00"));
+        assert!(metric.contains(&tr("UNIT_KM", None)));
+        let us = format_string_for_field(&distance, &Units::US).unwrap();
+        assert!(us.contains("3.11"));
+        assert!(us.contains(&tr("UNIT_MILES", None)));
+    }
+
+    #[test]
+    fn times_positions_and_temperatures_are_formatted() {
+        let time = field("total_timer_time", Value::Float64(5400.0), "s");
+        assert!(
+            format_string_for_field(&time, &Units::Metric)
+                .unwrap()
+                .contains("1h:30m:00s")
+        );
+        let latitude = field("start_position_lat", Value::SInt32(1 << 30), "semicircles");
+        assert!(
+            format_string_for_field(&latitude, &Units::Metric)
+                .unwrap()
+                .contains("90.000°")
+        );
+        let temperature = field("max_temperature", Value::SInt8(20), "C");
+        assert!(
+            format_string_for_field(&temperature, &Units::US)
+                .unwrap()
+                .contains("68.00 °F")
+        );
+    }
+
+    #[test]
+    fn other_fields_are_left_out() {
+        let field = field("unknown_field", Value::UInt8(1), "");
+        assert!(format_string_for_field(&field, &Units::Metric).is_none());
+    }
+
+    #[test]
+    fn report_has_the_session_every_lap_and_the_zones() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/runs/half_marathon.fit");
+        let data = fitparser::from_bytes(&std::fs::read(path).unwrap()).unwrap();
+        let laps = data
+            .iter()
+            .filter(|item| item.kind() == MesgNum::Lap)
+            .count();
+        let summary = build_summary_text(
+            &data,
+            &Units::Metric,
+            &HeartRateZones::default(),
+            &PaceZones::default(),
+        );
+        assert_eq!(
+            summary.matches(&tr("SUMMARY_SESSION_HEADER", None)).count(),
+            1
+        );
+        let lap_header = |n: usize| format!("{} {}-", tr("SUMMARY_LAP_HEADER", None), n);
+        assert!(laps > 0);
+        assert!(summary.contains(&lap_header(laps)));
+        assert!(!summary.contains(&lap_header(laps + 1)));
+        assert_eq!(summary.matches(&tr("PRETTY_TRIMP", None)).count(), 1);
+        assert!(summary.contains(&tr("SUMMARY_DECOUPLING_HEADER", None)));
+        assert!(summary.contains(&tr("SUMMARY_HR_ZONE_HEADER", None)));
+        assert!(summary.contains(&tr("SUMMARY_PACE_ZONE_HEADER", None)));
+    }
+
+    // Return a library entry of a sport with a single best effort.
+    fn entry_with_effort(sport: &str, name: &str, distance: f64, time: f64) -> LibraryEntry {
+        return LibraryEntry {
+            path: format!("{}.fit", sport),
+            start_time: 1_714_550_400,
+            sport: sport.to_string(),
+            best_efforts: vec![BestEffort {
+                name: name.to_string(),
+                distance,
+                time,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn best_effort_shows_time_and_pace() {
+        let effort = BestEffort {
+            name: String::from("5k"),
+            distance: 5000.0,
+            time: 1500.0,
+            ..Default::default()
+        };
+        let text = format_best_effort(&effort, &Units::Metric);
+        assert!(text.starts_with("0h:25m:00s  5.00 "));
+        assert!(text.ends_with(&tr("UNIT_PACE_METRIC", None)));
+        assert_eq!(best_effort_label("unknown"), "unknown");
+    }
+
+    #[test]
+    fn records_report_has_a_table_per_sport() {
+        assert_eq!(
+            build_records_report(&[], None, &Units::Metric),
+            format!("{}\n", tr("RECORDS_NONE", None))
+        );
+        let entries = vec![
+            entry_with_effort("running", "5k", 5000.0, 1500.0),
+            entry_with_effort("cycling", "10k", 10000.0, 1200.0),
+        ];
+        let report = build_records_report(&entries, None, &Units::Metric);
+        let cycling = report.find("cycling\n").unwrap();
+        let running = report.find("running\n").unwrap();
+        assert!(cycling < running);
+        assert!(report.contains("running.fit"));
+        assert!(report.contains(&best_effort_label("10k")));
+        let report = build_records_report(&entries, Some("running"), &Units::Metric);
+        assert!(!report.contains("cycling"));
+    }
+}
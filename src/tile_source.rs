@@ -0,0 +1,345 @@
+// Map tile source keeping the downloaded tiles on disk, so that maps already
+// seen, or fetched ahead of time, can be shown without a network.  It can also
+// serve the tiles of a local MBTiles file.
+
+use crate::config::CustomMapSource;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::glib::subclass::prelude::*;
+use libshumate::prelude::*;
+use libshumate::subclass::prelude::*;
+use libshumate::{
+    DataSource, MapProjection, MapSource, MapSourceRegistry, RasterRenderer, TileDownloader,
+};
+use siliconsneaker2_data::mbtiles::MbTiles;
+use siliconsneaker2_data::tiles::{Bounds, cached_tile_path, tiles_for_bounds};
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Age after which a cached tile is downloaded again when online.
+const TILE_MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+// Zoom levels downloaded ahead of time, from the town to the street, and the
+// most tiles downloaded at once, to go easy on the tile servers.
+const PREFETCH_MIN_ZOOM: u32 = 10;
+const PREFETCH_MAX_ZOOM: u32 = 17;
+const PREFETCH_MAX_TILES: usize = 2000;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct TileSource {
+        pub(super) source_id: RefCell<String>,
+        pub(super) cache_dir: RefCell<PathBuf>,
+        pub(super) downloader: RefCell<Option<TileDownloader>>,
+        pub(super) mbtiles: RefCell<Option<Arc<MbTiles>>>,
+        pub(super) offline: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TileSource {
+        const NAME: &'static str = "SiliconSneaker2TileSource";
+        type Type = super::TileSource;
+        type ParentType = DataSource;
+    }
+
+    impl ObjectImpl for TileSource {}
+
+    impl DataSourceImpl for TileSource {
+        fn tile_data_future(
+            &self,
+            x: i32,
+            y: i32,
+            zoom_level: i32,
+        ) -> Pin<Box<dyn Future<Output = Result<glib::Bytes, glib::Error>> + 'static>> {
+            let source = self.obj().clone();
+            return Box::pin(async move {
+                return source
+                    .tile(zoom_level as u32, x as u32, y as u32)
+                    .await
+                    .map(glib::Bytes::from_owned);
+            });
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct TileSource(ObjectSubclass<imp::TileSource>) @extends DataSource;
+}
+
+// Return the error of a tile that can not be had.
+fn tile_not_found(zoom: u32, x: u32, y: u32) -> glib::Error {
+    return glib::Error::new(
+        gio::IOErrorEnum::NotFound,
+        &format!("Tile {}/{}/{} is not available.", zoom, x, y),
+    );
+}
+
+// Create a folder and the folders above it that do not exist yet.
+async fn make_directories(dir: &gio::File) -> Result<(), glib::Error> {
+    let mut missing: Vec<gio::File> = Vec::new();
+    let mut next = Some(dir.clone());
+    while let Some(dir) = next {
+        match dir.make_directory_future(glib::Priority::DEFAULT).await {
+            Ok(()) => break,
+            Err(e) if e.matches(gio::IOErrorEnum::Exists) => break,
+            Err(e) if e.matches(gio::IOErrorEnum::NotFound) => {
+                next = dir.parent();
+                missing.push(dir);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    for dir in missing.iter().rev() {
+        match dir.make_directory_future(glib::Priority::DEFAULT).await {
+            Err(e) if !e.matches(gio::IOErrorEnum::Exists) => return Err(e),
+            _ => {}
+        }
+    }
+    return Ok(());
+}
+
+impl TileSource {
+    // Create a source downloading the tiles of a URL template, such as
+    // https://tile.openstreetmap.org/{z}/{x}/{y}.png, and caching them below
+    // cache_dir in a folder named after the source.
+    pub fn from_url(source_id: &str, url_template: &str, cache_dir: PathBuf) -> TileSource {
+        let source: TileSource = glib::Object::new();
+        source.imp().source_id.replace(String::from(source_id));
+        source.imp().cache_dir.replace(cache_dir);
+        source
+            .imp()
+            .downloader
+            .replace(Some(TileDownloader::new(url_template)));
+        return source;
+    }
+
+    // Create a source serving the tiles of an MBTiles file.
+    pub fn from_mbtiles(mbtiles: Arc<MbTiles>) -> TileSource {
+        let source: TileSource = glib::Object::new();
+        source.imp().mbtiles.replace(Some(mbtiles));
+        return source;
+    }
+
+    // Serve only the cached tiles when offline.
+    pub fn set_offline(&self, offline: bool) {
+        self.imp().offline.set(offline);
+    }
+
+    // Return the file a tile is, or would be, cached in.
+    fn cache_path(&self, zoom: u32, x: u32, y: u32) -> PathBuf {
+        return cached_tile_path(
+            &self.imp().cache_dir.borrow(),
+            &self.imp().source_id.borrow(),
+            zoom,
+            x,
+            y,
+        );
+    }
+
+    // Return whether a tile is cached and recent enough not to be downloaded
+    // again.
+    pub async fn is_cached(&self, zoom: u32, x: u32, y: u32) -> bool {
+        if self.imp().mbtiles.borrow().is_some() {
+            return true;
+        }
+        let file = gio::File::for_path(self.cache_path(zoom, x, y));
+        let age = file
+            .query_info_future(
+                gio::FILE_ATTRIBUTE_TIME_MODIFIED,
+                gio::FileQueryInfoFlags::NONE,
+                glib::Priority::DEFAULT,
+            )
+            .await
+            .ok()
+            .and_then(|info| info.modification_date_time())
+            .and_then(|modified| Some(glib::DateTime::now_utc().ok()?.difference(&modified)));
+        return age.is_some_and(|age| {
+            return age.as_seconds() >= 0 && (age.as_seconds() as u64) < TILE_MAX_AGE.as_secs();
+        });
+    }
+
+    // Return the image of a tile.  Cached tiles are used when recent, or
+    // whatever their age when offline or the download fails.
+    pub async fn tile(&self, zoom: u32, x: u32, y: u32) -> Result<Vec<u8>, glib::Error> {
+        if let Some(mbtiles) = self.imp().mbtiles.borrow().clone() {
+            return mbtiles
+                .tile(zoom, x, y)
+                .ok_or_else(|| tile_not_found(zoom, x, y));
+        }
+        let file = gio::File::for_path(self.cache_path(zoom, x, y));
+        let cached = file
+            .load_contents_future()
+            .await
+            .ok()
+            .map(|(image, _)| image.to_vec());
+        if let Some(image) = &cached
+            && (self.imp().offline.get() || self.is_cached(zoom, x, y).await)
+        {
+            return Ok(image.clone());
+        }
+        if self.imp().offline.get() {
+            return Err(tile_not_found(zoom, x, y));
+        }
+        let Some(downloader) = self.imp().downloader.borrow().clone() else {
+            return Err(tile_not_found(zoom, x, y));
+        };
+        match downloader
+            .tile_data_future(x as i32, y as i32, zoom as i32)
+            .await
+        {
+            Ok(Some(bytes)) => {
+                // A tile that can not be cached is still shown.
+                if let Some(parent) = file.parent()
+                    && make_directories(&parent).await.is_ok()
+                {
+                    let _ = file
+                        .replace_contents_future(
+                            bytes.clone(),
+                            None,
+                            false,
+                            gio::FileCreateFlags::REPLACE_DESTINATION,
+                        )
+                        .await;
+                }
+                return Ok(bytes.to_vec());
+            }
+            Ok(None) => return cached.ok_or_else(|| tile_not_found(zoom, x, y)),
+            Err(e) => return cached.ok_or(e),
+        }
+    }
+
+    // Download the tiles covering an extent that are not cached yet.
+    // progress is called with the number of tiles done and the total after
+    // each tile.  Returns the number of tiles that could not be downloaded
+    // and the total.
+    pub async fn prefetch(
+        &self,
+        bounds: &Bounds,
+        progress: impl Fn(usize, usize),
+    ) -> (usize, usize) {
+        let tiles = tiles_for_bounds(
+            bounds,
+            PREFETCH_MIN_ZOOM,
+            PREFETCH_MAX_ZOOM,
+            PREFETCH_MAX_TILES,
+        );
+        let mut failed = 0;
+        for (done, (zoom, x, y)) in tiles.iter().enumerate() {
+            if !self.is_cached(*zoom, *x, *y).await && self.tile(*zoom, *x, *y).await.is_err() {
+                failed += 1;
+            }
+            progress(done + 1, tiles.len());
+        }
+        return (failed, tiles.len());
+    }
+}
+
+// Return a map source drawing the tiles of a tile source, taking its name,
+// licence and zoom levels from another map source.
+pub fn cached_map_source(like: &MapSource, source: &TileSource) -> MapSource {
+    let renderer = RasterRenderer::new_full(
+        &like.id().unwrap_or_default(),
+        &like.name().unwrap_or_default(),
+        &like.license().unwrap_or_default(),
+        &like.license_uri().unwrap_or_default(),
+        like.min_zoom_level(),
+        like.max_zoom_level(),
+        like.tile_size(),
+        like.projection(),
+        source,
+    );
+    return renderer.upcast();
+}
+
+// Return the URL template of a map source drawing downloaded tiles.
+pub fn url_template(map_source: &MapSource) -> Option<String> {
+    let renderer = map_source.downcast_ref::<RasterRenderer>()?;
+    let downloader = renderer.data_source()?.downcast::<TileDownloader>().ok()?;
+    return downloader.url_template().map(|u| u.to_string());
+}
+
+// Return a map source drawing the tiles of an MBTiles file.
+pub fn mbtiles_map_source(mbtiles: Arc<MbTiles>) -> MapSource {
+    let (min_zoom, max_zoom) = mbtiles.zoom_range();
+    let name = mbtiles.metadata("name").unwrap_or("MBTiles").to_string();
+    let license = mbtiles.metadata("attribution").unwrap_or("").to_string();
+    let source = TileSource::from_mbtiles(mbtiles);
+    let renderer = RasterRenderer::new_full(
+        "mbtiles",
+        &name,
+        &license,
+        "",
+        min_zoom,
+        max_zoom,
+        256,
+        MapProjection::Mercator,
+        &source,
+    );
+    return renderer.upcast();
+}
+
+// Return the registry of the map sources libshumate knows about followed by
+// those added by the user.
+pub fn map_source_registry(custom: &[CustomMapSource]) -> MapSourceRegistry {
+    let registry = MapSourceRegistry::with_defaults();
+    for source in custom.iter() {
+        let renderer = RasterRenderer::new_full_from_url(
+            &source.id,
+            &source.name,
+            &source.attribution,
+            "",
+            source.min_zoom,
+            source.max_zoom,
+            256,
+            MapProjection::Mercator,
+            &source.url_template,
+        );
+        registry.add(renderer.upcast::<MapSource>());
+    }
+    return registry;
+}
+
+// Return whether a URL template names the zoom level, column and row of
+// the tiles.
+pub fn is_url_template(url_template: &str) -> bool {
+    return ["{z}", "{x}", "{y}"]
+        .iter()
+        .all(|field| url_template.contains(field));
+}
+
+// Return the 32 bit FNV-1a hash of a text.
+fn fnv1a_hash(text: &str) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in text.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    return hash;
+}
+
+// Return an id for a map source added by the user that is not taken yet,
+// made from its name and a hash of its URL template, e.g.
+// custom-local-server-1a2b3c4d.  The tiles are cached by id, so a source
+// removed and added again with another URL does not show the old tiles.
+pub fn new_map_source_id(name: &str, url_template: &str, taken: &[String]) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug: Vec<&str> = slug.split('-').filter(|part| !part.is_empty()).collect();
+    let base = format!("custom-{}-{:08x}", slug.join("-"), fnv1a_hash(url_template));
+    let mut id = base.clone();
+    let mut count = 1;
+    while taken.contains(&id) {
+        count += 1;
+        id = format!("{}-{}", base, count);
+    }
+    return id;
+}